@@ -1,13 +1,25 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use std::env::var;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Static assets served with a gzipped copy (see `routes::Asset`).
+const ASSETS: &[&str] = &[
+    "brick.svg",
+    "node_modules/tablesort/dist/tablesort.min.js",
+    "node_modules/tablesort/dist/sorts/tablesort.number.min.js",
+];
+
 fn main() {
-    let out = PathBuf::from(var("OUT_DIR").unwrap()).join("styles.css");
+    let out_dir = PathBuf::from(var("OUT_DIR").unwrap());
+    let out = out_dir.join("styles.css");
     println!("cargo:rerun-if-env-changed=COMPILED_CSS");
     if let Ok(path) = var("COMPILED_CSS") {
         println!("cargo:rerun-if-changed={}", path);
-        std::fs::copy(path, out).unwrap();
+        std::fs::copy(path, &out).unwrap();
     } else {
         println!("cargo:rerun-if-changed=package-lock.json");
         println!("cargo:rerun-if-changed=postcss.config.js");
@@ -23,7 +35,7 @@ fn main() {
             .arg("postcss")
             .arg("styles.css")
             .arg("-o")
-            .arg(out)
+            .arg(&out)
             .status()
             .unwrap()
             .success()
@@ -31,4 +43,22 @@ fn main() {
             panic!("postcss failed");
         }
     }
+
+    gzip(&out, &out_dir);
+    for asset in ASSETS {
+        println!("cargo:rerun-if-changed={}", asset);
+        gzip(Path::new(asset), &out_dir);
+    }
+}
+
+/// Writes a gzipped copy of the file to the output directory, named after the file.
+fn gzip(path: &Path, out_dir: &Path) {
+    let mut name = path.file_name().unwrap().to_owned();
+    name.push(".gz");
+    let mut encoder = GzEncoder::new(
+        File::create(out_dir.join(name)).unwrap(),
+        Compression::best(),
+    );
+    encoder.write_all(&std::fs::read(path).unwrap()).unwrap();
+    encoder.finish().unwrap();
 }
@@ -1,12 +1,26 @@
 use crate::game::Stats;
 use crate::table::{row, Table, Value};
 
-pub const COLS: usize = 22;
+pub const COLS: usize = 28;
+
+/// Rate stat columns eligible for heat map coloring, and whether higher values are better.
+pub const HEAT: &[(&str, bool)] = &[
+    ("BA", true),
+    ("OBP", true),
+    ("SLG", true),
+    ("OPS", true),
+    ("OPS+", true),
+    ("wOBA", true),
+    ("wRC+", true),
+    ("BABIP", true),
+    ("P/PA", true),
+];
 
 pub fn table(iter: impl Iterator<Item = Stats>, league: Stats) -> Table<COLS> {
     let mut table = Table::new(
         [
             ("Games Played", "G"),
+            ("Games Entered as a Substitute", "Sub"),
             ("Plate Appearances", "PA"),
             ("At Bats", "AB"),
             ("Runs Scored", "R"),
@@ -19,15 +33,20 @@ pub fn table(iter: impl Iterator<Item = Stats>, league: Stats) -> Table<COLS> {
             ("Caught Stealing", "CS"),
             ("Bases on Balls (Walks)", "BB"),
             ("Strikeouts", "SO"),
+            ("Strikeouts Looking", "SOL"),
             ("Batting Average", "BA"),
             ("On-base Percentage", "OBP"),
             ("Slugging Percentage", "SLG"),
             ("On-base Plus Slugging", "OPS"),
             ("Adjusted OPS (100 is league average)", "OPS+"),
+            ("Weighted On-base Average", "wOBA"),
+            ("Weighted Runs Created Plus (100 is league average)", "wRC+"),
             ("Batting Average on Balls In Play", "BABIP"),
+            ("Pitches Seen per Plate Appearance", "P/PA"),
             ("Total Bases", "TB"),
             ("Double Plays Grounded Into", "GIDP"),
             ("Sacrifices", "SAC"),
+            ("Run Expectancy Change (RE24)", "RE24"),
         ],
         "text-right",
         "number",
@@ -43,6 +62,7 @@ pub fn table(iter: impl Iterator<Item = Stats>, league: Stats) -> Table<COLS> {
 pub fn build_row(stats: Stats, league: Stats) -> [Value; COLS] {
     row![
         stats.games_batted,
+        stats.games_as_substitute,
         stats.plate_appearances,
         stats.at_bats,
         stats.runs,
@@ -55,14 +75,54 @@ pub fn build_row(stats: Stats, league: Stats) -> [Value; COLS] {
         stats.caught_stealing,
         stats.walks,
         stats.strike_outs,
+        stats.strike_outs_looking,
         stats.batting_average(),
         stats.on_base_percentage(),
         stats.slugging_percentage(),
         stats.on_base_plus_slugging(),
         stats.ops_plus(league),
+        stats.woba(league),
+        stats.wrc_plus(league),
         stats.batting_average_on_balls_in_play(),
+        stats.pitches_per_plate_appearance(),
         stats.total_bases(),
         stats.double_plays_grounded_into,
         stats.sacrifices,
+        stats.batting_re24(league),
     ]
 }
+
+pub const BASERUNNING_COLS: usize = 8;
+
+/// Stolen bases and caught stealing, broken down by the base being stolen.
+pub fn baserunning_table(iter: impl Iterator<Item = Stats>) -> Table<BASERUNNING_COLS> {
+    let mut table = Table::new(
+        [
+            ("Stolen Bases", "SB"),
+            ("Stolen Bases of Second", "SB2"),
+            ("Stolen Bases of Third", "SB3"),
+            ("Stolen Bases of Home", "SBH"),
+            ("Caught Stealing", "CS"),
+            ("Caught Stealing Second", "CS2"),
+            ("Caught Stealing Third", "CS3"),
+            ("Caught Stealing Home", "CSH"),
+        ],
+        "text-right",
+        "number",
+    );
+
+    for stats in iter {
+        table.push(row![
+            stats.stolen_bases,
+            stats.stolen_bases_second,
+            stats.stolen_bases_third,
+            stats.stolen_bases_home,
+            stats.caught_stealing,
+            stats.caught_stealing_second,
+            stats.caught_stealing_third,
+            stats.caught_stealing_home,
+        ]);
+    }
+
+    table
+}
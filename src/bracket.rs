@@ -0,0 +1,103 @@
+use crate::game::Kind;
+use crate::names::{self, TeamName};
+use crate::{schedule, seasons::Season, summary};
+use anyhow::Result;
+use std::collections::btree_map::{BTreeMap, Entry};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct Matchup {
+    pub teams: [BracketTeam; 2],
+    pub games: Vec<BracketGame>,
+}
+
+#[derive(Debug)]
+pub struct BracketTeam {
+    pub id: Uuid,
+    pub name: TeamName,
+    pub wins: u16,
+}
+
+#[derive(Debug)]
+pub struct BracketGame {
+    pub id: Uuid,
+    pub day: u16,
+    /// Runs scored by each team, in the same order as `Matchup::teams`
+    pub score: [u16; 2],
+    /// Index into `Matchup::teams` of the home team
+    pub home: usize,
+}
+
+impl Matchup {
+    pub fn is_winner(&self, index: &usize) -> bool {
+        self.teams[*index].wins > self.teams[1 - *index].wins
+    }
+
+    fn first_day(&self) -> u16 {
+        self.games.first().map(|game| game.day).unwrap_or_default()
+    }
+}
+
+/// Reconstructs the postseason bracket for a season, grouping postseason games into series by
+/// the pair of teams playing, and series into rounds.
+pub fn bracket(season: &Season) -> Result<Vec<Vec<Matchup>>> {
+    let mut series: BTreeMap<(Uuid, Uuid), Matchup> = BTreeMap::new();
+    for team in summary::season_postseason_team_summary(season)? {
+        for (_, entry) in schedule::schedule(team.id, season)? {
+            // each game appears in both teams' schedules; only look at one of them
+            if entry.kind != Kind::Postseason || team.id > entry.opponent_id {
+                continue;
+            }
+            let matchup = match series.entry((team.id, entry.opponent_id)) {
+                Entry::Occupied(occupied) => occupied.into_mut(),
+                Entry::Vacant(vacant) => vacant.insert(Matchup {
+                    teams: [
+                        BracketTeam {
+                            id: team.id,
+                            name: names::team_name(team.id)?.unwrap_or_default(),
+                            wins: 0,
+                        },
+                        BracketTeam {
+                            id: entry.opponent_id,
+                            name: entry.opponent.clone(),
+                            wins: 0,
+                        },
+                    ],
+                    games: Vec::new(),
+                }),
+            };
+            matchup.teams[if entry.won { 0 } else { 1 }].wins += 1;
+            matchup.games.push(BracketGame {
+                id: entry.id,
+                day: entry.day,
+                score: [entry.score, entry.opponent_score],
+                home: if entry.home { 0 } else { 1 },
+            });
+        }
+    }
+
+    let mut series = series.into_values().collect::<Vec<_>>();
+    series.sort_by_key(|matchup| matchup.first_day());
+
+    // a series belongs to the round after the latest round either team has already played in,
+    // which handles teams that had a bye
+    let mut team_rounds: HashMap<Uuid, usize> = HashMap::new();
+    let mut rounds: Vec<Vec<Matchup>> = Vec::new();
+    for matchup in series {
+        let round = matchup
+            .teams
+            .iter()
+            .map(|team| team_rounds.get(&team.id).map_or(0, |round| round + 1))
+            .max()
+            .unwrap_or_default();
+        for team in &matchup.teams {
+            team_rounds.insert(team.id, round);
+        }
+        if rounds.len() <= round {
+            rounds.resize_with(round + 1, Vec::new);
+        }
+        rounds[round].push(matchup);
+    }
+    Ok(rounds)
+}
@@ -0,0 +1,120 @@
+//! Just enough of iCalendar (RFC 5545) to publish a list of events.
+
+use chrono::{DateTime, Utc};
+use rocket::http::ContentType;
+use rocket::response::{self, content::Custom, Responder};
+use rocket::Request;
+
+pub struct Calendar {
+    pub name: String,
+    pub events: Vec<Event>,
+}
+
+pub struct Event {
+    /// Must be globally unique and stable, so that calendar clients update rather than duplicate
+    /// events when the calendar is refreshed
+    pub uid: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub summary: String,
+    pub description: String,
+    pub url: Option<String>,
+}
+
+impl Calendar {
+    /// Serializes the calendar. `stamp` is used as every event's `DTSTAMP`.
+    pub fn to_ics(&self, stamp: DateTime<Utc>) -> String {
+        let mut s = String::new();
+        fold(&mut s, "BEGIN:VCALENDAR");
+        fold(&mut s, "VERSION:2.0");
+        fold(&mut s, "PRODID:-//sibr.dev//bricks//EN");
+        fold(&mut s, &format!("X-WR-CALNAME:{}", escape(&self.name)));
+        for event in &self.events {
+            fold(&mut s, "BEGIN:VEVENT");
+            fold(&mut s, &format!("UID:{}", event.uid));
+            fold(&mut s, &format!("DTSTAMP:{}", timestamp(stamp)));
+            fold(&mut s, &format!("DTSTART:{}", timestamp(event.start)));
+            fold(&mut s, &format!("DTEND:{}", timestamp(event.end)));
+            fold(&mut s, &format!("SUMMARY:{}", escape(&event.summary)));
+            fold(
+                &mut s,
+                &format!("DESCRIPTION:{}", escape(&event.description)),
+            );
+            if let Some(url) = &event.url {
+                fold(&mut s, &format!("URL:{}", url));
+            }
+            fold(&mut s, "END:VEVENT");
+        }
+        fold(&mut s, "END:VCALENDAR");
+        s
+    }
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                s.push('\\');
+                s.push(c);
+            }
+            '\n' => s.push_str("\\n"),
+            '\r' => {}
+            _ => s.push(c),
+        }
+    }
+    s
+}
+
+/// Appends a content line, folded so that no line is longer than 75 octets (without splitting a
+/// UTF-8 sequence), followed by CRLF.
+fn fold(s: &mut String, line: &str) {
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            s.push_str("\r\n ");
+            len = 1;
+        }
+        s.push(c);
+        len += c.len_utf8();
+    }
+    s.push_str("\r\n");
+}
+
+pub struct Ics(pub Calendar);
+
+impl<'r> Responder<'r, 'static> for Ics {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        Custom(ContentType::Calendar, self.0.to_ics(Utc::now())).respond_to(req)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    let start = "2021-03-01T16:00:00Z".parse().unwrap();
+    let calendar = Calendar {
+        name: "Hellmouth Sunbeams, Season 12".into(),
+        events: vec![Event {
+            uid: "game@example".into(),
+            start,
+            end: "2021-03-01T16:25:30Z".parse().unwrap(),
+            summary: "Day 1: Sunbeams @ Tigers; W 5–3".into(),
+            description: "🌞".repeat(30),
+            url: None,
+        }],
+    };
+    let ics = calendar.to_ics(start);
+    assert!(ics.contains("\r\nX-WR-CALNAME:Hellmouth Sunbeams\\, Season 12\r\n"));
+    assert!(ics.contains("\r\nDTSTART:20210301T160000Z\r\nDTEND:20210301T162530Z\r\n"));
+    assert!(ics.contains("\r\nSUMMARY:Day 1: Sunbeams @ Tigers\\; W 5–3\r\n"));
+    assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+    for line in ics.split("\r\n") {
+        assert!(line.len() <= 75, "{:?}", line);
+    }
+    assert!(ics.contains(&format!("DESCRIPTION:{}\r\n {}", "🌞".repeat(15), "🌞")));
+}
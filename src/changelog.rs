@@ -0,0 +1,38 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+lazy_static::lazy_static! {
+    pub static ref CHANGELOG: Vec<Entry> =
+        serde_json::from_str(include_str!("../changelog.json")).expect("invalid changelog.json");
+}
+
+/// A change to how games are processed. Add an entry to `changelog.json` whenever `DB_VERSION` is
+/// bumped, newest first.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Entry {
+    pub version: u8,
+    pub date: NaiveDate,
+    pub summary: String,
+    /// Abbreviations of the stats whose values may have changed
+    pub stats: Vec<String>,
+    /// Era names affected by the change; empty if all eras were affected
+    pub eras: Vec<String>,
+    /// Whether the database was rebuilt, reprocessing every game
+    pub rebuild: bool,
+}
+
+impl Entry {
+    pub fn anchor(&self) -> String {
+        format!("v{}", self.version)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    assert_eq!(CHANGELOG[0].version, crate::DB_VERSION[0]);
+    for w in CHANGELOG.windows(2) {
+        assert!(w[0].version > w[1].version);
+        assert!(w[0].date >= w[1].date);
+    }
+}
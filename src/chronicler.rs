@@ -1,4 +1,4 @@
-use crate::{CHRONICLER_BASE, CLIENT, DB};
+use crate::{metrics, upstream, CHRONICLER_BASE, DB};
 use anyhow::{Context, Result};
 use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -11,7 +11,7 @@ pub async fn load<T: DeserializeOwned>(
     id: Uuid,
     at: DateTime<Utc>,
 ) -> Result<Option<T>> {
-    let tree = DB.open_tree(format!("cache_chronicler_v1_{}", ty.to_ascii_lowercase()))?;
+    let tree = cache_tree(ty)?;
 
     if let Some((key, value)) = tree.get_lt(Key::new(id, at).as_bytes())? {
         if let Some(key) = Key::read_from(&*key) {
@@ -22,26 +22,24 @@ pub async fn load<T: DeserializeOwned>(
         }
     }
 
-    let response = CLIENT
-        .get(format!(
-            "{}/v2/entities?type={}&id={}&at={}",
-            CHRONICLER_BASE,
-            ty,
-            id,
-            at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
-        ))
-        .send()
-        .await?;
+    metrics::CHRONICLER_REQUESTS.inc();
+    let response = upstream::get(format!(
+        "{}/v2/entities?type={}&id={}&at={}",
+        CHRONICLER_BASE,
+        ty,
+        id,
+        at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+    ))
+    .await?;
     let response_time: DateTime<Utc> = DateTime::parse_from_rfc2822(
         response
-            .headers()
-            .get("date")
-            .context("no date header in response")?
-            .to_str()?,
+            .date
+            .as_deref()
+            .context("no date header in response")?,
     )?
     .into();
 
-    let versions: Versions = response.json().await?;
+    let versions: Versions = response.json()?;
     let version = match versions.items.into_iter().next() {
         Some(v) => v,
         None => return Ok(None),
@@ -59,6 +57,60 @@ pub async fn load<T: DeserializeOwned>(
     Ok(Some(value))
 }
 
+fn cache_tree(ty: &str) -> Result<sled::Tree> {
+    Ok(DB.open_tree(format!("cache_chronicler_v1_{}", ty.to_ascii_lowercase()))?)
+}
+
+/// A cached version of an entity, in the form stored in test fixtures (see `fixtures`).
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedVersion {
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub id: Uuid,
+    pub valid_from: DateTime<Utc>,
+    pub valid_to: DateTime<Utc>,
+    pub data: Box<RawValue>,
+}
+
+/// Returns the cached versions of an entity that were valid at any point from `start` to `end`.
+pub fn cached_versions(
+    ty: &str,
+    id: Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<CachedVersion>> {
+    let mut v = Vec::new();
+    for row in cache_tree(ty)?.scan_prefix(id.as_bytes()) {
+        let (key, value) = row?;
+        let key = Key::read_from(&*key).context("invalid chronicler cache key")?;
+        let value: Value<Box<RawValue>> = serde_json::from_slice(&value)?;
+        if key.valid_from() <= end && start < value.valid_to {
+            v.push(CachedVersion {
+                ty: ty.into(),
+                id,
+                valid_from: key.valid_from(),
+                valid_to: value.valid_to,
+                data: value.data,
+            });
+        }
+    }
+    Ok(v)
+}
+
+/// Adds a version to the cache, so that `load` returns it without asking Chronicler.
+#[cfg(test)]
+pub fn insert_cached(version: &CachedVersion) -> Result<()> {
+    cache_tree(&version.ty)?.insert(
+        Key::new(version.id, version.valid_from).as_bytes(),
+        serde_json::to_vec(&Value {
+            valid_to: version.valid_to,
+            data: &version.data,
+        })?,
+    )?;
+    Ok(())
+}
+
 #[derive(AsBytes, FromBytes)]
 #[repr(C)]
 struct Key {
@@ -0,0 +1,87 @@
+//! `bricks process [--sim <sim> --season <season>] <game-id>...` processes games without starting
+//! the server or background tasks, printing each resulting game as JSON (or the error, with its
+//! full context) for debugging state machine changes.
+//!
+//! Games are written to the database as usual, replacing any previously-processed copy; summary
+//! deltas are folded the next time the server starts. The server must not be running, since the
+//! database can only be opened by one process.
+//!
+//! `bricks dump-fixture <game-id>...` writes test fixtures for processed games (see `fixtures`).
+
+use crate::seasons::Season;
+use crate::{fixtures, game};
+use anyhow::{bail, Context, Result};
+use uuid::Uuid;
+
+pub async fn process(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut sim = None;
+    let mut season = None;
+    let mut ids = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sim" => sim = Some(args.next().context("--sim requires a value")?),
+            "--season" => {
+                season = Some(
+                    args.next()
+                        .context("--season requires a value")?
+                        .parse::<u16>()
+                        .context("invalid --season")?,
+                )
+            }
+            _ => {
+                ids.push(Uuid::parse_str(&arg).with_context(|| format!("invalid game ID {}", arg))?)
+            }
+        }
+    }
+    let season = match (sim, season) {
+        (Some(sim), Some(season)) => Some(Season { sim, season }),
+        (None, None) => None,
+        _ => bail!("--sim and --season must be passed together"),
+    };
+    if ids.is_empty() {
+        bail!("usage: bricks process [--sim <sim> --season <season>] <game-id>...");
+    }
+
+    let mut failed = 0;
+    for id in ids {
+        if let Err(err) = process_one(season.clone(), id).await {
+            eprintln!("failed to process game {}: {:?}", id, err);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        bail!("{} game(s) failed to process", failed);
+    }
+    Ok(())
+}
+
+pub async fn dump_fixture(args: impl Iterator<Item = String>) -> Result<()> {
+    let ids = args
+        .map(|arg| Uuid::parse_str(&arg).with_context(|| format!("invalid game ID {}", arg)))
+        .collect::<Result<Vec<_>>>()?;
+    if ids.is_empty() {
+        bail!("usage: bricks dump-fixture <game-id>...");
+    }
+    for id in ids {
+        let dir = fixtures::dump(id)
+            .await
+            .with_context(|| format!("failed to dump game {}", id))?;
+        println!("{}", dir.display());
+    }
+    Ok(())
+}
+
+async fn process_one(season: Option<Season>, id: Uuid) -> Result<()> {
+    let season = match season {
+        Some(season) => season,
+        None => match game::load(id)? {
+            Some(game) => game.season,
+            // we don't know which season an unprocessed game belongs to
+            None => bail!("game has not been processed before; pass --sim and --season"),
+        },
+    };
+    game::reprocess(season, id).await?;
+    let game = game::load(id)?.context("processed game is missing from the database")?;
+    println!("{}", serde_json::to_string_pretty(&game)?);
+    Ok(())
+}
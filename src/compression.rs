@@ -0,0 +1,90 @@
+//! Gzip compression for responses, negotiated with `Accept-Encoding`.
+//!
+//! HTML, JSON, and CSV responses are compressed as they're sent; season exports in particular can
+//! be several megabytes of very repetitive text. Streamed responses are sent uncompressed, since
+//! compressing them here would mean buffering the whole stream first. Static assets are compressed
+//! at build time (see `build.rs` and `routes::Asset`).
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rocket::http::ContentType;
+use rocket::{Request, Response};
+use std::io::Write;
+
+/// Responses smaller than this are sent uncompressed, since the savings don't cover the overhead.
+const MIN_LEN: usize = 1024;
+
+/// Whether the client accepts gzip-encoded responses.
+pub fn accepts_gzip(req: &Request<'_>) -> bool {
+    req.headers()
+        .get("Accept-Encoding")
+        .any(|value| accepts(value, "gzip"))
+}
+
+/// Whether an `Accept-Encoding` header value allows the encoding, either by name or by `*`, with a
+/// nonzero quality.
+fn accepts(header: &str, encoding: &str) -> bool {
+    header.split(',').any(|item| {
+        let mut params = item.split(';').map(str::trim);
+        let name = params.next().unwrap_or_default();
+        let quality = params
+            .filter_map(|param| param.strip_prefix("q="))
+            .find_map(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        (name.eq_ignore_ascii_case(encoding) || name == "*") && quality > 0.0
+    })
+}
+
+/// Response fairing callback, attached last so that it compresses the final body.
+pub async fn compress(req: &Request<'_>, response: &mut Response<'_>) {
+    let compressible = [ContentType::HTML, ContentType::JSON, ContentType::CSV]
+        .iter()
+        .any(|ty| response.content_type().as_ref() == Some(ty));
+    if !compressible
+        || response.headers().contains("Content-Encoding")
+        || response.body().preset_size().is_none()
+    {
+        return;
+    }
+    response.adjoin_raw_header("Vary", "Accept-Encoding");
+    if !accepts_gzip(req) {
+        return;
+    }
+
+    let body = match response.body_mut().to_bytes().await {
+        Ok(body) => body,
+        Err(_) => return,
+    };
+    if body.len() < MIN_LEN {
+        response.set_sized_body(body.len(), std::io::Cursor::new(body));
+        return;
+    }
+    let mut encoder = GzEncoder::new(Vec::with_capacity(body.len() / 4), Compression::default());
+    let gzip = match encoder.write_all(&body).and_then(|()| encoder.finish()) {
+        Ok(gzip) => gzip,
+        Err(err) => {
+            log::error!("while compressing response: {}", err);
+            response.set_sized_body(body.len(), std::io::Cursor::new(body));
+            return;
+        }
+    };
+    response.set_sized_body(gzip.len(), std::io::Cursor::new(gzip));
+    response.set_raw_header("Content-Encoding", "gzip");
+    // the compressed body isn't byte-for-byte the same representation the ETag was computed from
+    if let Some(etag) = response.headers().get_one("ETag").map(str::to_owned) {
+        if !etag.starts_with("W/") {
+            response.set_raw_header("ETag", format!("W/{}", etag));
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    assert!(accepts("gzip, deflate, br", "gzip"));
+    assert!(accepts("br;q=1.0, GZIP;q=0.5", "gzip"));
+    assert!(accepts("*", "gzip"));
+    assert!(!accepts("gzip;q=0", "gzip"));
+    assert!(!accepts("identity", "gzip"));
+    assert!(!accepts("", "gzip"));
+}
@@ -1,4 +1,4 @@
-use csv::Writer;
+use csv::{Writer, WriterBuilder};
 use rocket::http::{ContentType, Status};
 use rocket::response::{self, content::Custom, Responder};
 use rocket::Request;
@@ -17,7 +17,7 @@ impl<'r, T: Serialize> Responder<'r, 'static> for Csv<Vec<T>> {
     }
 }
 
-fn write_csv<T: Serialize>(rows: Vec<T>) -> anyhow::Result<String> {
+pub fn write_csv<T: Serialize>(rows: Vec<T>) -> anyhow::Result<String> {
     let mut writer = Writer::from_writer(Cursor::new(Vec::new()));
     for row in rows {
         writer.serialize(row)?;
@@ -25,3 +25,35 @@ fn write_csv<T: Serialize>(rows: Vec<T>) -> anyhow::Result<String> {
     let buf = writer.into_inner()?.into_inner();
     Ok(String::from_utf8(buf)?)
 }
+
+/// Serializes rows one at a time as they're consumed, with the header before the first row, so
+/// that large exports can be streamed without building the whole file in memory. An error reading
+/// a row is passed through.
+pub fn stream_csv<T: Serialize>(
+    rows: impl Iterator<Item = anyhow::Result<T>>,
+) -> impl Iterator<Item = anyhow::Result<String>> {
+    rows.enumerate().map(|(i, row)| {
+        let row = row?;
+        let mut writer = WriterBuilder::new()
+            .has_headers(i == 0)
+            .from_writer(Vec::new());
+        writer.serialize(row)?;
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    })
+}
+
+#[cfg(test)]
+#[test]
+fn test_stream_csv() {
+    #[derive(Serialize)]
+    struct Row {
+        a: u8,
+        b: &'static str,
+    }
+
+    let rows = vec![Row { a: 1, b: "x" }, Row { a: 2, b: "y, z" }];
+    let streamed = stream_csv(rows.into_iter().map(Ok))
+        .collect::<anyhow::Result<String>>()
+        .unwrap();
+    assert_eq!(streamed, "a,b\n1,x\n2,\"y, z\"\n");
+}
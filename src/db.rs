@@ -0,0 +1,149 @@
+use crate::{names, seasons::Season, DB};
+use anyhow::{anyhow, Context, Result};
+use rocket::http::{ContentType, Status};
+use rocket::{Request, Response};
+use sled::Db;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+/// Set once the database configured by `BRICKS_SLED_V1` has been opened. If it couldn't be, we
+/// run against an empty temporary database instead: every page is a 503 (see `refuse_unready`),
+/// background tasks stay off, and the `/ready` probe fails so the server can be taken out of
+/// rotation without a crash loop.
+static READY: AtomicBool = AtomicBool::new(false);
+/// Set once `warm_up` has finished, whether or not it succeeded.
+static WARM: AtomicBool = AtomicBool::new(false);
+
+pub fn is_ready() -> bool {
+    READY.load(Ordering::Relaxed)
+}
+
+pub fn is_warm() -> bool {
+    WARM.load(Ordering::Relaxed)
+}
+
+/// Paths that still respond normally without the configured database.
+const UNREADY_PATHS: &[&str] = &["/metrics", "/ready"];
+
+/// Response fairing callback. Without the configured database, responses other than the probes
+/// are replaced with a 503, rather than serving pages from the empty fallback database.
+pub fn refuse_unready(req: &Request<'_>, response: &mut Response<'_>) {
+    if is_ready() || UNREADY_PATHS.contains(&req.uri().path().as_str()) {
+        return;
+    }
+    const BODY: &str = "The database is unavailable.";
+    response.set_status(Status::ServiceUnavailable);
+    response.set_header(ContentType::Plain);
+    response.set_sized_body(BODY.len(), std::io::Cursor::new(BODY));
+}
+
+/// Opens every tree and reads through the season and name data that nearly every page needs, so
+/// that the first requests after a restart don't pay for it. A failure is logged rather than
+/// holding back readiness forever; the pages that hit it will fail on their own.
+pub fn warm_up() {
+    let start = Instant::now();
+    match read_through() {
+        Ok(()) => log::info!("database warmed up in {:?}", Instant::now() - start),
+        Err(err) => log::error!("failed to warm up database: {:#}", err),
+    }
+    WARM.store(true, Ordering::Relaxed);
+}
+
+fn read_through() -> Result<()> {
+    for name in DB.tree_names() {
+        DB.open_tree(name)?;
+    }
+    for row in DB.open_tree(names::TREE)?.iter() {
+        row?;
+    }
+    let mut seasons = Season::known()?;
+    seasons.extend(Season::recorded()?);
+    for season in seasons {
+        season.era_name()?;
+    }
+    Ok(())
+}
+
+pub fn open() -> Db {
+    match open_configured() {
+        Ok(db) => {
+            READY.store(true, Ordering::Relaxed);
+            db
+        }
+        Err(err) => {
+            log::error!("{:#}", err);
+            if std::env::var_os("BRICKS_SLED_NO_FALLBACK").is_some() {
+                log::error!("BRICKS_SLED_NO_FALLBACK is set, exiting");
+                std::process::exit(1);
+            }
+            log::warn!(
+                "falling back to an empty temporary database; \
+                 pages and background tasks are disabled"
+            );
+            sled::Config::default()
+                .temporary(true)
+                .open()
+                .expect("failed to open temporary database")
+        }
+    }
+}
+
+fn open_configured() -> Result<Db> {
+    let path = PathBuf::from(std::env::var_os("BRICKS_SLED_V1").context(
+        "BRICKS_SLED_V1 is not set; it must be the path to the sled database directory",
+    )?);
+    sled::Config::default()
+        .path(&path)
+        .use_compression(true)
+        .open()
+        .map_err(|err| diagnose(&path, err))
+}
+
+fn diagnose(path: &Path, err: sled::Error) -> anyhow::Error {
+    let hint = match &err {
+        sled::Error::Io(io) if io.kind() == ErrorKind::WouldBlock => match lock_holder(path) {
+            Some(pid) => format!("the database is locked by process {}", pid),
+            None => "the database is locked by another process".into(),
+        },
+        sled::Error::Io(io) if io.kind() == ErrorKind::PermissionDenied => {
+            "permission denied; the database directory must be readable and writable".into()
+        }
+        sled::Error::Io(io) if io.kind() == ErrorKind::NotFound => {
+            "a parent directory of the database does not exist".into()
+        }
+        _ if path.exists() && !path.is_dir() => "the path exists but is not a directory".into(),
+        _ => "unknown cause".into(),
+    };
+    anyhow!(err).context(format!(
+        "failed to open database at {} ({})",
+        path.display(),
+        hint
+    ))
+}
+
+/// Finds the process holding the lock on sled's `db` file by looking up its inode in
+/// `/proc/locks`.
+#[cfg(target_os = "linux")]
+fn lock_holder(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    let inode = std::fs::metadata(path.join("db")).ok()?.ino();
+    std::fs::read_to_string("/proc/locks")
+        .ok()?
+        .lines()
+        .find_map(|line| {
+            // 1: FLOCK  ADVISORY  WRITE 1234 00:1d:5678 0 EOF
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let lock_inode = fields.get(5)?.rsplit(':').next()?.parse::<u64>().ok()?;
+            (lock_inode == inode)
+                .then(|| fields.get(4)?.parse().ok())
+                .flatten()
+        })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn lock_holder(_path: &Path) -> Option<u32> {
+    None
+}
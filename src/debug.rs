@@ -1,3 +1,4 @@
+use crate::event_type::EventType;
 use json_patch::Patch;
 use serde::{Deserialize, Serialize};
 
@@ -6,10 +7,24 @@ use serde::{Deserialize, Serialize};
 pub enum LogEntry {
     Ok {
         description: String,
+        /// The event type; missing from logs written before it was recorded
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ty: Option<EventType>,
         patch: Patch,
     },
     Err {
         description: Option<String>,
+        /// The event type, if the error came from an event
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ty: Option<EventType>,
         error: String,
     },
 }
+
+impl LogEntry {
+    pub fn ty(&self) -> Option<EventType> {
+        match self {
+            LogEntry::Ok { ty, .. } | LogEntry::Err { ty, .. } => *ty,
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::game::Game;
+use crate::{seasons::Season, DB};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sled::transaction::{
+    ConflictableTransactionError, ConflictableTransactionResult, TransactionalTree,
+};
+use uuid::Uuid;
+
+pub const TREE: &str = "decisions_v1";
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub enum Decision {
+    #[serde(rename = "W")]
+    Win,
+    #[serde(rename = "L")]
+    Loss,
+    #[serde(rename = "SV")]
+    Save,
+    #[serde(rename = "HLD")]
+    Hold,
+    #[serde(rename = "BSV")]
+    BlownSave,
+}
+
+/// The decisions credited to a pitcher in one game.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Entry {
+    pub game_id: Uuid,
+    #[serde(flatten)]
+    pub season: Season,
+    pub day: u16,
+    pub decisions: Vec<Decision>,
+}
+
+/// Writes a pitcher decisions entry for each pitcher credited with a decision in this game, keyed
+/// by pitcher and game.
+pub fn write_decisions(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    for (pitcher, decisions) in decisions(game) {
+        let entry = Entry {
+            game_id: id,
+            season: game.season.clone(),
+            day: game.day,
+            decisions,
+        };
+        tree.insert(
+            build_key(pitcher, id),
+            serde_json::to_vec(&entry).map_err(ConflictableTransactionError::Abort)?,
+        )?;
+    }
+    Ok(())
+}
+
+/// Reverses a prior `write_decisions` call for this game.
+pub fn remove_decisions(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    for (pitcher, _) in decisions(game) {
+        tree.remove(build_key(pitcher, id))?;
+    }
+    Ok(())
+}
+
+/// Returns every game a pitcher was credited with a decision in, in chronological order.
+pub fn load(pitcher: Uuid) -> Result<Vec<Entry>> {
+    let mut entries = DB
+        .open_tree(TREE)?
+        .scan_prefix(pitcher.as_bytes())
+        .values()
+        .map(|value| Ok(serde_json::from_slice::<Entry>(&value?)?))
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort_by(|a, b| a.season.cmp(&b.season).then(a.day.cmp(&b.day)));
+    Ok(entries)
+}
+
+fn decisions(game: &Game) -> Vec<(Uuid, Vec<Decision>)> {
+    let mut v = Vec::new();
+    for team in game.teams() {
+        for (id, stats) in &team.stats {
+            let mut decisions = Vec::new();
+            for (count, decision) in [
+                (stats.wins, Decision::Win),
+                (stats.losses, Decision::Loss),
+                (stats.saves, Decision::Save),
+                (stats.holds, Decision::Hold),
+                (stats.blown_saves, Decision::BlownSave),
+            ] {
+                if count > 0 {
+                    decisions.push(decision);
+                }
+            }
+            if !decisions.is_empty() {
+                v.push((*id, decisions));
+            }
+        }
+    }
+    v
+}
+
+fn build_key(pitcher: Uuid, game: Uuid) -> Vec<u8> {
+    let mut key = Vec::with_capacity(32);
+    key.extend_from_slice(pitcher.as_bytes());
+    key.extend_from_slice(game.as_bytes());
+    key
+}
@@ -0,0 +1,237 @@
+//! Conditional requests for pages built from processed data (game, player, and season pages).
+//!
+//! Each page's ETag is a hash of the page as served, remembered by path, the query parameters
+//! pages understand, and theme (since the theme cookie changes the page), along with when it was
+//! first served and the data generation at the time. Only the most recently used `CAPACITY` pages
+//! are remembered. The generation is bumped whenever processed data changes: when summaries are
+//! folded, when a rebuild starts or finishes, and when names are repaired. Until then, a request
+//! whose `If-None-Match` or `If-Modified-Since` matches is answered with a 304 without rendering
+//! the page again.
+
+use crate::game::GAME_STATS_TREE;
+use crate::theme::Theme;
+use crate::DB;
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use rocket::http::{Method, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::{get, uri, Request, Response};
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Season pages, by path prefix. Game pages are handled separately, since games in progress
+/// aren't built from processed data.
+const PREFIXES: &[&str] = &[
+    "/batting/",
+    "/day/",
+    "/milestones/",
+    "/pitching/",
+    "/player/",
+    "/postseason/",
+];
+
+/// Query parameters that change a page. Others are left out of the cache key, so that arbitrary
+/// query strings don't each get an entry.
+const PARAMS: &[&str] = &["exhibition", "order", "page", "qualified", "sort"];
+
+/// The number of pages to remember.
+const CAPACITY: usize = 4096;
+
+const HTTP_DATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    /// Least recently used first.
+    static ref CACHE: Mutex<IndexMap<String, Entry>> = Mutex::new(IndexMap::new());
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    etag: String,
+    last_modified: DateTime<Utc>,
+}
+
+/// What `check` decided for a request, for `finish` to act on.
+enum Conditional {
+    Skip,
+    NotModified(Entry),
+    Render { key: String, generation: u64 },
+}
+
+/// The current data generation, for other caches of processed data to check against.
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::SeqCst)
+}
+
+/// Forgets every remembered page. Call whenever processed data changes.
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    CACHE.lock().unwrap().clear();
+}
+
+/// Request fairing callback. Requests for a remembered page that the client already has are
+/// rerouted to `not_modified`, so the page isn't rendered.
+pub fn check(req: &mut Request<'_>) {
+    if !matches!(req.method(), Method::Get | Method::Head)
+        || !is_cacheable(req.uri().path().as_str())
+    {
+        return;
+    }
+    let key = cache_key(req);
+    let generation = GENERATION.load(Ordering::SeqCst);
+    let cached = {
+        let mut cache = CACHE.lock().unwrap();
+        let entry = cache.shift_remove(&key);
+        if let Some(entry) = &entry {
+            cache.insert(key.clone(), entry.clone());
+        }
+        entry
+    };
+    match cached {
+        Some(entry) if is_fresh(req, &entry) => {
+            req.local_cache(|| Conditional::NotModified(entry));
+            req.set_uri(uri!(not_modified()));
+        }
+        _ => {
+            req.local_cache(|| Conditional::Render { key, generation });
+        }
+    }
+}
+
+/// Response fairing callback, attached after any fairing that changes the body. Adds the ETag
+/// and Last-Modified headers, and remembers newly-rendered pages.
+pub async fn finish(req: &Request<'_>, response: &mut Response<'_>) {
+    let entry = match req.local_cache(|| Conditional::Skip) {
+        Conditional::Skip => return,
+        Conditional::NotModified(entry) => entry.clone(),
+        Conditional::Render { key, generation } => {
+            if response.status() != Status::Ok {
+                return;
+            }
+            let body = match response.body_mut().to_bytes().await {
+                Ok(body) => body,
+                Err(_) => return,
+            };
+            let mut hasher = twox_hash::XxHash64::default();
+            hasher.write(&body);
+            let etag = format!("\"{:016x}\"", hasher.finish());
+            response.set_sized_body(body.len(), std::io::Cursor::new(body));
+
+            let mut cache = CACHE.lock().unwrap();
+            let entry = match cache.shift_remove(key) {
+                Some(entry) if entry.etag == etag => entry,
+                _ => Entry {
+                    etag,
+                    last_modified: Utc::now(),
+                },
+            };
+            // a page rendered from data that changed while it was rendering isn't remembered
+            if GENERATION.load(Ordering::SeqCst) == *generation {
+                cache.insert(key.clone(), entry.clone());
+                if cache.len() > CAPACITY {
+                    cache.shift_remove_index(0);
+                }
+            }
+            entry
+        }
+    };
+    response.set_raw_header("ETag", entry.etag);
+    response.set_raw_header(
+        "Last-Modified",
+        entry.last_modified.format(HTTP_DATE).to_string(),
+    );
+    response.set_raw_header("Cache-Control", "no-cache");
+    response.set_raw_header("Vary", "Cookie");
+}
+
+/// Request guard that only succeeds for requests `check` rerouted, so `not_modified` can't be
+/// requested directly.
+pub struct Rerouted;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Rerouted {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Rerouted, ()> {
+        match req.local_cache(|| Conditional::Skip) {
+            Conditional::NotModified(_) => Outcome::Success(Rerouted),
+            _ => Outcome::Forward(()),
+        }
+    }
+}
+
+#[get("/not-modified")]
+pub fn not_modified(_rerouted: Rerouted) -> Status {
+    Status::NotModified
+}
+
+fn cache_key(req: &Request<'_>) -> String {
+    let mut key = format!(
+        "{} {}",
+        Theme::from_cookies(req.cookies()).class(),
+        req.uri().path()
+    );
+    if let Some(query) = req.uri().query() {
+        let mut params = query
+            .segments()
+            .filter(|(name, _)| PARAMS.contains(name))
+            .collect::<Vec<_>>();
+        params.sort_unstable();
+        for (name, value) in params {
+            key.push_str(&format!(" {}={}", name, value));
+        }
+    }
+    key
+}
+
+fn is_cacheable(path: &str) -> bool {
+    if let Some(id) = path.strip_prefix("/game/") {
+        // only processed games; pages for games in progress change without the data changing
+        match Uuid::parse_str(id) {
+            Ok(id) => DB
+                .open_tree(GAME_STATS_TREE)
+                .and_then(|tree| tree.contains_key(id.as_bytes()))
+                .unwrap_or(false),
+            Err(_) => false,
+        }
+    } else {
+        PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+    }
+}
+
+/// Whether the client's copy matches. `If-None-Match` takes precedence over `If-Modified-Since`.
+fn is_fresh(req: &Request<'_>, entry: &Entry) -> bool {
+    if let Some(tags) = req.headers().get_one("If-None-Match") {
+        return etag_matches(tags, &entry.etag);
+    }
+    match req
+        .headers()
+        .get_one("If-Modified-Since")
+        .and_then(|since| DateTime::parse_from_rfc2822(since).ok())
+    {
+        Some(since) => entry.last_modified.timestamp() <= since.timestamp(),
+        None => false,
+    }
+}
+
+fn etag_matches(tags: &str, etag: &str) -> bool {
+    tags.split(',')
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    assert!(etag_matches("\"abc\"", "\"abc\""));
+    assert!(etag_matches("\"xyz\", W/\"abc\"", "\"abc\""));
+    assert!(etag_matches("*", "\"abc\""));
+    assert!(!etag_matches("\"abcd\"", "\"abc\""));
+
+    assert!(is_cacheable("/batting/gamma10/0"));
+    assert!(!is_cacheable("/game/not-a-uuid"));
+    assert!(!is_cacheable("/glossary"));
+}
@@ -0,0 +1,139 @@
+//! Feed event types. The feed identifies each event by a number; these are the ones Bricks knows
+//! about, named after what they do. Numbers Bricks hasn't seen before are kept as `Unknown`, so
+//! that a feed with a new event type still parses and the state machine can report it.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt::{self, Display};
+
+macro_rules! event_types {
+    ($($name:ident = $value:literal,)*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+        #[serde(into = "u16")]
+        pub enum EventType {
+            $($name,)*
+            Unknown(u16),
+        }
+
+        impl TryFrom<u16> for EventType {
+            type Error = u16;
+
+            /// Returns the unrecognized number as the error.
+            fn try_from(value: u16) -> Result<EventType, u16> {
+                match value {
+                    $($value => Ok(EventType::$name),)*
+                    _ => Err(value),
+                }
+            }
+        }
+
+        impl From<EventType> for u16 {
+            fn from(ty: EventType) -> u16 {
+                match ty {
+                    $(EventType::$name => $value,)*
+                    EventType::Unknown(value) => value,
+                }
+            }
+        }
+    };
+}
+
+event_types! {
+    LetsGo = 0,
+    PlayBall = 1,
+    HalfInning = 2,
+    PitcherChange = 3,
+    StolenBase = 4,
+    Walk = 5,
+    Strikeout = 6,
+    FlyOut = 7,
+    GroundOut = 8,
+    HomeRun = 9,
+    Hit = 10,
+    GameEnd = 11,
+    BatterUp = 12,
+    Strike = 13,
+    Ball = 14,
+    FoulBall = 15,
+    Shame = 20,
+    PlayerSkipped = 23,
+    Partying = 24,
+    InningEnd = 28,
+    SunTwo = 30,
+    BlackHole = 31,
+    Feedback = 41,
+    PeanutYummy = 46,
+    PeanutAllergic = 47,
+    Incineration = 54,
+    Flooding = 62,
+    PeanutFlavorText = 73,
+    ReturnFromElsewhere = 84,
+    AddedMod = 106,
+    RemovedMod = 107,
+    PlayerAddedToTeam = 109,
+    PlayerRemovedFromTeam = 112,
+    Trade = 113,
+    Swap = 114,
+    PlayerMoved = 115,
+    IncinerationReplacement = 116,
+    StatIncrease = 117,
+    StatDecrease = 118,
+    StatReroll = 119,
+    EnterHallOfFlame = 125,
+    ReverbShuffle = 130,
+    ReverbLineupShuffle = 131,
+    ReverbRotationShuffle = 132,
+    PlayerHatched = 137,
+    AddedModFromOtherMod = 146,
+    RemovedModFromOtherMod = 147,
+    PrizeMatch = 193,
+    ScoreUpdate = 209,
+    WinCollectedRegular = 214,
+    WinCollectedPostseason = 215,
+    GameOver = 216,
+    WeatherEvent = 223,
+    NightShift = 252,
+    DoubleStrike = 261,
+    StrikeZapped = 262,
+    WinterStormWarning = 263,
+    Snowflakes = 264,
+    Unfreezable = 265,
+}
+
+impl EventType {
+    /// Like `try_from`, but keeps unrecognized numbers as `Unknown`.
+    pub fn from_raw(value: u16) -> EventType {
+        EventType::try_from(value).unwrap_or(EventType::Unknown(value))
+    }
+}
+
+impl<'de> Deserialize<'de> for EventType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<EventType, D::Error> {
+        u16::deserialize(deserializer).map(EventType::from_raw)
+    }
+}
+
+impl Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventType::Unknown(value) => write!(f, "{}", value),
+            ty => write!(f, "{} ({:?})", u16::from(*ty), ty),
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    assert_eq!(EventType::try_from(9), Ok(EventType::HomeRun));
+    assert_eq!(EventType::try_from(999), Err(999));
+    assert_eq!(EventType::from_raw(999), EventType::Unknown(999));
+    assert_eq!(u16::from(EventType::Unknown(999)), 999);
+    for value in 0..300 {
+        assert_eq!(u16::from(EventType::from_raw(value)), value);
+    }
+    assert_eq!(EventType::HomeRun.to_string(), "9 (HomeRun)");
+    assert_eq!(
+        serde_json::from_str::<EventType>("107").unwrap(),
+        EventType::RemovedMod
+    );
+}
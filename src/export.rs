@@ -1,6 +1,6 @@
 use crate::game::Stats;
 use crate::names::{self, TeamName};
-use crate::summary::SeasonSummary;
+use crate::summary::{CareerSummary, SeasonSummary};
 use serde::ser::{Error, Serialize, SerializeStruct, Serializer};
 
 pub struct WithLeagueStats<T> {
@@ -64,6 +64,7 @@ impl<'a> Exportable for WithLeagueStats<Stats> {
             @member,
             ?is_batting,
             games_batted,
+            games_as_substitute,
             plate_appearances,
             at_bats,
             at_bats_with_risp,
@@ -78,8 +79,14 @@ impl<'a> Exportable for WithLeagueStats<Stats> {
             stolen_bases,
             caught_stealing,
             strike_outs,
+            strike_outs_looking,
+            strike_outs_swinging,
             double_plays_grounded_into,
             walks,
+            pitches_seen,
+            strikes_taken,
+            swinging_strikes,
+            foul_balls,
             left_on_base,
         );
         map!(
@@ -92,8 +99,17 @@ impl<'a> Exportable for WithLeagueStats<Stats> {
             slugging_percentage,
             on_base_plus_slugging,
             batting_average_on_balls_in_play,
+            pitches_per_plate_appearance,
+            swings,
+        );
+        map!(
+            @func_league,
+            ?is_batting,
+            ops_plus,
+            woba,
+            wrc_plus,
+            batting_re24
         );
-        map!(@func_league, ?is_batting, ops_plus);
 
         s.serialize_field("is_pitching", &self.inner.is_pitching())?;
         map!(
@@ -109,14 +125,20 @@ impl<'a> Exportable for WithLeagueStats<Stats> {
             no_hitters,
             perfect_games,
             saves,
+            holds,
+            blown_saves,
             batters_faced,
             outs_recorded,
             hits_allowed,
             home_runs_allowed,
             earned_runs,
+            unearned_runs,
             struck_outs,
+            struck_outs_looking,
+            struck_outs_swinging,
             walks_issued,
             strikes_pitched,
+            called_strikes_pitched,
             balls_pitched,
             flyouts_pitched,
             groundouts_pitched,
@@ -125,7 +147,9 @@ impl<'a> Exportable for WithLeagueStats<Stats> {
             @func,
             ?is_pitching,
             win_loss_percentage,
+            save_opportunities,
             earned_run_average,
+            run_average,
             innings_pitched,
             whip,
             hits_per_9,
@@ -133,8 +157,9 @@ impl<'a> Exportable for WithLeagueStats<Stats> {
             walks_per_9,
             struck_outs_per_9,
             struck_outs_walks_ratio,
+            called_strike_percentage,
         );
-        map!(@func_league, ?is_pitching, era_plus);
+        map!(@func_league, ?is_pitching, era_plus, pitching_re24);
 
         Ok(())
     }
@@ -168,3 +193,19 @@ impl Exportable for WithLeagueStats<SeasonSummary> {
         .export(s)
     }
 }
+
+impl Exportable for CareerSummary {
+    fn export<S>(&self, s: &mut S) -> Result<(), S::Error>
+    where
+        S: SerializeStruct,
+    {
+        s.serialize_field("name", &self.name)?;
+        s.serialize_field("id", &self.id)?;
+        s.serialize_field("seasons", &self.seasons)?;
+        WithLeagueStats {
+            inner: self.stats,
+            league: self.league,
+        }
+        .export(s)
+    }
+}
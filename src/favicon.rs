@@ -0,0 +1,36 @@
+//! Favicons drawn from a single emoji, so that team pages can use the team's emoji instead of the
+//! brick.
+
+/// An SVG image of an emoji, centered and filling most of a square. The emoji is drawn as text, so
+/// it looks like the browser's own emoji font.
+pub fn emoji(emoji: &str) -> String {
+    format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">"#,
+            r#"<text x="50" y="50" font-size="85" text-anchor="middle" "#,
+            r#"dominant-baseline="central">"#,
+            "{}</text></svg>"
+        ),
+        escape(emoji)
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => s.push_str("&amp;"),
+            '<' => s.push_str("&lt;"),
+            '>' => s.push_str("&gt;"),
+            _ => s.push(c),
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    assert!(emoji("\u{1f9c0}").contains(">\u{1f9c0}</text>"));
+    assert!(emoji("<&>").contains(">&lt;&amp;&gt;</text>"));
+}
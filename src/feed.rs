@@ -1,8 +1,9 @@
-use crate::{CLIENT, DB, SACHET_BASE};
+use crate::event_type::EventType;
+use crate::{metrics, upstream, DB, SACHET_BASE};
 use anyhow::{bail, Result};
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
-use serde_json::Number;
+use serde_json::{Number, Value};
 use uuid::Uuid;
 
 pub async fn load(game_id: Uuid) -> Result<Vec<GameEvent>> {
@@ -11,6 +12,7 @@ pub async fn load(game_id: Uuid) -> Result<Vec<GameEvent>> {
         let mut events: Vec<GameEvent> = serde_json::from_slice(&data)?;
         sort(&mut events);
         if check(&events) {
+            metrics::FEED_CACHE_HITS.inc();
             return Ok(events);
         } else {
             log::warn!("removing cached feed for {}", game_id);
@@ -18,25 +20,62 @@ pub async fn load(game_id: Uuid) -> Result<Vec<GameEvent>> {
         }
     }
 
-    let data = CLIENT
-        .get(format!("{}/packets?id={}", SACHET_BASE, game_id))
-        .send()
-        .await?
-        .text()
-        .await?;
-    let mut events: Vec<GameEvent> = serde_json::from_str(&data)?;
+    metrics::FEED_CACHE_MISSES.inc();
+    let response = upstream::get(format!("{}/packets?id={}", SACHET_BASE, game_id)).await?;
+    let data = response.text()?;
+    let mut events: Vec<GameEvent> = serde_json::from_str(data)?;
     sort(&mut events);
     // if this check fails, return anyway so we can get debug output, but don't cache
     if check(&events) {
-        tree.insert(game_id.as_bytes(), data.into_bytes())?;
+        tree.insert(game_id.as_bytes(), data.as_bytes())?;
     } else {
         log::warn!("not caching feed for {}", game_id);
     }
     Ok(events)
 }
 
+/// Returns whether a complete feed for a game has been cached. Feeds that are missing or fail the
+/// completeness check are never cached.
+pub fn is_cached(game_id: Uuid) -> Result<bool> {
+    Ok(DB
+        .open_tree("cache_sachet_v1")?
+        .contains_key(game_id.as_bytes())?)
+}
+
+/// Loads a possibly-incomplete feed for a game in progress, bypassing the cache.
+pub async fn load_live(game_id: Uuid) -> Result<Vec<GameEvent>> {
+    let mut events: Vec<GameEvent> =
+        upstream::get(format!("{}/packets?id={}", SACHET_BASE, game_id))
+            .await?
+            .json()?;
+    sort(&mut events);
+    Ok(events)
+}
+
+/// Returns a game's feed as sachet sent it, sorted into the order it's processed in. The cached
+/// feed is used if there is one; otherwise the feed is fetched but not cached, since `load` is
+/// responsible for checking feeds before caching them.
+pub async fn load_raw(game_id: Uuid) -> Result<Vec<Value>> {
+    let values: Vec<Value> = match DB.open_tree("cache_sachet_v1")?.get(game_id.as_bytes())? {
+        Some(data) => serde_json::from_slice(&data)?,
+        None => upstream::get(format!("{}/packets?id={}", SACHET_BASE, game_id))
+            .await?
+            .json()?,
+    };
+    let mut events = values
+        .into_iter()
+        .map(|value| Ok((sort_key(&GameEvent::deserialize(&value)?), value)))
+        .collect::<Result<Vec<_>>>()?;
+    events.sort_unstable_by_key(|(key, _)| *key);
+    Ok(events.into_iter().map(|(_, value)| value).collect())
+}
+
 fn sort(feed: &mut Vec<GameEvent>) {
-    feed.sort_unstable_by_key(|event| (event.metadata.play, event.metadata.sub_play));
+    feed.sort_unstable_by_key(sort_key);
+}
+
+fn sort_key(event: &GameEvent) -> (u16, u16) {
+    (event.metadata.play, event.metadata.sub_play)
 }
 
 fn check(feed: &[GameEvent]) -> bool {
@@ -50,9 +89,12 @@ fn check(feed: &[GameEvent]) -> bool {
             Err(_) => return false,
         };
     }
-    feed.iter()
-        .rev()
-        .any(|event| event.ty == 214 || event.ty == 215)
+    feed.iter().rev().any(|event| {
+        matches!(
+            event.ty,
+            EventType::WinCollectedRegular | EventType::WinCollectedPostseason
+        )
+    })
 }
 
 #[derive(Debug, Deserialize)]
@@ -67,7 +109,7 @@ pub struct GameEvent {
     pub day: u16,
     pub season: u16,
     #[serde(rename = "type")]
-    pub ty: u16,
+    pub ty: EventType,
     pub description: String,
 
     pub base_runners: Option<Vec<Uuid>>,
@@ -85,7 +127,9 @@ impl GameEvent {
     pub fn expect(&self, expected: (u16, u16)) -> Result<(u16, u16)> {
         if expected != (self.metadata.play, self.metadata.sub_play) {
             // handle empty event before half-inning changes
-            if self.ty == 2 && expected == (self.metadata.play - 1, self.metadata.sub_play) {
+            if self.ty == EventType::HalfInning
+                && expected == (self.metadata.play - 1, self.metadata.sub_play)
+            {
                 Ok(self.next())
             } else {
                 bail!("missing event {:?}", expected);
@@ -125,6 +169,7 @@ pub enum ExtraData {
     Score(ScoreData),
     Swap(PlayerSwapData),
     Incineration(IncinerationReplacementData),
+    Move(PlayerMoveData),
 }
 
 #[derive(Debug, Deserialize)]
@@ -152,3 +197,15 @@ pub struct IncinerationReplacementData {
     pub out_player_id: Uuid,
     pub team_id: Uuid,
 }
+
+/// A player joining or leaving a team, or moving between the active roster and the Shadows.
+/// `location` is where the player ended up: 0 for the lineup, 1 for the rotation, and 2 or 3 for
+/// the Shadows.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerMoveData {
+    pub player_id: Uuid,
+    pub player_name: Option<String>,
+    pub team_id: Uuid,
+    pub location: Option<u8>,
+}
@@ -0,0 +1,114 @@
+use crate::game::Stats;
+use crate::table::{row, Table, Value};
+use derive_more::{Add, AddAssign, SubAssign, Sum};
+use serde::{Deserialize, Serialize};
+
+pub const COLS: usize = 4;
+
+/// Fielding credit, as far as the feed describes it. Flyouts and ground outs name the fielder;
+/// double plays and fielder's choices don't, so double plays are credited to the pitcher.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Deserialize,
+    Serialize,
+    Add,
+    AddAssign,
+    SubAssign,
+    Sum,
+)]
+#[serde(default)]
+pub struct FieldingStats {
+    /// Flyouts caught
+    pub putouts: u32,
+    /// Ground outs fielded and thrown to first
+    pub assists: u32,
+    pub double_plays: u32,
+}
+
+impl FieldingStats {
+    pub fn is_fielding(&self) -> bool {
+        self.total_chances() + self.double_plays > 0
+    }
+
+    pub fn total_chances(&self) -> u32 {
+        self.putouts + self.assists
+    }
+}
+
+/// How a fielder named in an event description took part in the out.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum Credit {
+    Putout,
+    Assist,
+}
+
+impl Credit {
+    /// Finds the fielder named in a flyout or ground out description.
+    pub fn parse(description: &str) -> Option<(Credit, &str)> {
+        let line = description.lines().next()?;
+        let (credit, fielder) = if let Some((_, fielder)) = line.split_once(" hit a flyout to ") {
+            (Credit::Putout, fielder)
+        } else {
+            (Credit::Assist, line.split_once(" hit a ground out to ")?.1)
+        };
+        Some((credit, fielder.strip_suffix('.').unwrap_or(fielder)))
+    }
+
+    pub fn apply(self, stats: &mut FieldingStats) {
+        match self {
+            Credit::Putout => stats.putouts += 1,
+            Credit::Assist => stats.assists += 1,
+        }
+    }
+}
+
+pub fn table(iter: impl Iterator<Item = Stats>, league: Stats) -> Table<COLS> {
+    let mut table = Table::new(
+        [
+            ("Putouts", "PO"),
+            ("Assists", "A"),
+            ("Total Chances", "TC"),
+            ("Double Plays Turned", "DP"),
+        ],
+        "text-right",
+        "number",
+    );
+    for stats in iter {
+        table.push(build_row(stats, league));
+    }
+    table
+}
+
+pub fn build_row(stats: Stats, _league: Stats) -> [Value; COLS] {
+    let fielding = stats.fielding;
+    row![
+        fielding.putouts,
+        fielding.assists,
+        fielding.total_chances(),
+        fielding.double_plays,
+    ]
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse() {
+    assert!(matches!(
+        Credit::parse("Nagomi Mcdaniel hit a flyout to Jessica Telephone."),
+        Some((Credit::Putout, "Jessica Telephone"))
+    ));
+    assert!(matches!(
+        Credit::parse(
+            "Nagomi Mcdaniel hit a ground out to Jessica Telephone.\n\
+             Mooney Doctor advances on the sacrifice."
+        ),
+        Some((Credit::Assist, "Jessica Telephone"))
+    ));
+    assert!(Credit::parse("Nagomi Mcdaniel hit into a double play!").is_none());
+}
@@ -0,0 +1,175 @@
+//! Recorded games for checking the state machine offline.
+//!
+//! Each fixture is a directory in `tests/fixtures` named after the game's ID, containing:
+//!
+//! - `feed.json`, the game's feed as sachet sent it, in the order it's processed
+//! - `chronicler.json`, the Chronicler versions the state machine loads while processing the game
+//!   (the two teams, and the home team's stadium)
+//! - `schedule.json`, the cached schedule for the game's day, which decides whether the game is a
+//!   regular season, postseason, or special game
+//! - `game.json`, the resulting game: a golden snapshot that the test below compares against
+//!
+//! `bricks dump-fixture <game-id>...` writes fixtures from the feed and Chronicler caches. After a
+//! change that intentionally alters the results, run the test with `BRICKS_UPDATE_FIXTURES=1` set
+//! to rewrite the snapshots, and check the differences before committing them. The test fails if
+//! there are no fixtures at all, so that it can't pass without checking anything.
+
+use crate::chronicler;
+use crate::feed::{self, GameEvent};
+use crate::game::{self, Game};
+use crate::seasons::Season;
+use crate::state::State;
+use crate::{schedule, team};
+use anyhow::{Context, Result};
+use serde::Serialize;
+#[cfg(test)]
+use serde_json::value::RawValue;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+const DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+#[cfg(test)]
+struct Fixture {
+    id: Uuid,
+    feed: Vec<GameEvent>,
+    chronicler: Vec<chronicler::CachedVersion>,
+    schedule: Box<RawValue>,
+    game: serde_json::Value,
+}
+
+/// Runs a feed through the state machine, as `game::process` does but without writing anything to
+/// the database. The series is left out, since it depends on the schedule.
+async fn replay(season: Season, id: Uuid, feed: &[GameEvent]) -> Result<Game> {
+    let mut state = State::new(season, id);
+    for event in feed {
+        state.push(event).await?;
+    }
+    let mut game = state.finish()?;
+    game.stars = game.stars();
+    game.start = feed.first().map(|event| event.created);
+    game.end = feed.last().map(|event| event.created);
+    game.event_count = feed.len();
+    Ok(game)
+}
+
+/// Writes a fixture for a processed game, whose feed and Chronicler data are cached.
+pub async fn dump(id: Uuid) -> Result<PathBuf> {
+    let game = game::load(id)?.context("game has not been processed")?;
+    let raw = feed::load_raw(id).await?;
+    let feed: Vec<GameEvent> = serde_json::from_value(serde_json::Value::Array(raw.clone()))?;
+    let (start, end) = match (feed.first(), feed.last()) {
+        (Some(first), Some(last)) => (first.created, last.created),
+        _ => anyhow::bail!("feed is empty"),
+    };
+
+    let mut versions = Vec::new();
+    for team in game.teams() {
+        versions.extend(chronicler::cached_versions("team", team.id, start, end)?);
+    }
+    let stadiums = versions
+        .iter()
+        .filter_map(|version| serde_json::from_str::<team::Team>(version.data.get()).ok())
+        .filter_map(|team| team.stadium)
+        .collect::<BTreeSet<_>>();
+    for stadium in stadiums {
+        versions.extend(chronicler::cached_versions("stadium", stadium, start, end)?);
+    }
+
+    let schedule = schedule::cached_day(&game.season, game.day)?.with_context(|| {
+        format!(
+            "schedule for day {} of season {} is not cached",
+            game.day + 1,
+            game.season
+        )
+    })?;
+
+    let replayed = replay(game.season, id, &feed).await?;
+    let dir = Path::new(DIR).join(id.to_string());
+    std::fs::create_dir_all(&dir)?;
+    write(&dir.join("feed.json"), &raw)?;
+    write(&dir.join("chronicler.json"), &versions)?;
+    write(&dir.join("schedule.json"), &schedule)?;
+    write(&dir.join("game.json"), &replayed)?;
+    Ok(dir)
+}
+
+fn write<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    let mut json = serde_json::to_string_pretty(value)?;
+    json.push('\n');
+    std::fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+fn read<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let data = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_slice(&data).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+#[cfg(test)]
+fn load_all() -> Result<Vec<Fixture>> {
+    let entries = std::fs::read_dir(DIR).with_context(|| format!("failed to read {}", DIR))?;
+    let mut fixtures = Vec::new();
+    for entry in entries {
+        let dir = entry?.path();
+        let id = match dir.file_name().and_then(|name| name.to_str()) {
+            Some(name) => Uuid::parse_str(name)
+                .with_context(|| format!("fixture directory {} is not a game ID", name))?,
+            None => continue,
+        };
+        fixtures.push(Fixture {
+            id,
+            feed: read(&dir.join("feed.json"))?,
+            chronicler: read(&dir.join("chronicler.json"))?,
+            schedule: read(&dir.join("schedule.json"))?,
+            game: read(&dir.join("game.json"))?,
+        });
+    }
+    anyhow::ensure!(
+        !fixtures.is_empty(),
+        "no fixtures in {}; write some with `bricks dump-fixture`",
+        DIR
+    );
+    fixtures.sort_by_key(|fixture| fixture.id);
+    Ok(fixtures)
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    let runtime = rocket::tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let update = std::env::var_os("BRICKS_UPDATE_FIXTURES").is_some();
+
+    for fixture in load_all().unwrap() {
+        for version in &fixture.chronicler {
+            chronicler::insert_cached(version).unwrap();
+        }
+        let season: Season = serde_json::from_value(fixture.game.clone()).unwrap();
+        let day = serde_json::from_value(fixture.game["day"].clone()).unwrap();
+        schedule::insert_cached_day(&season, day, &fixture.schedule).unwrap();
+        let game = runtime
+            .block_on(replay(season, fixture.id, &fixture.feed))
+            .unwrap_or_else(|err| panic!("failed to replay game {}: {:?}", fixture.id, err));
+        let actual = serde_json::to_value(&game).unwrap();
+
+        if update {
+            let path = Path::new(DIR)
+                .join(fixture.id.to_string())
+                .join("game.json");
+            write(&path, &actual).unwrap();
+        } else {
+            let diff = json_patch::diff(&fixture.game, &actual);
+            assert!(
+                diff.0.is_empty(),
+                "game {} differs from its snapshot (rerun with BRICKS_UPDATE_FIXTURES=1 to \
+                 update it):\n{}",
+                fixture.id,
+                serde_json::to_string_pretty(&diff).unwrap()
+            );
+        }
+    }
+}
@@ -1,16 +1,29 @@
+use crate::decisions;
+use crate::fielding::FieldingStats;
+use crate::highlights::Highlight;
+use crate::inhabiting::{self, Inhabiting};
+use crate::linear_weights::{self, Weights};
+use crate::matchup::{self, Matchup};
+use crate::milestones::{self, Milestone};
 use crate::names::{self, TeamName};
+use crate::opponents;
+use crate::run_expectancy::RunExpectancy;
 use crate::seasons::{self, Season};
+use crate::splits::Splits;
+use crate::tombstone::{self, Tombstone};
+use crate::win_probability::WinProbability;
 use crate::{
-    debug::LogEntry, fraction::Fraction, percentage::Pct, schedule, state::State, summary, DB,
+    debug::LogEntry, fraction::Fraction, metrics, percentage::Pct, reconcile, schedule,
+    state::State, summary, team, upstream, API_BASE, DB,
 };
-use anyhow::Result;
-use derive_more::{Add, AddAssign, Sum};
+use anyhow::{ensure, Context, Result};
+use chrono::{DateTime, Utc};
+use derive_more::{Add, AddAssign, SubAssign, Sum};
 use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sled::transaction::{ConflictableTransactionError, Transactional};
 use std::collections::{BTreeMap, HashMap};
-use std::mem::size_of_val;
 use uuid::Uuid;
 
 lazy_static::lazy_static! {
@@ -22,22 +35,303 @@ lazy_static::lazy_static! {
 
 pub const DEBUG_TREE: &str = "debug_v1";
 pub const GAME_STATS_TREE: &str = "game_stats_v3";
+/// Games recorded by `process_score_only`, which are not reprocessed unless forced.
+pub const SCORE_ONLY_TREE: &str = "score_only_games_v1";
 
 pub async fn process(season: Season, id: Uuid, force: bool) -> Result<bool> {
+    let result = process_inner(season, id, force).await;
+    match result {
+        Ok(true) => metrics::GAMES_PROCESSED.inc(),
+        Ok(false) => {}
+        Err(_) => metrics::PROCESSING_FAILURES.inc(),
+    }
+    result
+}
+
+/// Records only the final score of a game whose feed couldn't be processed, from the game object in
+/// the Blaseball API. This puts the game in its teams' schedules (and the season on the index),
+/// without any stats.
+pub async fn process_score_only(season: Season, id: Uuid) -> Result<()> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ApiGame {
+        day: u16,
+        is_postseason: bool,
+        away_team: Uuid,
+        home_team: Uuid,
+        away_score: f64,
+        home_score: f64,
+        game_complete: bool,
+    }
+
+    let game: ApiGame = upstream::get(format!("{}/database/gameById/{}", API_BASE, id))
+        .await?
+        .json()?;
+    ensure!(game.game_complete, "game {} is not complete", id);
+
+    let now = Utc::now();
+    let mut teams = Vec::new();
+    for (team_id, score, opponent_score) in [
+        (game.away_team, game.away_score, game.home_score),
+        (game.home_team, game.home_score, game.away_score),
+    ] {
+        let name = match names::team_name(team_id)? {
+            Some(name) => name,
+            None => {
+                let team = team::load(team_id, now)
+                    .await?
+                    .with_context(|| format!("team {} not found", team_id))?;
+                TeamName {
+                    name: team.full_name,
+                    nickname: team.nickname,
+                    shorthand: team.shorthand,
+                    emoji: team.emoji,
+                }
+            }
+        };
+        teams.push((team_id, name, score, opponent_score));
+    }
+
+    let schedule_tree = DB.open_tree(schedule::TREE)?;
+    let schedule_day_tree = DB.open_tree(schedule::DAY_TREE)?;
+    let names_tree = DB.open_tree(names::TREE)?;
+    let recorded_tree = DB.open_tree(seasons::RECORDED_TREE)?;
+    let score_only_tree = DB.open_tree(SCORE_ONLY_TREE)?;
+    (
+        &schedule_tree,
+        &schedule_day_tree,
+        &names_tree,
+        &recorded_tree,
+        &score_only_tree,
+    )
+        .transaction(
+            |(schedule_tree, schedule_day_tree, names_tree, recorded_tree, score_only_tree)| {
+                for (i, (team_id, name, score, opponent_score)) in teams.iter().enumerate() {
+                    let (opponent_id, opponent, _, _) = &teams[1 - i];
+                    names_tree.insert(
+                        team_id.as_bytes(),
+                        serde_json::to_vec(name).map_err(ConflictableTransactionError::Abort)?,
+                    )?;
+                    let game_index =
+                        schedule::find_game_index(schedule_tree, &season, *team_id, game.day, id)?;
+                    schedule_tree.insert(
+                        schedule::entry_key(&season, *team_id, game.day, game_index),
+                        serde_json::to_vec(&schedule::Entry {
+                            id,
+                            day: game.day,
+                            game_index,
+                            kind: if game.is_postseason {
+                                Kind::Postseason
+                            } else {
+                                Kind::Regular
+                            },
+                            home: i == 1,
+                            opponent: opponent.clone(),
+                            opponent_id: *opponent_id,
+                            won: score > opponent_score,
+                            weather_wins: 0,
+                            // negative and fractional scores are possible, but not in this schema
+                            score: score.max(0.0).round() as u16,
+                            opponent_score: opponent_score.max(0.0).round() as u16,
+                            lineup: Vec::new(),
+                            start: None,
+                            end: None,
+                        })
+                        .map_err(ConflictableTransactionError::Abort)?,
+                    )?;
+                    if i == 1 {
+                        schedule_day_tree.insert(
+                            schedule::day_index_key(&season, *team_id, game.day, game_index),
+                            Vec::new(),
+                        )?;
+                    }
+                }
+                recorded_tree.insert(season.recorded_key(), Vec::new())?;
+                score_only_tree.insert(id.as_bytes(), Vec::new())?;
+                Ok(())
+            },
+        )?;
+    Ok(())
+}
+
+/// Runs the state machine over the feed for a game in progress without writing anything to the
+/// database. Returns the partial game and whether the feed has reached the end of the game.
+pub async fn process_live(season: Season, id: Uuid) -> Result<Option<(Game, bool)>> {
+    let feed = crate::feed::load_live(id).await?;
+    if feed.is_empty() {
+        return Ok(None);
+    }
+
+    let mut state = State::new(season, id);
+    for event in feed {
+        if let Err(err) = state.push(&event).await {
+            log::warn!("stopped processing live game {}: {:#}", id, err);
+            break;
+        }
+    }
+    let finished = state.is_finished();
+    Ok(Some((state.into_partial(), finished)))
+}
+
+/// Removes a previously-processed game and processes it again, without requiring a rebuild.
+pub async fn reprocess(season: Season, id: Uuid) -> Result<bool> {
+    remove(id)?;
+    process(season, id, true).await
+}
+
+/// Removes a previously-processed game's stats, so that it can be processed again. A buried game's
+/// derived data is already gone, so only its box score is removed.
+pub fn remove(id: Uuid) -> Result<()> {
+    if tombstone::load(id)?.is_some() {
+        DB.open_tree(GAME_STATS_TREE)?.remove(id.as_bytes())?;
+        return Ok(());
+    }
+    unwind(id, false)
+}
+
+/// Takes a processed game out of summaries, schedules, and the other trees built from it, keeping
+/// its box score, and records a tombstone for it (see `tombstone`).
+pub fn bury(id: Uuid) -> Result<()> {
+    unwind(id, true)
+}
+
+/// Reverses everything written for a processed game. The box score is removed too, unless the game
+/// is being buried, in which case its schedule entries are removed and a tombstone is written.
+fn unwind(id: Uuid, bury: bool) -> Result<()> {
+    let game_stats_tree = DB.open_tree(GAME_STATS_TREE)?;
+    let schedule_tree = DB.open_tree(schedule::TREE)?;
+    let schedule_day_tree = DB.open_tree(schedule::DAY_TREE)?;
+    let tombstone_tree = DB.open_tree(tombstone::TREE)?;
+    let delta_tree = DB.open_tree(summary::DELTA_TREE)?;
+    let matchup_tree = DB.open_tree(matchup::TREE)?;
+    let decisions_tree = DB.open_tree(decisions::TREE)?;
+    let inhabiting_tree = DB.open_tree(inhabiting::TREE)?;
+    let daily_tree = DB.open_tree(summary::DAILY_TREE)?;
+    let milestones_tree = DB.open_tree(milestones::TREE)?;
+    let opponents_tree = DB.open_tree(opponents::TREE)?;
+
+    if let Some(value) = game_stats_tree.get(id.as_bytes())? {
+        let game: Game = serde_json::from_slice(&value)?;
+        let tombstone = match tombstone::load(id)? {
+            Some(tombstone) => tombstone,
+            None => Tombstone {
+                season: game.season.clone(),
+                day: game.day,
+                removed_at: Utc::now(),
+            },
+        };
+        (
+            &game_stats_tree,
+            &schedule_tree,
+            &schedule_day_tree,
+            &tombstone_tree,
+            &delta_tree,
+            &matchup_tree,
+            &decisions_tree,
+            &inhabiting_tree,
+            &daily_tree,
+            &milestones_tree,
+            &opponents_tree,
+        )
+            .transaction(
+                |(
+                    game_stats_tree,
+                    schedule_tree,
+                    schedule_day_tree,
+                    tombstone_tree,
+                    delta_tree,
+                    matchup_tree,
+                    decisions_tree,
+                    inhabiting_tree,
+                    daily_tree,
+                    milestones_tree,
+                    opponents_tree,
+                )| {
+                    summary::remove_summary(delta_tree, &game)?;
+                    summary::remove_daily(daily_tree, &game, id)?;
+                    matchup::remove_matchups(matchup_tree, &game, id)?;
+                    decisions::remove_decisions(decisions_tree, &game, id)?;
+                    inhabiting::remove_inhabiting(inhabiting_tree, &game, id)?;
+                    milestones::remove_milestones(milestones_tree, &game, id)?;
+                    opponents::remove_opponents(opponents_tree, &game, id)?;
+                    if bury {
+                        for team in game.teams() {
+                            let game_index = schedule::find_game_index(
+                                schedule_tree,
+                                &game.season,
+                                team.id,
+                                game.day,
+                                id,
+                            )?;
+                            schedule_tree.remove(schedule::entry_key(
+                                &game.season,
+                                team.id,
+                                game.day,
+                                game_index,
+                            ))?;
+                            if team.id == game.home.id {
+                                schedule_day_tree.remove(schedule::day_index_key(
+                                    &game.season,
+                                    team.id,
+                                    game.day,
+                                    game_index,
+                                ))?;
+                            }
+                        }
+                        tombstone_tree.insert(
+                            id.as_bytes(),
+                            serde_json::to_vec(&tombstone)
+                                .map_err(ConflictableTransactionError::Abort)?,
+                        )?;
+                    } else {
+                        game_stats_tree.remove(id.as_bytes())?;
+                    }
+                    Ok(())
+                },
+            )?;
+        reconcile::clear(id)?;
+        summary::FOLD.notify_one();
+    }
+    Ok(())
+}
+
+pub fn load(id: Uuid) -> Result<Option<Game>> {
+    let tree = DB.open_tree(GAME_STATS_TREE)?;
+    Ok(match tree.get(id.as_bytes())? {
+        Some(value) => Some(serde_json::from_slice(&value)?),
+        None => None,
+    })
+}
+
+async fn process_inner(season: Season, id: Uuid, force: bool) -> Result<bool> {
     let game_stats_tree = DB.open_tree(GAME_STATS_TREE)?;
-    if force || !game_stats_tree.contains_key(id.as_bytes())? {
+    let score_only_tree = DB.open_tree(SCORE_ONLY_TREE)?;
+    if force
+        || !(game_stats_tree.contains_key(id.as_bytes())?
+            || score_only_tree.contains_key(id.as_bytes())?)
+    {
         let debug_tree = DB.open_tree(DEBUG_TREE)?;
-        let summary_tree = DB.open_tree(summary::TREE)?;
-        let season_summary_tree = DB.open_tree(summary::SEASON_TREE)?;
+        let delta_tree = DB.open_tree(summary::DELTA_TREE)?;
         let names_tree = DB.open_tree(names::TREE)?;
+        let name_history_tree = DB.open_tree(names::HISTORY_TREE)?;
         let common_names_tree = DB.open_tree(names::COMMON_TREE)?;
         let recorded_tree = DB.open_tree(seasons::RECORDED_TREE)?;
         let schedule_tree = DB.open_tree(schedule::TREE)?;
+        let schedule_day_tree = DB.open_tree(schedule::DAY_TREE)?;
+        let matchup_tree = DB.open_tree(matchup::TREE)?;
+        let decisions_tree = DB.open_tree(decisions::TREE)?;
+        let inhabiting_tree = DB.open_tree(inhabiting::TREE)?;
+        let daily_tree = DB.open_tree(summary::DAILY_TREE)?;
+        let milestones_tree = DB.open_tree(milestones::TREE)?;
+        let opponents_tree = DB.open_tree(opponents::TREE)?;
 
         let mut state = State::new(season, id);
         let mut debug_log = Vec::new();
         let mut old = Value::default();
         let feed = crate::feed::load(id).await?;
+        let event_count = feed.len();
+        let start = feed.first().map(|event| event.created);
+        let end = feed.last().map(|event| event.created);
         for event in feed {
             match state.push(&event).await {
                 Ok(()) => {
@@ -45,6 +339,7 @@ pub async fn process(season: Season, id: Uuid, force: bool) -> Result<bool> {
                         let new = serde_json::to_value(&state)?;
                         debug_log.push(LogEntry::Ok {
                             description: event.description,
+                            ty: Some(event.ty),
                             patch: json_patch::diff(&old, &new),
                         });
                         old = new;
@@ -53,6 +348,7 @@ pub async fn process(season: Season, id: Uuid, force: bool) -> Result<bool> {
                 Err(err) => {
                     debug_log.push(LogEntry::Err {
                         description: Some(event.description),
+                        ty: Some(event.ty),
                         error: format!("{:?}", err),
                     });
                     debug_tree.insert(id.as_bytes(), serde_json::to_vec(&debug_log)?.as_slice())?;
@@ -61,11 +357,12 @@ pub async fn process(season: Season, id: Uuid, force: bool) -> Result<bool> {
                 }
             }
         }
-        let game = match state.finish() {
+        let mut game = match state.finish() {
             Ok(game) => game,
             Err(err) => {
                 debug_log.push(LogEntry::Err {
                     description: None,
+                    ty: None,
                     error: format!("{:?}", err),
                 });
                 debug_tree.insert(id.as_bytes(), serde_json::to_vec(&debug_log)?.as_slice())?;
@@ -74,25 +371,44 @@ pub async fn process(season: Season, id: Uuid, force: bool) -> Result<bool> {
             }
         };
         debug_tree.insert(id.as_bytes(), serde_json::to_vec(&debug_log)?.as_slice())?;
+        game.series = schedule::series(&game)?;
+        game.stars = game.stars();
+        game.start = start;
+        game.end = end;
+        game.event_count = event_count;
 
         (
             &game_stats_tree,
-            &summary_tree,
-            &season_summary_tree,
+            &delta_tree,
             &names_tree,
+            &name_history_tree,
             &common_names_tree,
             &recorded_tree,
             &schedule_tree,
+            &schedule_day_tree,
+            &matchup_tree,
+            &decisions_tree,
+            &inhabiting_tree,
+            &daily_tree,
+            &milestones_tree,
+            &opponents_tree,
         )
             .transaction(
                 |(
                     game_stats_tree,
-                    summary_tree,
-                    season_summary_tree,
+                    delta_tree,
                     names_tree,
+                    name_history_tree,
                     common_names_tree,
                     recorded_tree,
                     schedule_tree,
+                    schedule_day_tree,
+                    matchup_tree,
+                    decisions_tree,
+                    inhabiting_tree,
+                    daily_tree,
+                    milestones_tree,
+                    opponents_tree,
                 )| {
                     for team in game.teams() {
                         names_tree.insert(
@@ -102,6 +418,7 @@ pub async fn process(season: Season, id: Uuid, force: bool) -> Result<bool> {
                         )?;
                         for (id, name) in &team.player_names {
                             names_tree.insert(id.as_bytes(), name.as_bytes())?;
+                            names::write_history(name_history_tree, *id, name, &game.season)?;
                         }
 
                         let mut common_key = Vec::new();
@@ -110,31 +427,59 @@ pub async fn process(season: Season, id: Uuid, force: bool) -> Result<bool> {
                         common_key.extend_from_slice(game.season.sim.as_bytes());
                         common_names_tree.insert(common_key, team.id.as_bytes())?;
 
-                        let mut schedule_key = Vec::new();
-                        schedule_key.extend_from_slice(game.season.sim.as_bytes());
-                        schedule_key.extend_from_slice(&game.season.season.to_ne_bytes());
-                        schedule_key.extend_from_slice(team.id.as_bytes());
-                        schedule_key.extend_from_slice(&game.day.to_be_bytes());
-
                         let opponent = game.opponent(team.id);
+                        let game_index = schedule::find_game_index(
+                            schedule_tree,
+                            &game.season,
+                            team.id,
+                            game.day,
+                            id,
+                        )?;
                         schedule_tree.insert(
-                            schedule_key.as_slice(),
+                            schedule::entry_key(&game.season, team.id, game.day, game_index),
                             serde_json::to_vec(&schedule::Entry {
                                 id,
                                 day: game.day,
+                                game_index,
                                 kind: game.kind,
                                 home: game.home.id == team.id,
                                 opponent: opponent.name.clone(),
+                                opponent_id: opponent.id,
                                 won: game.winner().id == team.id,
+                                weather_wins: team.weather_wins,
                                 score: team.runs(),
                                 opponent_score: opponent.runs(),
+                                lineup: team
+                                    .lineup
+                                    .iter()
+                                    .filter_map(|position| position.first().copied())
+                                    .collect(),
+                                start,
+                                end,
                             })
                             .map_err(ConflictableTransactionError::Abort)?
                             .as_slice(),
                         )?;
+                        if game.home.id == team.id {
+                            schedule_day_tree.insert(
+                                schedule::day_index_key(
+                                    &game.season,
+                                    team.id,
+                                    game.day,
+                                    game_index,
+                                ),
+                                Vec::new(),
+                            )?;
+                        }
                     }
 
-                    summary::write_summary(summary_tree, season_summary_tree, &game)?;
+                    summary::write_summary(delta_tree, &game)?;
+                    summary::write_daily(daily_tree, &game, id)?;
+                    matchup::write_matchups(matchup_tree, &game, id)?;
+                    decisions::write_decisions(decisions_tree, &game, id)?;
+                    inhabiting::write_inhabiting(inhabiting_tree, &game, id)?;
+                    milestones::write_milestones(milestones_tree, &game, id)?;
+                    opponents::write_opponents(opponents_tree, &game, id)?;
 
                     game_stats_tree.insert(
                         id.as_bytes(),
@@ -143,16 +488,18 @@ pub async fn process(season: Season, id: Uuid, force: bool) -> Result<bool> {
                             .as_slice(),
                     )?;
 
-                    let mut key = Vec::with_capacity(
-                        game.season.sim.len() + size_of_val(&game.season.season),
-                    );
-                    key.extend_from_slice(game.season.sim.as_bytes());
-                    key.extend_from_slice(&game.season.season.to_be_bytes());
-                    recorded_tree.insert(key, Vec::new())?;
+                    recorded_tree.insert(game.season.recorded_key(), Vec::new())?;
 
                     Ok(())
                 },
             )?;
+        score_only_tree.remove(id.as_bytes())?;
+        reconcile::check(&game, id)?;
+        if tombstone::load(id)?.is_some() {
+            // reprocessing a buried game (from the admin dashboard, say) shouldn't bring it back
+            bury(id)?;
+        }
+        summary::FOLD.notify_one();
 
         Ok(true)
     } else {
@@ -171,6 +518,32 @@ pub struct Game {
     pub home: Team,
     #[serde(default)]
     pub weather: u16,
+    /// Name of the home team's stadium, if they had one
+    #[serde(default)]
+    pub stadium: Option<String>,
+    #[serde(default)]
+    pub series: Option<Series>,
+    #[serde(default)]
+    pub matchups: Vec<Matchup>,
+    /// The top three performers in the game, best first
+    #[serde(default)]
+    pub stars: Vec<Uuid>,
+    #[serde(default)]
+    pub win_probability: Vec<WinProbability>,
+    #[serde(default)]
+    pub inhabiting: Vec<Inhabiting>,
+    /// When the first and last events in the game's feed were created
+    #[serde(default)]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub end: Option<DateTime<Utc>>,
+    /// Number of events in the game's feed
+    #[serde(default)]
+    pub event_count: usize,
+    #[serde(default)]
+    pub milestones: Vec<Milestone>,
+    #[serde(default)]
+    pub highlights: Vec<Highlight>,
 }
 
 impl Game {
@@ -209,6 +582,77 @@ impl Game {
     pub fn is_postseason(&self) -> bool {
         self.kind == Kind::Postseason
     }
+
+    /// Picks the top three performers by `Stats::game_score`.
+    pub fn stars(&self) -> Vec<Uuid> {
+        let mut scores = self
+            .teams()
+            .flat_map(|team| team.stats.iter())
+            .map(|(id, stats)| (stats.game_score(), *id))
+            .collect::<Vec<_>>();
+        scores.sort_unstable_by(|a, b| b.cmp(a));
+        scores.into_iter().take(3).map(|(_, id)| id).collect()
+    }
+
+    pub fn series_line(&self) -> Option<String> {
+        let series = self.series?;
+        let standing = match series.away_wins.cmp(&series.home_wins) {
+            std::cmp::Ordering::Equal if series.game_number == 1 => String::new(),
+            std::cmp::Ordering::Equal => {
+                format!(", series tied {}-{}", series.away_wins, series.home_wins)
+            }
+            std::cmp::Ordering::Greater => format!(
+                ", {} leads series {}-{}",
+                self.away.name.shorthand, series.away_wins, series.home_wins
+            ),
+            std::cmp::Ordering::Less => format!(
+                ", {} leads series {}-{}",
+                self.home.name.shorthand, series.home_wins, series.away_wins
+            ),
+        };
+        Some(format!(
+            "Postseason Game {}{} ({} has home-field advantage)",
+            series.game_number,
+            standing,
+            self.teams()
+                .find(|team| team.id == series.home_field)
+                .map(|team| team.name.shorthand.as_str())
+                .unwrap_or_default(),
+        ))
+    }
+
+    /// Wall-clock time from the first event in the feed to the last, as hours and minutes.
+    pub fn time_of_game(&self) -> Option<String> {
+        let minutes = (self.end? - self.start?).num_minutes();
+        Some(format!("{}:{:02}", minutes / 60, minutes % 60))
+    }
+
+    pub fn weather_name(&self) -> String {
+        crate::weather::name(self.weather)
+    }
+
+    /// Notes for wins set or swallowed by the weather during this game.
+    pub fn weather_lines(&self) -> Vec<String> {
+        self.teams()
+            .filter(|team| team.weather_wins != 0)
+            .map(|team| {
+                let count = team.weather_wins.unsigned_abs();
+                let wins = if count == 1 {
+                    "a Win".into()
+                } else {
+                    format!("{} Wins", count)
+                };
+                if team.weather_wins > 0 {
+                    format!("The Sun 2 set {} upon the {}", wins, team.name.nickname)
+                } else {
+                    format!(
+                        "The Black Hole swallowed {} from the {}",
+                        wins, team.name.nickname
+                    )
+                }
+            })
+            .collect()
+    }
 }
 
 impl<'a> IntoIterator for &'a Game {
@@ -220,6 +664,16 @@ impl<'a> IntoIterator for &'a Game {
     }
 }
 
+/// A postseason game's position within its series, as of the start of the game.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct Series {
+    pub game_number: u16,
+    /// The home team for game 1 of the series
+    pub home_field: Uuid,
+    pub away_wins: u16,
+    pub home_wins: u16,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Kind {
     /// This game affects regular season standings
@@ -252,7 +706,14 @@ pub struct Team {
 
     pub stats: IndexMap<Uuid, Stats>,
     pub inning_runs: BTreeMap<u16, u16>,
+    pub inning_hits: BTreeMap<u16, u16>,
+    /// Fielding errors committed by this team while on defense
+    pub errors: u16,
     pub left_on_base: usize,
+    /// Wins set upon this team by Sun 2 (positive) or swallowed by a Black Hole (negative)
+    pub weather_wins: i16,
+    /// Runs taken off the scoreboard when those wins were set or swallowed
+    pub runs_cleared: u16,
 
     pub crisp: IndexSet<Uuid>,
 }
@@ -262,6 +723,12 @@ impl Team {
         self.inning_runs.values().sum()
     }
 
+    /// Runs on the scoreboard, which Sun 2 and Black Holes clear without taking away from the
+    /// runs the team scored.
+    pub fn score(&self) -> u16 {
+        self.runs().saturating_sub(self.runs_cleared)
+    }
+
     pub fn hits(&self) -> u32 {
         self.stats.values().map(|s| s.hits()).sum()
     }
@@ -302,12 +769,15 @@ impl Team {
     Serialize,
     Add,
     AddAssign,
+    SubAssign,
     Sum,
 )]
 #[serde(default)]
 pub struct Stats {
     pub games_batted: u32,
     pub games_pitched: u32,
+    /// Games the player entered after the first pitch, replacing someone else in the lineup
+    pub games_as_substitute: u32,
 
     // Batting stats
     pub plate_appearances: u32,
@@ -323,9 +793,25 @@ pub struct Stats {
     pub sacrifices: u32,
     pub stolen_bases: u32,
     pub caught_stealing: u32,
+    /// Steals and caught stealing by the base being stolen. Home is fourth base; steals of any
+    /// other base (e.g. fifth base) only count toward the totals.
+    pub stolen_bases_second: u32,
+    pub stolen_bases_third: u32,
+    pub stolen_bases_home: u32,
+    pub caught_stealing_second: u32,
+    pub caught_stealing_third: u32,
+    pub caught_stealing_home: u32,
     pub strike_outs: u32,
+    pub strike_outs_looking: u32,
+    pub strike_outs_swinging: u32,
     pub double_plays_grounded_into: u32,
     pub walks: u32,
+    pub pitches_seen: u32,
+    /// Called strikes, including strikeouts looking
+    pub strikes_taken: u32,
+    /// Swinging strikes, including strikeouts swinging
+    pub swinging_strikes: u32,
+    pub foul_balls: u32,
     pub left_on_base: usize,
 
     // Pitching stats
@@ -338,17 +824,32 @@ pub struct Stats {
     pub no_hitters: u32,
     pub perfect_games: u32,
     pub saves: u32,
+    pub holds: u32,
+    pub blown_saves: u32,
+    /// Runners on base when this pitcher entered in relief
+    pub inherited_runners: u32,
+    /// Inherited runners who scored, charged to the pitchers who allowed them
+    pub inherited_runners_scored: u32,
     pub batters_faced: u32,
     pub outs_recorded: u32,
     pub hits_allowed: u32,
     pub home_runs_allowed: u32,
     pub earned_runs: u32,
+    pub unearned_runs: u32,
     pub struck_outs: u32,
+    pub struck_outs_looking: u32,
+    pub struck_outs_swinging: u32,
     pub walks_issued: u32,
     pub strikes_pitched: u32,
+    pub called_strikes_pitched: u32,
     pub balls_pitched: u32,
     pub flyouts_pitched: u32,
     pub groundouts_pitched: u32,
+    pub lineup_slot_splits: Splits,
+
+    pub fielding: FieldingStats,
+    #[serde(skip_serializing_if = "RunExpectancy::is_empty")]
+    pub run_expectancy: RunExpectancy,
 }
 
 impl Stats {
@@ -360,6 +861,18 @@ impl Stats {
         self.strikes_pitched + self.balls_pitched > 0
     }
 
+    /// A rough measure of a player's contribution to a single game, across batting and pitching.
+    pub fn game_score(&self) -> i64 {
+        let batting = 2 * i64::from(
+            self.total_bases() + self.runs + self.runs_batted_in + self.walks + self.stolen_bases,
+        ) - 2 * i64::from(self.caught_stealing)
+            - i64::from(self.at_bats - self.hits());
+        let pitching = i64::from(self.outs_recorded + 2 * self.struck_outs)
+            - 2 * i64::from(self.hits_allowed + self.walks_issued)
+            - 4 * i64::from(self.runs_allowed());
+        batting + pitching
+    }
+
     pub fn hits(&self) -> u32 {
         self.singles + self.doubles + self.triples + self.home_runs
     }
@@ -401,10 +914,68 @@ impl Stats {
         Pct(pct * 100.into())
     }
 
+    /// Weighted on-base average, using linear weights from the league totals.
+    pub fn woba(&self, league: Stats) -> Pct<3> {
+        linear_weights::to_pct(Weights::new(&league).woba(self), 1000)
+    }
+
+    pub fn wrc_plus(&self, league: Stats) -> Pct<0> {
+        linear_weights::to_pct(Weights::new(&league).wrc_plus(self), 1)
+    }
+
+    /// Saves plus blown saves. Holds aren't counted, since a pitcher who holds the lead wasn't in
+    /// position to finish the game.
+    pub fn save_opportunities(&self) -> u32 {
+        self.saves + self.blown_saves
+    }
+
     pub fn win_loss_percentage(&self) -> Pct<3> {
         Pct::new(self.wins, self.wins + self.losses)
     }
 
+    pub fn pitches_per_plate_appearance(&self) -> Pct<2> {
+        Pct::new(self.pitches_seen, self.plate_appearances)
+    }
+
+    /// Swings that didn't put the ball in play. The feed doesn't say which pitch of a plate
+    /// appearance was hit, so contact on balls in play isn't counted.
+    pub fn swings(&self) -> u32 {
+        self.swinging_strikes + self.foul_balls
+    }
+
+    /// At bats by opposing batters. Sacrifices are not tracked for pitchers, so they are counted
+    /// here as at bats.
+    pub fn opponents_at_bats(&self) -> u32 {
+        self.batters_faced.saturating_sub(self.walks_issued)
+    }
+
+    pub fn opponents_batting_average(&self) -> Pct<3> {
+        Pct::new(self.hits_allowed, self.opponents_at_bats())
+    }
+
+    pub fn opponents_on_base_percentage(&self) -> Pct<3> {
+        Pct::new(self.hits_allowed + self.walks_issued, self.batters_faced)
+    }
+
+    /// The share of balls put into play by opposing batters that the defense turned into outs.
+    pub fn defensive_efficiency(&self) -> Pct<3> {
+        let in_play = self
+            .opponents_at_bats()
+            .saturating_sub(self.struck_outs + self.home_runs_allowed);
+        Pct::new(
+            in_play.saturating_sub(self.hits_allowed - self.home_runs_allowed),
+            in_play,
+        )
+    }
+
+    pub fn runs_allowed(&self) -> u32 {
+        self.earned_runs + self.unearned_runs
+    }
+
+    pub fn run_average(&self) -> Pct<2> {
+        Pct::new(self.runs_allowed() * 27, self.outs_recorded)
+    }
+
     pub fn earned_run_average(&self) -> Pct<2> {
         Pct::new(self.earned_runs * 27, self.outs_recorded)
     }
@@ -440,6 +1011,13 @@ impl Stats {
         Pct::new(self.struck_outs, self.walks_issued)
     }
 
+    pub fn called_strike_percentage(&self) -> Pct<1> {
+        Pct::new(
+            self.called_strikes_pitched * 100,
+            self.strikes_pitched + self.balls_pitched,
+        )
+    }
+
     pub fn era_plus(&self, league: Stats) -> Pct<0> {
         let pct = league.earned_run_average().0 / self.earned_run_average().0;
         Pct(pct * 100.into())
@@ -453,6 +1031,18 @@ impl Stats {
         )
     }
 
+    /// Runs added over the league's expectancy in this player's plate appearances.
+    pub fn batting_re24(&self, league: Stats) -> Pct<1> {
+        self.run_expectancy
+            .batting_re24(&league.run_expectancy.matrix())
+    }
+
+    /// Runs prevented below the league's expectancy in plate appearances against this pitcher.
+    pub fn pitching_re24(&self, league: Stats) -> Pct<1> {
+        self.run_expectancy
+            .pitching_re24(&league.run_expectancy.matrix())
+    }
+
     pub fn fielding_independent_pitching(&self, league: Stats) -> Pct<2> {
         let c = league.earned_run_average().0 - league.fip_base();
         Pct(self.fip_base() + c)
@@ -470,6 +1060,17 @@ impl Stats {
         }
     }
 
+    pub fn inherited_runners_scored(&self) -> String {
+        if self.inherited_runners > 0 {
+            format!(
+                "{}-{}",
+                self.inherited_runners, self.inherited_runners_scored
+            )
+        } else {
+            String::new()
+        }
+    }
+
     pub fn groundouts_flyouts(&self) -> String {
         if self.groundouts_pitched + self.flyouts_pitched > 0 {
             format!("{}-{}", self.groundouts_pitched, self.flyouts_pitched)
@@ -0,0 +1,93 @@
+//! Which glossary entry defines each column abbreviation, so that table headers can link to the
+//! glossary. Tables are matched by abbreviation alone, so an abbreviation should mean the same
+//! thing everywhere it's used (`R` is runs scored on batting tables and runs allowed on pitching
+//! tables, which the glossary covers in one entry).
+
+/// (abbreviation, glossary entry ID)
+const ENTRIES: &[(&str, &str)] = &[
+    ("AB", "ab"),
+    ("BA", "ba"),
+    ("BABIP", "babip"),
+    ("BB", "bb"),
+    ("BF", "bf"),
+    ("BSV", "bsv"),
+    ("CS", "cs"),
+    ("CS2", "cs"),
+    ("CS3", "cs"),
+    ("CSH", "cs"),
+    ("DER", "der"),
+    ("ER", "era"),
+    ("ERA", "era"),
+    ("ERA+", "eraplus"),
+    ("FIP", "fip"),
+    ("G", "g"),
+    ("GIDP", "gidp"),
+    ("H", "h"),
+    ("H/G", "pergame"),
+    ("HLD", "hld"),
+    ("HR/G", "pergame"),
+    ("IP", "ip"),
+    ("L", "w"),
+    ("LOB", "lob"),
+    ("OBP", "obp"),
+    ("OPS", "ops"),
+    ("OPS+", "opsplus"),
+    ("P/PA", "ppa"),
+    ("PA", "pa"),
+    ("R", "r"),
+    ("R/G", "pergame"),
+    ("RA", "r"),
+    ("RA/G", "pergame"),
+    ("RBI", "rbi"),
+    ("RE24", "re24"),
+    ("SAC", "sac"),
+    ("SB", "sb"),
+    ("SB2", "sb"),
+    ("SB3", "sb"),
+    ("SBH", "sb"),
+    ("SHO", "sho"),
+    ("SLG", "slg"),
+    ("SO", "so"),
+    ("SO/G", "pergame"),
+    ("SOL", "so"),
+    ("SV", "sv"),
+    ("SVO", "sv"),
+    ("Sub", "sub"),
+    ("TB", "tb"),
+    ("W", "w"),
+    ("W-L%", "w"),
+    ("WHIP", "whip"),
+    ("aERA", "aera"),
+    ("oOPS", "aera"),
+    ("wOBA", "woba"),
+    ("wRC+", "wrcplus"),
+];
+
+/// Returns the glossary entry ID for a column abbreviation, if the glossary defines it.
+pub fn anchor(abbr: &str) -> Option<&'static str> {
+    ENTRIES
+        .binary_search_by_key(&abbr, |(abbr, _)| abbr)
+        .ok()
+        .map(|index| ENTRIES[index].1)
+}
+
+#[cfg(test)]
+#[test]
+fn test_entries() {
+    let glossary = include_str!("../templates/glossary.html");
+    assert!(
+        ENTRIES.windows(2).all(|w| w[0].0 < w[1].0),
+        "entries not sorted"
+    );
+    for (abbr, id) in ENTRIES {
+        assert_eq!(
+            glossary.matches(&format!("id=\"{}\"", id)).count(),
+            1,
+            "glossary entry {} for {} missing or duplicated",
+            id,
+            abbr
+        );
+    }
+    assert_eq!(anchor("ERA+"), Some("eraplus"));
+    assert_eq!(anchor("2B"), None);
+}
@@ -0,0 +1,172 @@
+//! Calendar-style strips of a player's regular season games, one square per day, shaded by how
+//! well they played.
+
+use crate::game::Kind;
+use crate::routes::game::rocket_uri_macro_game;
+use crate::schedule;
+use crate::seasons::Season;
+use crate::summary::Daily;
+use anyhow::Result;
+use rocket::uri;
+use uuid::Uuid;
+
+/// Square colors, from a poor game to a great one.
+const LEVELS: [&str; 5] = [
+    "bg-gray-300 dark:bg-gray-600",
+    "bg-green-200 dark:bg-green-900",
+    "bg-green-400 dark:bg-green-700",
+    "bg-green-600 dark:bg-green-500",
+    "bg-green-800 dark:bg-green-300",
+];
+const EMPTY: &str = "bg-gray-100 dark:bg-gray-800";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Batting,
+    Pitching,
+}
+
+pub struct Strip {
+    pub season: String,
+    pub cells: Vec<Cell>,
+}
+
+pub struct Cell {
+    pub href: String,
+    pub title: String,
+    pub class: &'static str,
+}
+
+/// Builds a strip for each regular season the player batted (or pitched) in. Each strip runs
+/// through the last regular season day of the player's latest team that season.
+pub fn strips(rows: &[Daily], side: Side) -> Result<Vec<Strip>> {
+    let mut strips = Vec::new();
+    let mut seasons: Vec<Vec<&Daily>> = Vec::new();
+    for row in rows
+        .iter()
+        .filter(|row| row.kind == Kind::Regular && appeared(row, side))
+    {
+        match seasons.last_mut() {
+            Some(season_rows) if season_rows[0].season == row.season => season_rows.push(row),
+            _ => seasons.push(vec![row]),
+        }
+    }
+    for season_rows in seasons {
+        let last = season_rows[season_rows.len() - 1];
+        let days = season_days(last.team_id, &last.season)?.max(last.day + 1);
+        let mut cells = (0..days)
+            .map(|day| Cell {
+                href: String::new(),
+                title: format!("Day {}", day + 1),
+                class: EMPTY,
+            })
+            .collect::<Vec<_>>();
+        for row in season_rows {
+            let cell = &mut cells[usize::from(row.day)];
+            cell.href = uri!(game(id = row.game_id)).to_string();
+            cell.title = format!("Day {}: {}", row.day + 1, line(row, side));
+            cell.class = LEVELS[level(row, side)];
+        }
+        strips.push(Strip {
+            season: format!("{:#}", last.season),
+            cells,
+        });
+    }
+    Ok(strips)
+}
+
+fn season_days(team_id: Uuid, season: &Season) -> Result<u16> {
+    Ok(schedule::schedule(team_id, season)?
+        .iter()
+        .filter(|(_, entry)| entry.kind == Kind::Regular)
+        .map(|(_, entry)| entry.day + 1)
+        .max()
+        .unwrap_or_default())
+}
+
+fn appeared(row: &Daily, side: Side) -> bool {
+    match side {
+        Side::Batting => row.plate_appearances > 0,
+        Side::Pitching => row.is_pitching,
+    }
+}
+
+fn line(row: &Daily, side: Side) -> String {
+    let line = match side {
+        Side::Batting if row.home_runs > 0 => {
+            format!("{}-{}, {} HR", row.hits, row.at_bats, row.home_runs)
+        }
+        Side::Batting => format!("{}-{}", row.hits, row.at_bats),
+        Side::Pitching => format!(
+            "{}.{} IP, {} ER",
+            row.outs_recorded / 3,
+            row.outs_recorded % 3,
+            row.earned_runs
+        ),
+    };
+    if side == Side::Batting && row.substitute {
+        format!("{} (entered as a substitute)", line)
+    } else {
+        line
+    }
+}
+
+/// Batters are shaded by hits, with any home run game at the top. Pitchers are shaded by earned
+/// runs allowed, with six-inning starts at the top.
+fn level(row: &Daily, side: Side) -> usize {
+    match side {
+        Side::Batting if row.home_runs > 0 => 4,
+        Side::Batting => row.hits.min(3) as usize,
+        Side::Pitching => match (row.outs_recorded >= 18, row.earned_runs) {
+            (true, 0) => 4,
+            (true, 1..=3) => 3,
+            (false, 0..=1) => 2,
+            (_, 2..=3) => 1,
+            _ => 0,
+        },
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_level() {
+    let row = |hits, home_runs, outs_recorded, earned_runs| Daily {
+        game_id: Uuid::default(),
+        team_id: Uuid::default(),
+        season: Season {
+            sim: "thisidisstaticyo".into(),
+            season: 0,
+        },
+        day: 0,
+        kind: Kind::Regular,
+        plate_appearances: 4,
+        at_bats: 4,
+        hits,
+        home_runs,
+        is_pitching: outs_recorded > 0,
+        outs_recorded,
+        earned_runs,
+        substitute: false,
+    };
+
+    assert_eq!(level(&row(0, 0, 0, 0), Side::Batting), 0);
+    assert_eq!(level(&row(2, 0, 0, 0), Side::Batting), 2);
+    assert_eq!(level(&row(5, 0, 0, 0), Side::Batting), 3);
+    assert_eq!(level(&row(1, 1, 0, 0), Side::Batting), 4);
+    assert_eq!(line(&row(1, 1, 0, 0), Side::Batting), "1-4, 1 HR");
+    let substitute = Daily {
+        substitute: true,
+        ..row(1, 0, 0, 0)
+    };
+    assert_eq!(
+        line(&substitute, Side::Batting),
+        "1-4 (entered as a substitute)"
+    );
+
+    assert_eq!(level(&row(0, 0, 27, 0), Side::Pitching), 4);
+    assert_eq!(level(&row(0, 0, 18, 3), Side::Pitching), 3);
+    assert_eq!(level(&row(0, 0, 3, 1), Side::Pitching), 2);
+    assert_eq!(level(&row(0, 0, 12, 3), Side::Pitching), 1);
+    assert_eq!(level(&row(0, 0, 15, 6), Side::Pitching), 0);
+    assert_eq!(line(&row(0, 0, 17, 2), Side::Pitching), "5.2 IP, 2 ER");
+}
@@ -0,0 +1,140 @@
+//! Notable events in a game: home runs, incinerations, feedback and reverb shuffles, and big
+//! innings. Events are picked out of the feed as the game is processed, along with the score right
+//! after them; big innings are added from the line score when the game finishes.
+
+use crate::event_type::EventType;
+use crate::game::Game;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Half-innings with at least this many runs are listed as big innings.
+pub const BIG_INNING: u16 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    HomeRun,
+    Incineration,
+    Feedback,
+    Reverb,
+    BigInning,
+}
+
+impl Kind {
+    pub fn from_event(ty: EventType) -> Option<Kind> {
+        Some(match ty {
+            EventType::HomeRun => Kind::HomeRun,
+            EventType::Incineration => Kind::Incineration,
+            EventType::Feedback => Kind::Feedback,
+            EventType::ReverbShuffle
+            | EventType::ReverbLineupShuffle
+            | EventType::ReverbRotationShuffle => Kind::Reverb,
+            _ => return None,
+        })
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Kind::HomeRun => "Home Run",
+            Kind::Incineration => "Incineration",
+            Kind::Feedback => "Feedback",
+            Kind::Reverb => "Reverb",
+            Kind::BigInning => "Big Inning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Highlight {
+    pub inning: u16,
+    pub top_of_inning: bool,
+    pub kind: Kind,
+    pub description: String,
+    /// The score right after the event
+    pub away_runs: u16,
+    pub home_runs: u16,
+}
+
+impl Highlight {
+    pub fn half(&self) -> &'static str {
+        if self.top_of_inning {
+            "Top"
+        } else {
+            "Bottom"
+        }
+    }
+}
+
+/// Adds the game's big innings after any other highlights in the same half-inning, and sorts the
+/// highlights by half-inning.
+pub fn finish(game: &mut Game) {
+    for (inning, &runs) in &game.away.inning_runs {
+        if runs >= BIG_INNING {
+            let highlight = big_inning(game, *inning, true, runs);
+            game.highlights.push(highlight);
+        }
+    }
+    for (inning, &runs) in &game.home.inning_runs {
+        if runs >= BIG_INNING {
+            let highlight = big_inning(game, *inning, false, runs);
+            game.highlights.push(highlight);
+        }
+    }
+    // stable, so events stay in feed order and big innings end their half-inning
+    game.highlights
+        .sort_by_key(|highlight| (highlight.inning, !highlight.top_of_inning));
+}
+
+fn big_inning(game: &Game, inning: u16, top_of_inning: bool, runs: u16) -> Highlight {
+    let through = |runs: &BTreeMap<u16, u16>, last: u16| -> u16 {
+        runs.range(..=last).map(|(_, runs)| runs).sum()
+    };
+    let team = if top_of_inning {
+        &game.away
+    } else {
+        &game.home
+    };
+    Highlight {
+        inning,
+        top_of_inning,
+        kind: Kind::BigInning,
+        description: format!("The {} scored {} runs.", team.name.nickname, runs),
+        away_runs: through(&game.away.inning_runs, inning),
+        // in the top of an inning, the home team has batted through the previous inning
+        home_runs: if top_of_inning {
+            through(&game.home.inning_runs, inning.saturating_sub(1))
+        } else {
+            through(&game.home.inning_runs, inning)
+        },
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_finish() {
+    let mut game = Game::default();
+    game.away.inning_runs = [(1, 4), (2, 0)].into_iter().collect();
+    game.home.inning_runs = [(1, 1), (2, 5)].into_iter().collect();
+    game.highlights.push(Highlight {
+        inning: 2,
+        top_of_inning: false,
+        kind: Kind::HomeRun,
+        description: String::new(),
+        away_runs: 4,
+        home_runs: 3,
+    });
+    finish(&mut game);
+    let summary = game
+        .highlights
+        .iter()
+        .map(|h| (h.inning, h.top_of_inning, h.kind, h.away_runs, h.home_runs))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            (1, true, Kind::BigInning, 4, 0),
+            (2, false, Kind::HomeRun, 4, 3),
+            (2, false, Kind::BigInning, 4, 6),
+        ]
+    );
+}
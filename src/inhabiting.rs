@@ -0,0 +1,87 @@
+//! Inhabiting (haunting) relationships. When a player Inhabits another, the Inhabiting player bats
+//! in the host's lineup spot and their stats are recorded under their own ID, with nothing linking
+//! them to the host. Each game records who Inhabited whom, and both players get an entry here so
+//! their pages can link to each other.
+
+use crate::game::Game;
+use crate::{seasons::Season, DB};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sled::transaction::{
+    ConflictableTransactionError, ConflictableTransactionResult, TransactionalTree,
+};
+use uuid::Uuid;
+
+pub const TREE: &str = "inhabiting_v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Inhabiting {
+    /// The player doing the Inhabiting
+    pub ghost: Uuid,
+    /// The player being Inhabited
+    pub host: Uuid,
+}
+
+/// An Inhabiting appearance in one game.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Entry {
+    pub game_id: Uuid,
+    #[serde(flatten)]
+    pub season: Season,
+    pub day: u16,
+    #[serde(flatten)]
+    pub inhabiting: Inhabiting,
+}
+
+/// Writes an entry for both players in each Inhabiting appearance in this game, keyed by player
+/// and game.
+pub fn write_inhabiting(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    for inhabiting in &game.inhabiting {
+        let entry = serde_json::to_vec(&Entry {
+            game_id: id,
+            season: game.season.clone(),
+            day: game.day,
+            inhabiting: *inhabiting,
+        })
+        .map_err(ConflictableTransactionError::Abort)?;
+        tree.insert(build_key(inhabiting.ghost, id), entry.as_slice())?;
+        tree.insert(build_key(inhabiting.host, id), entry.as_slice())?;
+    }
+    Ok(())
+}
+
+/// Reverses a prior `write_inhabiting` call for this game.
+pub fn remove_inhabiting(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    for inhabiting in &game.inhabiting {
+        tree.remove(build_key(inhabiting.ghost, id))?;
+        tree.remove(build_key(inhabiting.host, id))?;
+    }
+    Ok(())
+}
+
+/// Returns every game where a player Inhabited or was Inhabited, in chronological order.
+pub fn load(player: Uuid) -> Result<Vec<Entry>> {
+    let mut entries = DB
+        .open_tree(TREE)?
+        .scan_prefix(player.as_bytes())
+        .values()
+        .map(|value| Ok(serde_json::from_slice::<Entry>(&value?)?))
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort_by(|a, b| a.season.cmp(&b.season).then(a.day.cmp(&b.day)));
+    Ok(entries)
+}
+
+fn build_key(player: Uuid, game: Uuid) -> Vec<u8> {
+    let mut key = Vec::with_capacity(32);
+    key.extend_from_slice(player.as_bytes());
+    key.extend_from_slice(game.as_bytes());
+    key
+}
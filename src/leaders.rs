@@ -0,0 +1,198 @@
+//! Franchise career leaderboards, ranking players by their regular season totals across every
+//! recorded season with a team.
+
+use crate::game::Stats;
+use crate::names;
+use crate::routes::player::rocket_uri_macro_player;
+use crate::table::{row, Table, Value};
+use anyhow::Result;
+use rocket::uri;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Players listed on each leaderboard.
+const LIMIT: usize = 10;
+/// Career plate appearances needed to qualify for batting rate stat leaderboards.
+const MIN_PLATE_APPEARANCES: u32 = 500;
+/// Career outs recorded (150 innings) needed to qualify for pitching rate stat leaderboards.
+const MIN_OUTS_RECORDED: u32 = 450;
+
+pub struct Category {
+    pub name: &'static str,
+    pub abbr: &'static str,
+    /// Ranking key, where higher is better; `None` if the player doesn't qualify.
+    key: fn(&Stats) -> Option<f64>,
+    value: fn(&Stats) -> Value,
+}
+
+pub const BATTING: &[Category] = &[
+    Category {
+        name: "Games Played",
+        abbr: "G",
+        key: |s| count(s.games_batted),
+        value: |s| s.games_batted.into(),
+    },
+    Category {
+        name: "Hits",
+        abbr: "H",
+        key: |s| count(s.hits()),
+        value: |s| s.hits().into(),
+    },
+    Category {
+        name: "Home Runs",
+        abbr: "HR",
+        key: |s| count(s.home_runs),
+        value: |s| s.home_runs.into(),
+    },
+    Category {
+        name: "Runs Scored",
+        abbr: "R",
+        key: |s| count(s.runs),
+        value: |s| s.runs.into(),
+    },
+    Category {
+        name: "Runs Batted In",
+        abbr: "RBI",
+        key: |s| count(s.runs_batted_in),
+        value: |s| s.runs_batted_in.into(),
+    },
+    Category {
+        name: "Stolen Bases",
+        abbr: "SB",
+        key: |s| count(s.stolen_bases),
+        value: |s| s.stolen_bases.into(),
+    },
+    Category {
+        name: "Bases on Balls (Walks)",
+        abbr: "BB",
+        key: |s| count(s.walks),
+        value: |s| s.walks.into(),
+    },
+    Category {
+        name: "Batting Average",
+        abbr: "BA",
+        key: |s| {
+            (s.plate_appearances >= MIN_PLATE_APPEARANCES).then(|| s.batting_average().0.to_f64())
+        },
+        value: |s| s.batting_average().into(),
+    },
+    Category {
+        name: "On-base Plus Slugging",
+        abbr: "OPS",
+        key: |s| {
+            (s.plate_appearances >= MIN_PLATE_APPEARANCES)
+                .then(|| s.on_base_plus_slugging().0.to_f64())
+        },
+        value: |s| s.on_base_plus_slugging().into(),
+    },
+];
+
+pub const PITCHING: &[Category] = &[
+    Category {
+        name: "Wins",
+        abbr: "W",
+        key: |s| count(s.wins),
+        value: |s| s.wins.into(),
+    },
+    Category {
+        name: "Strikeouts",
+        abbr: "SO",
+        key: |s| count(s.struck_outs),
+        value: |s| s.struck_outs.into(),
+    },
+    Category {
+        name: "Innings Pitched",
+        abbr: "IP",
+        key: |s| count(s.outs_recorded),
+        value: |s| s.innings_pitched().into(),
+    },
+    Category {
+        name: "Saves",
+        abbr: "SV",
+        key: |s| count(s.saves),
+        value: |s| s.saves.into(),
+    },
+    Category {
+        name: "Shutouts",
+        abbr: "SHO",
+        key: |s| count(s.shutouts),
+        value: |s| s.shutouts.into(),
+    },
+    Category {
+        name: "Earned Run Average",
+        abbr: "ERA",
+        key: |s| (s.outs_recorded >= MIN_OUTS_RECORDED).then(|| -s.earned_run_average().0.to_f64()),
+        value: |s| s.earned_run_average().into(),
+    },
+];
+
+fn count(n: u32) -> Option<f64> {
+    (n > 0).then(|| f64::from(n))
+}
+
+/// Ranks players in `totals`, with tied players sharing a rank.
+fn rank<'a>(
+    totals: &'a BTreeMap<Uuid, Stats>,
+    category: &Category,
+) -> Vec<(usize, Uuid, &'a Stats)> {
+    let mut ranked = totals
+        .iter()
+        .filter_map(|(id, stats)| Some(((category.key)(stats)?, *id, stats)))
+        .filter(|(key, _, _)| key.is_finite())
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+
+    let mut rank = 0;
+    let mut previous = None;
+    ranked
+        .into_iter()
+        .enumerate()
+        .take(LIMIT)
+        .map(|(i, (key, id, stats))| {
+            if previous != Some(key) {
+                rank = i + 1;
+                previous = Some(key);
+            }
+            (rank, id, stats)
+        })
+        .collect()
+}
+
+pub fn table(totals: &BTreeMap<Uuid, Stats>, category: &Category) -> Result<Table<3>> {
+    let mut table = Table::new(
+        [("Rank", ""), ("Player", ""), (category.name, category.abbr)],
+        "text-right",
+        "none",
+    );
+    table.col_class[1] = "text-left";
+    for (rank, player_id, stats) in rank(totals, category) {
+        table.push(row![
+            rank,
+            names::player_name(player_id)?.unwrap_or_default(),
+            (category.value)(stats),
+        ]);
+        table.set_href(1, uri!(player(id = player_id, exhibition = _)));
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+#[test]
+fn test_rank() {
+    let mut totals = BTreeMap::new();
+    for (i, home_runs) in [3, 7, 0, 7, 5].into_iter().enumerate() {
+        totals.insert(
+            Uuid::from_u128(i as u128),
+            Stats {
+                home_runs,
+                ..Default::default()
+            },
+        );
+    }
+    let ranked = rank(&totals, &BATTING[2])
+        .into_iter()
+        .map(|(rank, _, stats)| (rank, stats.home_runs))
+        .collect::<Vec<_>>();
+    assert_eq!(ranked, [(1, 7), (1, 7), (3, 5), (4, 3)]);
+}
@@ -0,0 +1,116 @@
+//! Linear weights, wOBA, and wRC+.
+//!
+//! Each season's weights are derived from its league totals, following the method FanGraphs uses
+//! for its yearly wOBA constants: the run value of each way of reaching base is estimated from
+//! the league's runs per out, then shifted and scaled so that league average wOBA equals league
+//! average on-base percentage. Blaseball has no hit-by-pitch or intentional walks, so those terms
+//! are left out.
+
+use crate::fraction::Fraction;
+use crate::game::Stats;
+use crate::percentage::Pct;
+
+/// Run value of a home run, which doesn't depend on the run environment.
+const HOME_RUN: f64 = 1.4;
+const STOLEN_BASE: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Weights {
+    pub walk: f64,
+    pub single: f64,
+    pub double: f64,
+    pub triple: f64,
+    pub home_run: f64,
+    /// Multiplier that puts wOBA on the scale of on-base percentage
+    pub scale: f64,
+    pub league_woba: f64,
+    pub league_runs_per_pa: f64,
+}
+
+impl Weights {
+    pub fn new(league: &Stats) -> Weights {
+        let runs_per_out = f64::from(league.runs) / f64::from(league.outs_recorded);
+        let walk = runs_per_out + 0.14;
+        let single = walk + 0.155;
+        let double = single + 0.3;
+        let triple = double + 0.27;
+        let caught_stealing = 2.0 * runs_per_out + 0.075;
+
+        let runs = walk * f64::from(league.walks)
+            + single * f64::from(league.singles)
+            + double * f64::from(league.doubles)
+            + triple * f64::from(league.triples)
+            + HOME_RUN * f64::from(league.home_runs)
+            + STOLEN_BASE * f64::from(league.stolen_bases)
+            - caught_stealing * f64::from(league.caught_stealing);
+        // run value of an out, and of reaching base, relative to the average plate appearance
+        let run_minus = runs / f64::from(league.at_bats - league.hits() + league.sacrifices);
+        let run_plus = runs / f64::from(league.walks + league.hits());
+        let scale = 1.0 / (run_plus + run_minus);
+
+        Weights {
+            walk: (walk + run_minus) * scale,
+            single: (single + run_minus) * scale,
+            double: (double + run_minus) * scale,
+            triple: (triple + run_minus) * scale,
+            home_run: (HOME_RUN + run_minus) * scale,
+            scale,
+            league_woba: league.on_base_percentage().0.to_f64(),
+            league_runs_per_pa: f64::from(league.runs) / f64::from(league.plate_appearances),
+        }
+    }
+
+    pub fn woba(&self, stats: &Stats) -> f64 {
+        (self.walk * f64::from(stats.walks)
+            + self.single * f64::from(stats.singles)
+            + self.double * f64::from(stats.doubles)
+            + self.triple * f64::from(stats.triples)
+            + self.home_run * f64::from(stats.home_runs))
+            / f64::from(stats.at_bats + stats.walks + stats.sacrifices)
+    }
+
+    /// Runs created per plate appearance relative to the league, where 100 is average.
+    pub fn wrc_plus(&self, stats: &Stats) -> f64 {
+        let runs_above_average = (self.woba(stats) - self.league_woba) / self.scale;
+        (runs_above_average + self.league_runs_per_pa) / self.league_runs_per_pa * 100.0
+    }
+}
+
+/// Rounds to a fraction with the given denominator; values that aren't finite (such as a player
+/// without a plate appearance) show as NaN.
+pub fn to_pct<const PRECISION: u8>(value: f64, denom: u64) -> Pct<PRECISION> {
+    if value.is_finite() {
+        Pct(Fraction::new((value * denom as f64).round() as i64, denom))
+    } else {
+        Pct(Fraction::new(0, 0))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    let league = Stats {
+        plate_appearances: 1000,
+        at_bats: 900,
+        singles: 150,
+        doubles: 40,
+        triples: 5,
+        home_runs: 25,
+        walks: 90,
+        sacrifices: 10,
+        runs: 120,
+        outs_recorded: 690,
+        ..Stats::default()
+    };
+    let weights = Weights::new(&league);
+    assert!(weights.walk < weights.single);
+    assert!(weights.triple < weights.home_run);
+    // the league as a whole is average by construction
+    assert!((weights.woba(&league) - weights.league_woba).abs() < 1e-9);
+    assert!((weights.wrc_plus(&league) - 100.0).abs() < 1e-9);
+    assert_eq!(to_pct::<0>(weights.wrc_plus(&league), 1).to_string(), "100");
+    assert_eq!(
+        to_pct::<3>(weights.woba(&Stats::default()), 1000).to_string(),
+        to_pct::<3>(f64::NAN, 1000).to_string()
+    );
+}
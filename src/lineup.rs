@@ -0,0 +1,139 @@
+use crate::game::Stats;
+use crate::schedule::{Entry, Record};
+use crate::table::{row, Table};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+#[derive(Debug, Default)]
+pub struct LineupLog<'a> {
+    pub distinct: usize,
+    pub changes: usize,
+    pub entries: Vec<LineupEntry<'a>>,
+}
+
+#[derive(Debug)]
+pub struct LineupEntry<'a> {
+    pub day: u16,
+    pub id: Uuid,
+    pub lineup: &'a [Uuid],
+    /// Whether this starting lineup differs from the previous game's
+    pub changed: bool,
+}
+
+/// Builds a log of starting lineups from a team's schedule, skipping games recorded before
+/// starting lineups were stored.
+pub fn lineup_log(schedule: &[(Record, Entry)]) -> LineupLog<'_> {
+    let mut log = LineupLog::default();
+    let mut seen: HashSet<&[Uuid]> = HashSet::new();
+    let mut last: Option<&[Uuid]> = None;
+
+    for (_, entry) in schedule {
+        if entry.lineup.is_empty() {
+            continue;
+        }
+        let changed = last.map_or(false, |last| last != entry.lineup.as_slice());
+        if changed {
+            log.changes += 1;
+        }
+        seen.insert(&entry.lineup);
+        last = Some(&entry.lineup);
+        log.entries.push(LineupEntry {
+            day: entry.day,
+            id: entry.id,
+            lineup: &entry.lineup,
+            changed,
+        });
+    }
+
+    log.distinct = seen.len();
+    log
+}
+
+pub const COLS: usize = 13;
+
+/// Team batting by lineup slot, from per-slot team totals (see `summary::team_lineup_totals`).
+pub fn slot_table(iter: impl Iterator<Item = (u16, Stats)>, league: Stats) -> Table<COLS> {
+    let mut table = Table::new(
+        [
+            ("Lineup Slot", ""),
+            ("Games Played", "G"),
+            ("Plate Appearances", "PA"),
+            ("Runs Scored", "R"),
+            ("Hits", "H"),
+            ("Home Runs", "HR"),
+            ("Runs Batted In", "RBI"),
+            ("Bases on Balls (Walks)", "BB"),
+            ("Strikeouts", "SO"),
+            ("Batting Average", "BA"),
+            ("On-base Percentage", "OBP"),
+            ("Slugging Percentage", "SLG"),
+            ("Adjusted OPS (100 is league average)", "OPS+"),
+        ],
+        "text-right",
+        "number",
+    );
+    table.col_class[0] = "text-left";
+
+    for (slot, stats) in iter {
+        table.push(row![
+            u32::from(slot) + 1,
+            stats.games_batted,
+            stats.plate_appearances,
+            stats.runs,
+            stats.hits(),
+            stats.home_runs,
+            stats.runs_batted_in,
+            stats.walks,
+            stats.strike_outs,
+            stats.batting_average(),
+            stats.on_base_percentage(),
+            stats.slugging_percentage(),
+            stats.ops_plus(league),
+        ]);
+    }
+
+    table
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    use crate::game::Kind;
+
+    let entry = |day: u16, lineup: &[u128]| {
+        (
+            Record::default(),
+            Entry {
+                id: Uuid::from_u128(day.into()),
+                day,
+                game_index: 0,
+                kind: Kind::Regular,
+                home: true,
+                opponent: Default::default(),
+                opponent_id: Uuid::default(),
+                won: true,
+                weather_wins: 0,
+                score: 0,
+                opponent_score: 0,
+                lineup: lineup.iter().copied().map(Uuid::from_u128).collect(),
+                start: None,
+                end: None,
+            },
+        )
+    };
+
+    let schedule = [
+        entry(0, &[1, 2, 3]),
+        entry(1, &[1, 2, 3]),
+        entry(2, &[]),
+        entry(3, &[2, 1, 3]),
+        entry(4, &[1, 2, 3]),
+    ];
+    let log = lineup_log(&schedule);
+    assert_eq!(log.distinct, 2);
+    assert_eq!(log.changes, 2);
+    assert_eq!(
+        log.entries.iter().map(|e| e.changed).collect::<Vec<_>>(),
+        [false, false, true, true]
+    );
+}
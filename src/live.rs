@@ -0,0 +1,107 @@
+use crate::game::{self, Game};
+use crate::seasons::Season;
+use anyhow::Result;
+use rocket::tokio::sync::broadcast::{self, Receiver, Sender};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+lazy_static::lazy_static! {
+    static ref CHANNELS: Mutex<HashMap<Uuid, Channel>> = Mutex::new(HashMap::new());
+    static ref SCHEDULED: Mutex<HashMap<Uuid, Season>> = Mutex::new(HashMap::new());
+}
+
+/// Records the games in the current schedule that aren't complete yet. Called by the update task
+/// each time it loads the schedule; only these games are processed live.
+pub fn set_scheduled(season: &Season, games: Vec<Uuid>) {
+    *SCHEDULED.lock().unwrap() = games.into_iter().map(|id| (id, season.clone())).collect();
+}
+
+/// Returns the season of a game in the current schedule that isn't complete yet.
+pub fn scheduled(id: Uuid) -> Option<Season> {
+    SCHEDULED.lock().unwrap().get(&id).cloned()
+}
+
+struct Channel {
+    sender: Sender<LiveUpdate>,
+    last: Option<LiveUpdate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LiveUpdate {
+    pub inning: u16,
+    pub away_runs: u16,
+    pub home_runs: u16,
+    pub away_hits: u32,
+    pub home_hits: u32,
+    pub finished: bool,
+}
+
+impl LiveUpdate {
+    fn new(game: &Game, finished: bool) -> LiveUpdate {
+        LiveUpdate {
+            inning: game
+                .away
+                .inning_runs
+                .keys()
+                .chain(game.home.inning_runs.keys())
+                .copied()
+                .max()
+                .unwrap_or_default(),
+            away_runs: game.away.runs(),
+            home_runs: game.home.runs(),
+            away_hits: game.away.hits(),
+            home_hits: game.home.hits(),
+            finished,
+        }
+    }
+}
+
+/// Subscribes to updates for a game, returning the most recent update (if any) along with the
+/// receiver for future updates. Returns `None` unless the game is in the current schedule and not
+/// yet complete, so that only real games are polled upstream.
+pub fn subscribe(id: Uuid) -> Option<(Option<LiveUpdate>, Receiver<LiveUpdate>)> {
+    scheduled(id)?;
+    let mut channels = CHANNELS.lock().unwrap();
+    let channel = channels.entry(id).or_insert_with(|| Channel {
+        sender: broadcast::channel(16).0,
+        last: None,
+    });
+    Some((channel.last.clone(), channel.sender.subscribe()))
+}
+
+/// Returns the games that currently have subscribers, dropping channels nobody is listening to
+/// and channels for games that have left the current schedule.
+pub fn watched() -> Vec<Uuid> {
+    let scheduled = SCHEDULED.lock().unwrap();
+    let mut channels = CHANNELS.lock().unwrap();
+    channels
+        .retain(|id, channel| channel.sender.receiver_count() > 0 && scheduled.contains_key(id));
+    channels.keys().copied().collect()
+}
+
+/// Processes the current feed for a game and sends an update to subscribers if anything changed.
+pub async fn publish(id: Uuid) -> Result<()> {
+    let season = match scheduled(id) {
+        Some(season) => season,
+        None => return Ok(()),
+    };
+    let update = match game::process_live(season, id).await? {
+        Some((game, finished)) => LiveUpdate::new(&game, finished),
+        None => return Ok(()),
+    };
+
+    let mut channels = CHANNELS.lock().unwrap();
+    if let Some(channel) = channels.get_mut(&id) {
+        if channel.last.as_ref() != Some(&update) {
+            // an error here only means every subscriber has gone away
+            channel.sender.send(update.clone()).ok();
+            channel.last = Some(update.clone());
+        }
+    }
+    if update.finished {
+        channels.remove(&id);
+    }
+    Ok(())
+}
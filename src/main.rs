@@ -1,32 +1,77 @@
 mod batting;
+mod bracket;
+mod calendar;
+mod changelog;
 mod chronicler;
+mod cli;
+mod compression;
 mod csv;
+mod db;
 mod debug;
+mod decisions;
+mod etag;
+mod event_type;
 mod export;
+mod favicon;
 mod feed;
+mod fielding;
+mod fixtures;
 mod fraction;
 mod game;
+mod glossary;
+mod heatmap;
+mod highlights;
+mod inhabiting;
+mod leaders;
+mod linear_weights;
+mod lineup;
+mod live;
+mod matchup;
+mod metrics;
+mod milestones;
 mod names;
+mod notify;
+mod opponents;
+mod per_game;
 mod percentage;
 mod pitching;
+mod reconcile;
+mod render;
+mod repair;
+mod resolve;
+mod retry;
 mod routes;
+mod run_expectancy;
 mod schedule;
 mod seasons;
+mod snapshot;
+mod splits;
+mod stadium;
+mod stars;
 mod state;
 mod summary;
 mod table;
+mod tasks;
 mod team;
+mod theme;
+mod tombstone;
+mod upstream;
+mod versions;
+mod weather;
+mod win_probability;
 
 use crate::seasons::Season;
+use crate::tasks::{Task, Trigger};
+use crate::theme::Theme;
+use crate::versions::Stale;
 use anyhow::Result;
 use reqwest::Client;
 use rocket::fairing::AdHoc;
 use rocket::fs::FileServer;
 use rocket::http::ContentType;
-use rocket::tokio::time::sleep;
-use rocket::{launch, routes, tokio};
-use serde::Deserialize;
+use rocket::{routes, tokio, Build, Rocket};
 use sled::Db;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
@@ -40,17 +85,29 @@ const SACHET_BASE: &str = "https://api.sibr.dev/eventually/sachet";
 
 static REBUILDING: AtomicBool = AtomicBool::new(false);
 
-// Increment this if you need to force a rebuild.
-const DB_VERSION: &[u8] = &[34];
-const CLEAR_ON_REBUILD: &[&str] = &[summary::TREE, summary::SEASON_TREE];
-const OLD_TREES: &[&str] = &[];
+// Increment this if you need to force a rebuild, and add an entry to changelog.json. Fixes that
+// only affect some sims can be recorded in `versions::CHANGES` instead.
+const DB_VERSION: &[u8] = &[78];
+const CLEAR_ON_REBUILD: &[&str] = &[
+    summary::TREE,
+    summary::SEASON_TREE,
+    summary::DELTA_TREE,
+    summary::DAILY_TREE,
+    matchup::TREE,
+    decisions::TREE,
+    inhabiting::TREE,
+    milestones::TREE,
+    opponents::TREE,
+    reconcile::TREE,
+];
+const OLD_TREES: &[&str] = &["schedule_v1"];
+
+/// HTML responses larger than this are served without minifying. The largest pages are big stat
+/// tables (such as season pages), which gain little from minifying but take the longest to do.
+const MINIFY_MAX_LEN: usize = 512 * 1024;
 
 lazy_static::lazy_static! {
-    static ref DB: Db = sled::Config::default()
-        .path(std::env::var_os("BRICKS_SLED_V1").expect("BRICKS_SLED_V1 not set in environment"))
-        .use_compression(true)
-        .open()
-        .unwrap();
+    static ref DB: Db = db::open();
     static ref CLIENT: Client = Client::builder()
         .user_agent("bricks/0.0 (iliana@sibr.dev)")
         .build()
@@ -71,15 +128,49 @@ macro_rules! log_err {
 
 async fn process_game_or_log(season: Season, id: Uuid, force: bool) {
     let start = Instant::now();
-    match game::process(season, id, force).await {
-        Ok(true) => log::info!("processed game {} in {:?}", id, Instant::now() - start),
+    match game::process(season.clone(), id, force).await {
+        Ok(true) => {
+            log::info!("processed game {} in {:?}", id, Instant::now() - start);
+            log_err!(retry::remove(id));
+        }
         Ok(false) => {}
-        Err(err) => log::error!("failed to process game {}: {:#}", id, err),
+        Err(err) if season.score_only_fallback() => {
+            log::warn!(
+                "failed to process game {}, recording score only: {:#}",
+                id,
+                err
+            );
+            log_err!(game::process_score_only(season, id).await);
+            log_err!(retry::remove(id));
+        }
+        Err(err) => {
+            log::error!("failed to process game {}: {:#}", id, err);
+            notify::alert(
+                format!("process:{}", id),
+                format!("Failed to process game {} ({}): {:#}", id, season, err),
+            );
+            // a complete feed is always cached, so this failure was due to missing upstream data
+            let missing_upstream = log_err!(feed::is_cached(id)) == Some(false);
+            log_err!(retry::settle(season, id, missing_upstream));
+        }
     }
 }
 
-async fn start_task() -> Result<()> {
-    let force = if std::env::args_os().any(|arg| arg == "--rebuild-test") {
+async fn load_schedule(season: &Season) -> Result<Option<Vec<Uuid>>> {
+    Ok(match schedule::last_day(season).await? {
+        Some(last_day) => Some(schedule::load(season, 0, last_day).await?),
+        None => None,
+    })
+}
+
+/// Processes every known season's games, first rebuilding the database if its version is out of
+/// date or `rebuild` is set (by an admin trigger). Otherwise, seasons affected by a processing
+/// change recorded since the last run (see `versions`) are reprocessed.
+async fn start_task(rebuild: bool) -> Result<()> {
+    let force = if rebuild {
+        log::info!("rebuild requested, rebuilding");
+        true
+    } else if std::env::args_os().any(|arg| arg == "--rebuild-test") {
         log::info!("--rebuild-test passed, rebuilding");
         true
     } else {
@@ -104,72 +195,126 @@ async fn start_task() -> Result<()> {
         for tree in CLEAR_ON_REBUILD {
             DB.drop_tree(tree)?;
         }
+        summary::invalidate_league_totals();
+        resolve::invalidate();
+        etag::invalidate();
     }
+    let stale = if force {
+        Stale::default()
+    } else {
+        versions::stale()?
+    };
 
     seasons::load().await?;
 
     // perform api.blaseball.com requests first to avoid server-side HTTP timeouts due to heavy I/O
     let mut schedules = Vec::new();
     for season in Season::known()? {
-        if season.sim == "thisidisstaticyo" || season.sim == "gamma4" {
-            continue;
-        }
-        if let Some(last_day) = schedule::last_day(&season).await? {
-            let games = schedule::load(&season, 0, last_day).await?;
+        let games = if season.score_only_fallback() {
+            log_err!(load_schedule(&season).await).flatten()
+        } else {
+            load_schedule(&season).await?
+        };
+        if let Some(games) = games {
             schedules.push((season, games));
         }
     }
 
     for (season, games) in schedules {
-        for game in games {
-            process_game_or_log(season.clone(), game, force).await;
+        let reprocess = stale.affects(&season);
+        if reprocess {
+            log::info!("reprocessing season {}", season);
+        }
+        tombstone::restore(&season, &games)?;
+        for game in &games {
+            if reprocess {
+                game::remove(*game)?;
+            }
+            process_game_or_log(season.clone(), *game, force || reprocess).await;
         }
+        tombstone::sweep(&season, &games)?;
     }
 
+    // summaries are incomplete until every queued delta has been folded in
+    tokio::task::spawn_blocking(summary::fold).await??;
     REBUILDING.store(false, Ordering::Relaxed);
+    etag::invalidate();
 
     DB.insert("version", DB_VERSION)?;
+    versions::stamp()?;
     if force {
         log::info!("database rebuilt, version {:?}", DB_VERSION);
     }
 
+    let (current, _) = seasons::current().await?;
+    tokio::task::spawn_blocking(move || snapshot::update(&current, true)).await??;
+
     Ok(())
 }
 
 async fn update_task() -> Result<()> {
-    #[derive(Debug, Deserialize)]
-    struct SimData {
-        #[serde(rename = "id")]
-        sim: String,
-        season: u16,
-        day: u16,
-    }
-
-    let now: SimData = CLIENT
-        .get(format!("{}/database/simulationData", API_BASE))
-        .send()
-        .await?
-        .json()
-        .await?;
-    let season = Season {
-        sim: now.sim,
-        season: now.season,
-    };
+    let (season, day) = seasons::current().await?;
     if season.era_name()?.is_none() {
         seasons::load().await?;
     }
 
-    for game_id in schedule::load(&season, now.day.max(1) - 1, now.day).await? {
+    let (complete, incomplete) =
+        schedule::load_with_incomplete(&season, day.max(1) - 1, day).await?;
+    live::set_scheduled(&season, incomplete);
+    for game_id in complete {
         process_game_or_log(season.clone(), game_id, false).await;
     }
 
+    for (season, game_id) in retry::due()? {
+        process_game_or_log(season, game_id, false).await;
+    }
+
+    for game_id in live::watched() {
+        log_err!(live::publish(game_id).await);
+    }
+
+    tokio::task::spawn_blocking(move || snapshot::update(&season, false)).await??;
+
     Ok(())
 }
 
-#[launch]
-fn rocket() -> _ {
+#[rocket::main]
+async fn main() -> Result<()> {
     dotenv::dotenv().ok();
 
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("process") => return cli::process(args.into_iter().skip(1)).await,
+        Some("dump-fixture") => return cli::dump_fixture(args.into_iter().skip(1)).await,
+        _ => {}
+    }
+
+    rocket().launch().await?;
+    Ok(())
+}
+
+/// Minifies an HTML response body into a new buffer, leaving the original to fall back to if
+/// minifying fails.
+fn minify(html: Vec<u8>) -> Vec<u8> {
+    if html.len() > MINIFY_MAX_LEN {
+        return html;
+    }
+    match minify_html_onepass::copy(
+        &html,
+        &minify_html_onepass::Cfg {
+            minify_js: true,
+            minify_css: false,
+        },
+    ) {
+        Ok(minified) => minified,
+        Err(error) => {
+            log::error!("while minifying HTML: {:?}", error);
+            html
+        }
+    }
+}
+
+fn rocket() -> Rocket<Build> {
     let twemoji = match std::env::var_os("TWEMOJI_SVG") {
         Some(path) => PathBuf::from(path),
         None => Path::new(env!("CARGO_MANIFEST_DIR"))
@@ -182,38 +327,135 @@ fn rocket() -> _ {
         .mount(
             "/",
             routes![
+                routes::admin::purge_cache,
+                routes::admin::repair_names,
+                routes::admin::reprocess,
+                routes::admin::task_status,
+                routes::admin::trigger_rebuild,
+                routes::admin::trigger_update,
                 routes::attribution,
                 routes::brick,
+                routes::changelog,
+                routes::changelog_json,
                 routes::css,
+                routes::season::day,
                 routes::debug::debug,
+                routes::debug::debug_json,
                 routes::debug::errors,
+                routes::debug::feed_json,
+                routes::debug::status,
+                routes::export::players_export_csv,
+                routes::export::players_export_json,
+                routes::export::season_games_zip,
                 routes::export::season_player_summary_csv,
                 routes::export::season_player_summary_json,
                 routes::export::season_team_summary_csv,
                 routes::export::season_team_summary_json,
+                routes::export::team_player_summary_csv,
+                routes::export::team_player_summary_json,
+                routes::export::snapshot_file,
+                routes::export::snapshots,
                 routes::game::game,
+                routes::game::game_win_probability,
+                routes::game::live,
                 routes::glossary,
                 routes::index,
                 routes::jump,
+                routes::metrics,
+                routes::team::matchup,
+                routes::player::compare,
                 routes::player::player,
+                routes::player::player_decisions,
+                routes::player::player_splits,
+                routes::player::player_vs_pitcher,
+                routes::player::resolve_player,
+                routes::ready,
+                routes::season::postseason,
+                routes::season::season_milestones,
+                routes::season::season_achievements,
+                routes::season::season_schedule,
                 routes::season::season_player_batting,
                 routes::season::season_player_pitching,
                 routes::season::season_team_batting,
                 routes::season::season_team_pitching,
+                routes::sitemap::robots,
+                routes::sitemap::sitemap_index,
+                routes::sitemap::sitemap_players,
+                routes::sitemap::sitemap_season,
                 routes::tablesort,
                 routes::tablesort_number,
                 routes::team::team,
+                routes::team::team_favicon,
+                routes::team::team_history,
+                routes::team::team_leaders,
+                routes::team::team_lineups_csv,
+                routes::team::team_schedule_csv,
+                routes::team::team_schedule_ics,
+                etag::not_modified,
             ],
         )
-        .mount("/twemoji", FileServer::from(twemoji))
-        .attach(AdHoc::on_liftoff("Background tasks", |_rocket| {
-            Box::pin(async {
-                if std::env::var_os("DISABLE_TASKS").is_none() {
+        .mount("/twemoji", FileServer::from(twemoji.clone()))
+        .attach(AdHoc::on_liftoff("Background tasks", move |rocket| {
+            let twemoji = twemoji.clone();
+            Box::pin(async move {
+                // open the database now so that any errors are logged
+                lazy_static::initialize(&DB);
+                if db::is_ready() {
+                    log_err!(tokio::task::spawn_blocking(db::warm_up).await);
+                }
+
+                if db::is_ready() {
+                    // the only writer to the summary trees; deltas left over from a previous run
+                    // are folded immediately
                     tokio::spawn(async {
-                        log_err!(start_task().await);
                         loop {
-                            sleep(Duration::from_secs(120)).await;
-                            log_err!(update_task().await);
+                            let rebuilding = REBUILDING.load(Ordering::Relaxed);
+                            if log_err!(summary::should_fold(rebuilding)) != Some(false) {
+                                if let Some(result) =
+                                    log_err!(tokio::task::spawn_blocking(summary::fold).await)
+                                {
+                                    log_err!(result);
+                                }
+                            }
+                            summary::FOLD.notified().await;
+                        }
+                    });
+                }
+
+                let tasks = std::env::var_os("DISABLE_TASKS").is_none() && db::is_ready();
+                if let Some(out) = std::env::var_os("BRICKS_RENDER_OUT") {
+                    let shutdown = rocket.shutdown();
+                    let twemoji = twemoji.clone();
+                    let address = match rocket.config().address {
+                        address if address.is_unspecified() => Ipv4Addr::LOCALHOST.into(),
+                        address => address,
+                    };
+                    let base = format!("http://{}", SocketAddr::new(address, rocket.config().port));
+                    tokio::spawn(async move {
+                        if tasks {
+                            tasks::supervise(Task::Start, start_task(false)).await;
+                        }
+                        let out = PathBuf::from(out);
+                        if let Some(written) = log_err!(render::render(&base, &out, &twemoji).await)
+                        {
+                            log::info!("rendered {} files to {}", written, out.display());
+                        }
+                        shutdown.notify();
+                    });
+                } else if tasks {
+                    tasks::enable();
+                    tokio::spawn(notify::run());
+                    tokio::spawn(async {
+                        tasks::supervise(Task::Start, start_task(false)).await;
+                        loop {
+                            match tasks::wait(Duration::from_secs(120)).await {
+                                Trigger::Update => {
+                                    tasks::supervise(Task::Update, update_task()).await
+                                }
+                                Trigger::Rebuild => {
+                                    tasks::supervise(Task::Start, start_task(true)).await
+                                }
+                            }
                         }
                     });
                 }
@@ -225,7 +467,18 @@ fn rocket() -> _ {
                 });
             })
         }))
-        .attach(AdHoc::on_response("HTML minifier", |_, response| {
+        .attach(AdHoc::on_request("Conditional requests", |req, _| {
+            Box::pin(async move { etag::check(req) })
+        }))
+        .attach(AdHoc::on_response(
+            "Database unavailable",
+            |req, response| Box::pin(async move { db::refuse_unready(req, response) }),
+        ))
+        .attach(AdHoc::on_response(
+            "Canonical redirects",
+            |req, response| Box::pin(async move { routes::redirect_canonical(req, response) }),
+        ))
+        .attach(AdHoc::on_response("HTML minifier", |req, response| {
             Box::pin(async move {
                 if response.content_type() == Some(ContentType::HTML) {
                     if let Ok(html) = response.body_mut().take().to_string().await {
@@ -237,8 +490,15 @@ fn rocket() -> _ {
 
                         let rebuild_pos = html.find(HIDDEN);
                         let commit_pos = (html.find(COMMIT), html.rfind(COMMIT));
+                        let theme_pos = html.find(theme::PLACEHOLDER);
                         let mut html = html.into_bytes();
 
+                        if let Some(pos) = theme_pos {
+                            html[pos..(pos + theme::PLACEHOLDER.len())].copy_from_slice(
+                                Theme::from_cookies(req.cookies()).class().as_bytes(),
+                            );
+                        }
+
                         if REBUILDING.load(Ordering::Relaxed) {
                             if let Some(pos) = rebuild_pos {
                                 (&mut html[pos..(pos + UNHIDE.len())]).copy_from_slice(UNHIDE);
@@ -252,23 +512,18 @@ fn rocket() -> _ {
                                 }
                             }
                         }
-                        response.set_sized_body(html.len(), std::io::Cursor::new(html.clone()));
-
-                        match minify_html_onepass::with_friendly_error(
-                            &mut html,
-                            &minify_html_onepass::Cfg {
-                                minify_js: true,
-                                minify_css: false,
-                            },
-                        ) {
-                            Ok(len) => {
-                                html.truncate(len);
-                                response.set_sized_body(html.len(), std::io::Cursor::new(html));
-                            }
-                            Err(error) => log::error!("while minifying HTML: {:?}", error),
-                        }
+
+                        let html = minify(html);
+                        response.set_sized_body(html.len(), std::io::Cursor::new(html));
                     }
                 }
             })
         }))
+        .attach(AdHoc::on_response(
+            "Conditional requests",
+            |req, response| Box::pin(etag::finish(req, response)),
+        ))
+        .attach(AdHoc::on_response("Compression", |req, response| {
+            Box::pin(compression::compress(req, response))
+        }))
 }
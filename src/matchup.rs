@@ -0,0 +1,91 @@
+use crate::game::Game;
+use crate::{seasons::Season, DB};
+use anyhow::Result;
+use derive_more::{Add, AddAssign};
+use serde::{Deserialize, Serialize};
+use sled::transaction::{
+    ConflictableTransactionError, ConflictableTransactionResult, TransactionalTree,
+};
+use std::mem::size_of_val;
+use uuid::Uuid;
+
+pub const TREE: &str = "matchups_v1";
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, Add, AddAssign)]
+#[serde(default)]
+pub struct MatchupStats {
+    pub plate_appearances: u32,
+    pub hits: u32,
+    pub home_runs: u32,
+    pub walks: u32,
+    pub strike_outs: u32,
+}
+
+/// Plate appearance outcomes for one batter against one pitcher in a game.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Matchup {
+    pub batter: Uuid,
+    pub pitcher: Uuid,
+    #[serde(flatten)]
+    pub stats: MatchupStats,
+}
+
+/// Writes a game's matchups to the matchup tree, keyed by season, batter, pitcher, and game.
+pub fn write_matchups(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    for matchup in &game.matchups {
+        tree.insert(
+            build_key(&game.season, matchup.batter, matchup.pitcher, id),
+            serde_json::to_vec(&matchup.stats).map_err(ConflictableTransactionError::Abort)?,
+        )?;
+    }
+    Ok(())
+}
+
+/// Reverses a prior `write_matchups` call for this game.
+pub fn remove_matchups(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    for matchup in &game.matchups {
+        tree.remove(build_key(&game.season, matchup.batter, matchup.pitcher, id))?;
+    }
+    Ok(())
+}
+
+/// Returns a batter's outcomes against a pitcher for each game in a season they faced each other.
+pub fn games(season: &Season, batter: Uuid, pitcher: Uuid) -> Result<Vec<(Uuid, MatchupStats)>> {
+    let mut prefix = build_key(season, batter, pitcher, Uuid::default());
+    prefix.truncate(prefix.len() - size_of_val(&Uuid::default()));
+
+    DB.open_tree(TREE)?
+        .scan_prefix(&prefix)
+        .map(|row| {
+            let (key, value) = row?;
+            Ok((
+                Uuid::from_slice(&key[prefix.len()..])?,
+                serde_json::from_slice(&value)?,
+            ))
+        })
+        .collect()
+}
+
+fn build_key(season: &Season, batter: Uuid, pitcher: Uuid, game: Uuid) -> Vec<u8> {
+    let mut key = Vec::with_capacity(
+        season.sim.len()
+            + size_of_val(&season.season)
+            + size_of_val(&batter)
+            + size_of_val(&pitcher)
+            + size_of_val(&game),
+    );
+    key.extend_from_slice(season.sim.as_bytes());
+    key.extend_from_slice(&season.season.to_ne_bytes());
+    key.extend_from_slice(batter.as_bytes());
+    key.extend_from_slice(pitcher.as_bytes());
+    key.extend_from_slice(game.as_bytes());
+    key
+}
@@ -0,0 +1,145 @@
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+pub static GAMES_PROCESSED: Counter = Counter::new();
+pub static PROCESSING_FAILURES: Counter = Counter::new();
+pub static FEED_CACHE_HITS: Counter = Counter::new();
+pub static FEED_CACHE_MISSES: Counter = Counter::new();
+pub static CHRONICLER_REQUESTS: Counter = Counter::new();
+pub static UPSTREAM_THROTTLED: Counter = Counter::new();
+pub static UPSTREAM_COALESCED: Counter = Counter::new();
+pub static SUMMARY_DELTAS_FOLDED: Counter = Counter::new();
+pub static LEAGUE_TOTALS_CACHE_HITS: Counter = Counter::new();
+pub static LEAGUE_TOTALS_CACHE_MISSES: Counter = Counter::new();
+pub static START_TASK_DURATION: Gauge = Gauge::new();
+pub static UPDATE_TASK_DURATION: Gauge = Gauge::new();
+
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    const fn new() -> Counter {
+        Counter(AtomicU64::new(0))
+    }
+
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add(&self, n: usize) {
+        self.0
+            .fetch_add(u64::try_from(n).unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+
+    fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Stores the most recent duration recorded, in milliseconds.
+pub struct Gauge(AtomicU64);
+
+impl Gauge {
+    const fn new() -> Gauge {
+        Gauge(AtomicU64::new(0))
+    }
+
+    pub fn set(&self, duration: Duration) {
+        self.0.store(
+            u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    fn get_secs(&self) -> f64 {
+        self.0.load(Ordering::Relaxed) as f64 / 1000.0
+    }
+}
+
+/// Renders all metrics in the Prometheus text exposition format.
+pub fn render() -> Result<String, fmt::Error> {
+    let mut s = String::new();
+
+    macro_rules! metric {
+        ($name:expr, $ty:expr, $help:expr, $value:expr) => {
+            writeln!(s, "# HELP {} {}", $name, $help)?;
+            writeln!(s, "# TYPE {} {}", $name, $ty)?;
+            writeln!(s, "{} {}", $name, $value)?;
+        };
+    }
+
+    metric!(
+        "bricks_games_processed_total",
+        "counter",
+        "Games successfully processed.",
+        GAMES_PROCESSED.get()
+    );
+    metric!(
+        "bricks_processing_failures_total",
+        "counter",
+        "Games that failed to process.",
+        PROCESSING_FAILURES.get()
+    );
+    metric!(
+        "bricks_feed_cache_hits_total",
+        "counter",
+        "Game feeds loaded from the sachet cache.",
+        FEED_CACHE_HITS.get()
+    );
+    metric!(
+        "bricks_feed_cache_misses_total",
+        "counter",
+        "Game feeds fetched from sachet.",
+        FEED_CACHE_MISSES.get()
+    );
+    metric!(
+        "bricks_chronicler_requests_total",
+        "counter",
+        "Requests made to Chronicler.",
+        CHRONICLER_REQUESTS.get()
+    );
+    metric!(
+        "bricks_upstream_throttled_total",
+        "counter",
+        "Times an upstream request waited for its host's rate limit.",
+        UPSTREAM_THROTTLED.get()
+    );
+    metric!(
+        "bricks_upstream_coalesced_total",
+        "counter",
+        "Upstream requests served by an identical request already in flight.",
+        UPSTREAM_COALESCED.get()
+    );
+    metric!(
+        "bricks_summary_deltas_folded_total",
+        "counter",
+        "Queued game summaries applied to the summary trees.",
+        SUMMARY_DELTAS_FOLDED.get()
+    );
+    metric!(
+        "bricks_league_totals_cache_hits_total",
+        "counter",
+        "League totals served from the in-memory cache.",
+        LEAGUE_TOTALS_CACHE_HITS.get()
+    );
+    metric!(
+        "bricks_league_totals_cache_misses_total",
+        "counter",
+        "League totals loaded from the season summary tree.",
+        LEAGUE_TOTALS_CACHE_MISSES.get()
+    );
+    metric!(
+        "bricks_start_task_duration_seconds",
+        "gauge",
+        "Duration of the most recent start task run.",
+        START_TASK_DURATION.get_secs()
+    );
+    metric!(
+        "bricks_update_task_duration_seconds",
+        "gauge",
+        "Duration of the most recent update task run.",
+        UPDATE_TASK_DURATION.get_secs()
+    );
+
+    Ok(s)
+}
@@ -0,0 +1,333 @@
+//! Notable individual and team games: multi-home run games, cycles (and near-cycles), no-hitters,
+//! perfect games, and shutouts. These are found when each game finishes processing and stored per
+//! season. Hitting streaks span games, so they're found from the daily performance rows when
+//! they're displayed.
+
+use crate::game::{Game, Kind, Stats};
+use crate::{seasons::Season, summary, DB};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sled::transaction::{
+    ConflictableTransactionError, ConflictableTransactionResult, TransactionalTree,
+};
+use std::mem::size_of_val;
+use uuid::Uuid;
+
+pub const TREE: &str = "milestones_v1";
+
+/// Hitting streaks shorter than this aren't listed.
+pub const MIN_STREAK: u32 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Milestone {
+    MultiHomeRun {
+        player: Uuid,
+        home_runs: u32,
+    },
+    Cycle {
+        player: Uuid,
+    },
+    /// Three of the four hits needed for the cycle
+    CycleAttempt {
+        player: Uuid,
+        missing: String,
+    },
+    /// The team's pitchers held the other team without a hit
+    NoHitter {
+        team: Uuid,
+        pitchers: Vec<Uuid>,
+    },
+    /// A complete game no-hitter without a walk (listed instead of a `NoHitter`)
+    PerfectGame {
+        team: Uuid,
+        pitcher: Uuid,
+    },
+    /// A complete game shutout that wasn't also a no-hitter
+    Shutout {
+        team: Uuid,
+        pitcher: Uuid,
+    },
+}
+
+/// The milestones from one game.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Entry {
+    pub game_id: Uuid,
+    pub day: u16,
+    pub kind: Kind,
+    pub milestones: Vec<Milestone>,
+}
+
+pub fn detect(game: &Game) -> Vec<Milestone> {
+    let mut milestones = Vec::new();
+    for team in game.teams() {
+        for (player, stats) in &team.stats {
+            if stats.home_runs >= 2 {
+                milestones.push(Milestone::MultiHomeRun {
+                    player: *player,
+                    home_runs: stats.home_runs,
+                });
+            }
+            if let Some(milestone) = cycle(*player, stats) {
+                milestones.push(milestone);
+            }
+        }
+        let opponent = game.opponent(team.id);
+        let complete_game = team
+            .stats
+            .iter()
+            .find(|(_, stats)| stats.shutouts > 0)
+            .map(|(pitcher, stats)| (*pitcher, stats.perfect_games > 0));
+        match complete_game {
+            Some((pitcher, true)) => milestones.push(Milestone::PerfectGame {
+                team: team.id,
+                pitcher,
+            }),
+            _ if opponent.hits() == 0 && !opponent.stats.is_empty() => {
+                milestones.push(Milestone::NoHitter {
+                    team: team.id,
+                    pitchers: team.pitchers.clone(),
+                })
+            }
+            Some((pitcher, false)) => milestones.push(Milestone::Shutout {
+                team: team.id,
+                pitcher,
+            }),
+            None => {}
+        }
+    }
+    milestones
+}
+
+fn cycle(player: Uuid, stats: &Stats) -> Option<Milestone> {
+    let hits = [
+        ("single", stats.singles),
+        ("double", stats.doubles),
+        ("triple", stats.triples),
+        ("home run", stats.home_runs),
+    ];
+    let mut missing = hits.iter().filter(|(_, count)| *count == 0);
+    match (missing.next(), missing.next()) {
+        (None, _) => Some(Milestone::Cycle { player }),
+        (Some((hit, _)), None) => Some(Milestone::CycleAttempt {
+            player,
+            missing: (*hit).to_owned(),
+        }),
+        (Some(_), Some(_)) => None,
+    }
+}
+
+/// Writes the game's milestones, if it has any, keyed by season and game.
+pub fn write_milestones(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    if !game.milestones.is_empty() {
+        tree.insert(
+            build_key(&game.season, id),
+            serde_json::to_vec(&Entry {
+                game_id: id,
+                day: game.day,
+                kind: game.kind,
+                milestones: game.milestones.clone(),
+            })
+            .map_err(ConflictableTransactionError::Abort)?,
+        )?;
+    }
+    Ok(())
+}
+
+/// Reverses a prior `write_milestones` call for this game.
+pub fn remove_milestones(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    tree.remove(build_key(&game.season, id))?;
+    Ok(())
+}
+
+/// Returns every game in the season with a milestone, in order.
+pub fn load(season: &Season) -> Result<Vec<Entry>> {
+    let mut entries = DB
+        .open_tree(TREE)?
+        .scan_prefix(season_prefix(season))
+        .values()
+        .map(|value| Ok(serde_json::from_slice::<Entry>(&value?)?))
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.day);
+    Ok(entries)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Streak {
+    pub player: Uuid,
+    pub games: u32,
+    pub first_day: u16,
+    pub last_day: u16,
+}
+
+/// Returns every regular season hitting streak of at least `MIN_STREAK` games, longest first.
+pub fn hitting_streaks(season: &Season) -> Result<Vec<Streak>> {
+    let mut streaks = Vec::new();
+    for player in summary::season_player_summary(season)? {
+        let games = summary::player_daily(player.id)?
+            .into_iter()
+            .filter(|row| &row.season == season && row.kind == Kind::Regular)
+            .map(|row| (row.day, row.at_bats, row.hits))
+            .collect::<Vec<_>>();
+        streaks.extend(streaks_in(player.id, &games));
+    }
+    streaks.sort_by(|a, b| b.games.cmp(&a.games).then(a.first_day.cmp(&b.first_day)));
+    Ok(streaks)
+}
+
+/// Finds streaks in a player's games, given as (day, at bats, hits) in day order. Games without
+/// an at bat neither extend nor end a streak.
+fn streaks_in(player: Uuid, games: &[(u16, u32, u32)]) -> Vec<Streak> {
+    let mut streaks = Vec::new();
+    let mut current: Option<Streak> = None;
+    for &(day, at_bats, hits) in games {
+        if hits > 0 {
+            let streak = current.get_or_insert(Streak {
+                player,
+                games: 0,
+                first_day: day,
+                last_day: day,
+            });
+            streak.games += 1;
+            streak.last_day = day;
+        } else if at_bats > 0 {
+            streaks.extend(current.take());
+        }
+    }
+    streaks.extend(current);
+    streaks.retain(|streak| streak.games >= MIN_STREAK);
+    streaks
+}
+
+fn season_prefix(season: &Season) -> Vec<u8> {
+    let mut key = Vec::with_capacity(season.sim.len() + size_of_val(&season.season));
+    key.extend_from_slice(season.sim.as_bytes());
+    key.extend_from_slice(&season.season.to_ne_bytes());
+    key
+}
+
+fn build_key(season: &Season, id: Uuid) -> Vec<u8> {
+    let mut key = season_prefix(season);
+    key.extend_from_slice(id.as_bytes());
+    key
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    let player = Uuid::default();
+    let stats = Stats {
+        singles: 2,
+        doubles: 1,
+        home_runs: 1,
+        ..Stats::default()
+    };
+    assert_eq!(
+        cycle(player, &stats),
+        Some(Milestone::CycleAttempt {
+            player,
+            missing: "triple".into()
+        })
+    );
+    assert_eq!(cycle(player, &Stats::default()), None);
+
+    let mut games = (0..12).map(|day| (day, 4, 1)).collect::<Vec<_>>();
+    games[5] = (5, 0, 0); // all walks
+    games.push((12, 3, 0));
+    games.extend((13..16).map(|day| (day, 4, 1)));
+    assert_eq!(
+        streaks_in(player, &games),
+        vec![Streak {
+            player,
+            games: 11,
+            first_day: 0,
+            last_day: 11,
+        }]
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_detect_pitching() {
+    use crate::game::Team;
+
+    let pitcher = Uuid::from_u128(1);
+    let batter = Uuid::from_u128(2);
+    let game = |pitching: Stats, batting: Stats| {
+        let mut away = Team {
+            id: Uuid::from_u128(10),
+            pitchers: vec![pitcher],
+            ..Team::default()
+        };
+        away.stats.insert(pitcher, pitching);
+        // keep the home team from throwing a no-hitter of its own
+        away.stats.insert(
+            Uuid::from_u128(3),
+            Stats {
+                singles: 1,
+                ..Stats::default()
+            },
+        );
+        let mut home = Team {
+            id: Uuid::from_u128(11),
+            ..Team::default()
+        };
+        home.stats.insert(batter, batting);
+        Game {
+            away,
+            home,
+            ..Game::default()
+        }
+    };
+
+    let shutout = Stats {
+        shutouts: 1,
+        ..Stats::default()
+    };
+    let walked = Stats {
+        walks: 1,
+        ..Stats::default()
+    };
+    let singled = Stats {
+        singles: 1,
+        ..Stats::default()
+    };
+    assert_eq!(
+        detect(&game(shutout, singled)),
+        [Milestone::Shutout {
+            team: Uuid::from_u128(10),
+            pitcher
+        }]
+    );
+    let no_hitter = Stats {
+        no_hitters: 1,
+        ..shutout
+    };
+    assert_eq!(
+        detect(&game(no_hitter, walked)),
+        [Milestone::NoHitter {
+            team: Uuid::from_u128(10),
+            pitchers: vec![pitcher]
+        }]
+    );
+    let perfect_game = Stats {
+        perfect_games: 1,
+        ..no_hitter
+    };
+    assert_eq!(
+        detect(&game(perfect_game, Stats::default())),
+        [Milestone::PerfectGame {
+            team: Uuid::from_u128(10),
+            pitcher
+        }]
+    );
+}
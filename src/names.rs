@@ -2,13 +2,86 @@ use crate::{seasons::Season, DB};
 use anyhow::{ensure, Result};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use sled::transaction::{
+    ConflictableTransactionError, ConflictableTransactionResult, TransactionalTree,
+};
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
-use std::mem::size_of;
+use std::mem::{size_of, size_of_val};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
 pub const TREE: &str = "names_v1";
 pub const COMMON_TREE: &str = "common_names_v1";
+pub const HISTORY_TREE: &str = "name_history_v1";
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref INDEX: Mutex<Option<Arc<Index>>> = Mutex::new(None);
+}
+
+/// Lookups that would otherwise scan `TREE` or `HISTORY_TREE` on every request. Built on first
+/// use and kept until `invalidate`.
+#[derive(Default)]
+struct Index {
+    /// Every player ID in `TREE`, in key order
+    players: Vec<Uuid>,
+    /// Every player in `HISTORY_TREE`, by each name they were recorded under, lowercased
+    history: HashMap<String, Vec<(Uuid, Alias)>>,
+}
+
+fn index() -> Result<Arc<Index>> {
+    if let Some(index) = INDEX.lock().unwrap().as_ref() {
+        return Ok(index.clone());
+    }
+    let generation = GENERATION.load(Ordering::SeqCst);
+
+    let mut index = Index::default();
+    for row in DB.open_tree(TREE)?.iter() {
+        let (key, value) = row?;
+        // team names are stored in the same tree, as JSON objects
+        if serde_json::from_slice::<TeamName>(&value).is_err() {
+            index.players.push(Uuid::from_slice(&key)?);
+        }
+    }
+    for row in DB.open_tree(HISTORY_TREE)?.iter() {
+        let (key, value) = row?;
+        ensure!(key.len() >= 16, "invalid key in name history tree");
+        let (id, alias) = key.split_at(16);
+        let alias = std::str::from_utf8(alias)?;
+        index
+            .history
+            .entry(alias.to_lowercase())
+            .or_default()
+            .push((
+                Uuid::from_slice(id)?,
+                Alias {
+                    name: alias.to_owned(),
+                    first_seen: serde_json::from_slice(&value)?,
+                },
+            ));
+    }
+
+    let index = Arc::new(index);
+    // an index built from names that changed while it was being built isn't kept
+    if GENERATION.load(Ordering::SeqCst) == generation {
+        *INDEX.lock().unwrap() = Some(index.clone());
+    }
+    Ok(index)
+}
+
+/// Drops the index of player IDs and name history. Call whenever names may have changed.
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    *INDEX.lock().unwrap() = None;
+}
+
+/// Lists every player ID in the names tree, in key order.
+pub fn player_ids() -> Result<Vec<Uuid>> {
+    Ok(index()?.players.clone())
+}
 
 pub fn player_name(id: Uuid) -> Result<Option<String>> {
     Ok(match DB.open_tree(TREE)?.get(id.as_bytes())? {
@@ -24,6 +97,64 @@ pub fn team_name(id: Uuid) -> Result<Option<TeamName>> {
     })
 }
 
+/// A name a player has been recorded under, and the first season they were.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alias {
+    pub name: String,
+    pub first_seen: Season,
+}
+
+/// Records that a player appeared under this name in a season. `TREE` only keeps a player's
+/// latest name; this keeps every name, keyed by player and name, with the earliest season seen.
+pub fn write_history(
+    tree: &TransactionalTree,
+    id: Uuid,
+    name: &str,
+    season: &Season,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    let mut key = Vec::with_capacity(size_of_val(&id) + name.len());
+    key.extend_from_slice(id.as_bytes());
+    key.extend_from_slice(name.as_bytes());
+    if let Some(value) = tree.get(&key)? {
+        let first_seen: Season =
+            serde_json::from_slice(&value).map_err(ConflictableTransactionError::Abort)?;
+        if &first_seen <= season {
+            return Ok(());
+        }
+    }
+    tree.insert(
+        key,
+        serde_json::to_vec(season).map_err(ConflictableTransactionError::Abort)?,
+    )?;
+    Ok(())
+}
+
+/// Returns every name a player has been recorded under, in the order they were first seen.
+pub fn name_history(id: Uuid) -> Result<Vec<Alias>> {
+    let mut v = DB
+        .open_tree(HISTORY_TREE)?
+        .scan_prefix(id.as_bytes())
+        .map(|row| {
+            let (key, value) = row?;
+            Ok(Alias {
+                name: std::str::from_utf8(&key[size_of_val(&id)..])?.to_owned(),
+                first_seen: serde_json::from_slice(&value)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    v.sort_by(|a, b| a.first_seen.cmp(&b.first_seen));
+    Ok(v)
+}
+
+/// Returns every player who has ever been recorded under this name, ignoring case.
+pub fn players_named(name: &str) -> Result<Vec<(Uuid, Alias)>> {
+    Ok(index()?
+        .history
+        .get(&name.to_lowercase())
+        .cloned()
+        .unwrap_or_default())
+}
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct TeamName {
     pub name: String,
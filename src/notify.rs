@@ -0,0 +1,101 @@
+//! Alerts for processing failures, posted to a webhook set in `BRICKS_WEBHOOK_URL`. The payload
+//! carries the message as both `content` and `text`, so Discord and Slack webhooks both accept it.
+//!
+//! Alerts are queued and sent together once a minute, and each alert is only sent once per run
+//! (a game that keeps failing on every update is reported the first time), so a bad deploy
+//! produces one message rather than hundreds.
+
+use crate::CLIENT;
+use anyhow::Result;
+use rocket::tokio::time::sleep;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const INTERVAL: Duration = Duration::from_secs(60);
+
+/// Discord rejects messages longer than 2,000 characters; alerts past this are summarized.
+const MAX_LEN: usize = 1900;
+
+lazy_static::lazy_static! {
+    static ref WEBHOOK_URL: Option<String> = std::env::var("BRICKS_WEBHOOK_URL")
+        .ok()
+        .filter(|url| !url.is_empty());
+    static ref QUEUE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static ref SENT: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// Queues an alert, unless no webhook is configured or an alert with the same key was already
+/// queued.
+pub fn alert(key: impl Into<String>, message: impl Into<String>) {
+    if WEBHOOK_URL.is_none() || !SENT.lock().unwrap().insert(key.into()) {
+        return;
+    }
+    QUEUE.lock().unwrap().push(message.into());
+}
+
+/// Sends queued alerts every `INTERVAL`. Returns immediately if no webhook is configured.
+pub async fn run() {
+    let url = match WEBHOOK_URL.as_deref() {
+        Some(url) => url,
+        None => return,
+    };
+    loop {
+        sleep(INTERVAL).await;
+        let alerts = std::mem::take(&mut *QUEUE.lock().unwrap());
+        if alerts.is_empty() {
+            continue;
+        }
+        if let Err(err) = send(url, &batch(&alerts)).await {
+            log::error!("failed to send {} alerts: {:#}", alerts.len(), err);
+        }
+    }
+}
+
+async fn send(url: &str, message: &str) -> Result<()> {
+    #[derive(Serialize)]
+    struct Payload<'a> {
+        content: &'a str,
+        text: &'a str,
+    }
+
+    CLIENT
+        .post(url)
+        .json(&Payload {
+            content: message,
+            text: message,
+        })
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Joins alerts one per line, counting the ones that don't fit instead of listing them.
+fn batch(alerts: &[String]) -> String {
+    let mut message = String::new();
+    for (i, alert) in alerts.iter().enumerate() {
+        let line = alert.lines().next().unwrap_or_default();
+        if message.len() + line.len() + 1 > MAX_LEN {
+            message.push_str(&format!("…and {} more", alerts.len() - i));
+            break;
+        }
+        message.push_str(line);
+        message.push('\n');
+    }
+    message.trim_end().to_string()
+}
+
+#[cfg(test)]
+#[test]
+fn test_batch() {
+    assert_eq!(
+        batch(&["one".into(), "two\nmore detail".into()]),
+        "one\ntwo"
+    );
+    let alerts = vec!["x".repeat(1000); 3];
+    let message = batch(&alerts);
+    assert!(message.len() <= MAX_LEN + 20);
+    assert!(message.ends_with("…and 2 more"));
+}
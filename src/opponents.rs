@@ -0,0 +1,191 @@
+//! Opponent quality for pitchers: how strong the lineups a pitcher faced were, and their ERA
+//! adjusted for it.
+//!
+//! While processing, each pitcher's batters faced in a game are recorded along with the opposing
+//! team. A pitcher's opponent OPS (oOPS) is the average of those teams' regular season OPS,
+//! weighted by batters faced. Opponent-adjusted ERA (aERA) scales ERA by the league OPS over the
+//! opponent OPS, so a pitcher who faced the league's best lineups has a lower aERA than ERA.
+
+use crate::game::{Game, Stats};
+use crate::percentage::Pct;
+use crate::summary::{self, Summary};
+use crate::table::{row, Table};
+use crate::{names, seasons::Season, DB};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sled::transaction::{
+    ConflictableTransactionError, ConflictableTransactionResult, TransactionalTree,
+};
+use std::mem::size_of_val;
+use uuid::Uuid;
+
+pub const TREE: &str = "opponents_faced_v1";
+
+/// One pitcher's appearance in a game.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct Faced {
+    pub team: Uuid,
+    pub opponent: Uuid,
+    pub batters_faced: u32,
+}
+
+/// Writes the opponents each pitcher in a game faced, keyed by season, pitcher, and game.
+pub fn write_opponents(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    for (pitcher, faced) in faced(game) {
+        tree.insert(
+            build_key(&game.season, pitcher, id),
+            serde_json::to_vec(&faced).map_err(ConflictableTransactionError::Abort)?,
+        )?;
+    }
+    Ok(())
+}
+
+/// Reverses a prior `write_opponents` call for this game.
+pub fn remove_opponents(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    for (pitcher, _) in faced(game) {
+        tree.remove(build_key(&game.season, pitcher, id))?;
+    }
+    Ok(())
+}
+
+fn faced(game: &Game) -> Vec<(Uuid, Faced)> {
+    let mut v = Vec::new();
+    for team in game.teams() {
+        let opponent = game.opponent(team.id).id;
+        for pitcher in &team.pitchers {
+            let batters_faced = team.stats.get(pitcher).map_or(0, |s| s.batters_faced);
+            if batters_faced > 0 {
+                v.push((
+                    *pitcher,
+                    Faced {
+                        team: team.id,
+                        opponent,
+                        batters_faced,
+                    },
+                ));
+            }
+        }
+    }
+    v
+}
+
+/// Returns the opponent OPS a pitcher faced while pitching for a team in a season, or `None` if
+/// they faced no batters for that team.
+pub fn opponent_ops(season: &Season, pitcher: Uuid, team: Uuid) -> Result<Option<Pct<3>>> {
+    let mut prefix = build_key(season, pitcher, Uuid::default());
+    prefix.truncate(prefix.len() - size_of_val(&Uuid::default()));
+
+    let offense = summary::team_batting_totals(season)?;
+    let (mut weighted, mut batters_faced) = (0, 0);
+    for value in DB.open_tree(TREE)?.scan_prefix(&prefix).values() {
+        let faced: Faced = serde_json::from_slice(&value?)?;
+        if faced.team != team {
+            continue;
+        }
+        if let Some(stats) = offense.get(&faced.opponent) {
+            weighted += i64::from(faced.batters_faced) * milli_ops(stats);
+            batters_faced += u64::from(faced.batters_faced);
+        }
+    }
+    Ok((batters_faced > 0).then(|| Pct::new(weighted, batters_faced * 1000)))
+}
+
+/// ERA times the league OPS over the opponent OPS.
+pub fn adjusted_era(stats: &Stats, opponent_ops: Pct<3>, league: &Stats) -> Pct<2> {
+    Pct::new(
+        i64::from(stats.earned_runs * 27) * milli_ops(league),
+        u64::from(stats.outs_recorded) * milli(opponent_ops),
+    )
+}
+
+/// OPS rounded to thousandths and scaled to an integer, so that weighted averages of OPS across
+/// many teams don't overflow a `Fraction`.
+fn milli_ops(stats: &Stats) -> i64 {
+    milli(stats.on_base_plus_slugging()) as i64
+}
+
+fn milli(pct: Pct<3>) -> u64 {
+    (pct.0.to_f64() * 1000.0).round().max(0.0) as u64
+}
+
+pub const COLS: usize = 6;
+
+/// A pitcher's opponent OPS and adjusted ERA for each of their regular season rows.
+pub fn table<'a>(pitcher: Uuid, rows: impl Iterator<Item = &'a Summary>) -> Result<Table<COLS>> {
+    let mut table = Table::new(
+        [
+            ("Season", ""),
+            ("Team", ""),
+            ("Batters Faced", "BF"),
+            ("Earned Run Average", "ERA"),
+            ("Opponent OPS", "oOPS"),
+            ("Opponent-adjusted ERA", "aERA"),
+        ],
+        "text-right",
+        "number",
+    );
+    table.col_class[0] = "text-left";
+    table.col_class[1] = "text-left";
+    for row in rows {
+        let opponent_ops = match opponent_ops(&row.season, pitcher, row.team_id)? {
+            Some(opponent_ops) => opponent_ops,
+            None => continue,
+        };
+        let league = summary::league_totals(&row.season)?;
+        table.push(row![
+            format!("{:#}", row.season),
+            names::team_name(row.team_id)?.unwrap_or_default().shorthand,
+            row.stats.batters_faced,
+            row.stats.earned_run_average(),
+            opponent_ops,
+            adjusted_era(&row.stats, opponent_ops, &league),
+        ]);
+    }
+    Ok(table)
+}
+
+fn build_key(season: &Season, pitcher: Uuid, game: Uuid) -> Vec<u8> {
+    let mut key = Vec::with_capacity(
+        season.sim.len() + size_of_val(&season.season) + size_of_val(&pitcher) + size_of_val(&game),
+    );
+    key.extend_from_slice(season.sim.as_bytes());
+    key.extend_from_slice(&season.season.to_ne_bytes());
+    key.extend_from_slice(pitcher.as_bytes());
+    key.extend_from_slice(game.as_bytes());
+    key
+}
+
+#[cfg(test)]
+#[test]
+fn test_adjusted_era() {
+    let pitcher = Stats {
+        earned_runs: 10,
+        outs_recorded: 90,
+        ..Default::default()
+    };
+    // an OBP of .300 and SLG of .400, for a .700 OPS
+    let league = Stats {
+        plate_appearances: 10,
+        at_bats: 10,
+        singles: 2,
+        doubles: 1,
+        ..Default::default()
+    };
+    assert_eq!(milli_ops(&league), 700);
+    assert_eq!(pitcher.earned_run_average().to_string(), "3.00");
+    let stronger: Pct<3> = Pct::new(800, 1000u16);
+    assert_eq!(
+        adjusted_era(&pitcher, stronger, &league).to_string(),
+        "2.63"
+    );
+    let weaker: Pct<3> = Pct::new(600, 1000u16);
+    assert_eq!(adjusted_era(&pitcher, weaker, &league).to_string(), "3.50");
+}
@@ -0,0 +1,56 @@
+use crate::game::Stats;
+use crate::percentage::Pct;
+use crate::table::{row, Table};
+
+pub const COLS: usize = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Side {
+    Batting,
+    Pitching,
+}
+
+/// Team per-game rates, from team totals and the number of games played in the schedule.
+pub fn table(side: Side, iter: impl Iterator<Item = (Stats, u32)>) -> Table<COLS> {
+    let mut table = Table::new(
+        match side {
+            Side::Batting => [
+                ("Games Played", "G"),
+                ("Runs Scored per Game", "R/G"),
+                ("Hits per Game", "H/G"),
+                ("Home Runs per Game", "HR/G"),
+                ("Strikeouts per Game", "SO/G"),
+            ],
+            Side::Pitching => [
+                ("Games Played", "G"),
+                ("Runs Allowed per Game", "RA/G"),
+                ("Hits Allowed per Game", "H/G"),
+                ("Home Runs Allowed per Game", "HR/G"),
+                ("Strikeouts per 9 Innings", "SO/9"),
+            ],
+        },
+        "text-right",
+        "number",
+    );
+
+    for (stats, games) in iter {
+        table.push(match side {
+            Side::Batting => row![
+                games,
+                Pct::<2>::new(stats.runs, games),
+                Pct::<2>::new(stats.hits(), games),
+                Pct::<2>::new(stats.home_runs, games),
+                Pct::<2>::new(stats.strike_outs, games),
+            ],
+            Side::Pitching => row![
+                games,
+                Pct::<2>::new(stats.runs_allowed(), games),
+                Pct::<2>::new(stats.hits_allowed, games),
+                Pct::<2>::new(stats.home_runs_allowed, games),
+                stats.struck_outs_per_9(),
+            ],
+        });
+    }
+
+    table
+}
@@ -1,7 +1,21 @@
 use crate::game::Stats;
 use crate::table::{row, Table, Value};
 
-pub const COLS: usize = 22;
+pub const COLS: usize = 29;
+
+/// Rate stat columns eligible for heat map coloring, and whether higher values are better.
+pub const HEAT: &[(&str, bool)] = &[
+    ("ERA", false),
+    ("ERA+", true),
+    ("FIP", false),
+    ("WHIP", false),
+    ("H/9", false),
+    ("HR/9", false),
+    ("BB/9", false),
+    ("SO/9", true),
+    ("SO/BB", true),
+    ("CStr%", true),
+];
 
 pub fn table(iter: impl Iterator<Item = Stats>, league: Stats) -> Table<COLS> {
     let mut table = Table::new(
@@ -13,12 +27,17 @@ pub fn table(iter: impl Iterator<Item = Stats>, league: Stats) -> Table<COLS> {
             ("Games Played", "G"),
             ("Shutouts", "SHO"),
             ("Saves", "SV"),
+            ("Save Opportunities", "SVO"),
+            ("Blown Saves", "BSV"),
+            ("Holds", "HLD"),
             ("Innings Pitched", "IP"),
             ("Hits Allowed", "H"),
             ("Runs Allowed", "R"),
+            ("Earned Runs Allowed", "ER"),
             ("Home Runs Allowed", "HR"),
             ("Bases on Balls (Walks)", "BB"),
             ("Strikeouts", "SO"),
+            ("Strikeouts Looking", "SOL"),
             ("Batters Faced", "BF"),
             ("Adjusted ERA (100 is league average)", "ERA+"),
             ("Fielding Independent Pitching", "FIP"),
@@ -28,6 +47,8 @@ pub fn table(iter: impl Iterator<Item = Stats>, league: Stats) -> Table<COLS> {
             ("Walks per 9 Innings", "BB/9"),
             ("Strikeouts per 9 Innings", "SO/9"),
             ("Strikeout-to-Walk Ratio", "SO/BB"),
+            ("Called Strike Percentage", "CStr%"),
+            ("Run Expectancy Prevented (RE24)", "RE24"),
         ],
         "text-right",
         "number",
@@ -49,12 +70,17 @@ pub fn build_row(stats: Stats, league: Stats) -> [Value; COLS] {
         stats.games_pitched,
         stats.shutouts,
         stats.saves,
+        stats.save_opportunities(),
+        stats.blown_saves,
+        stats.holds,
         stats.innings_pitched(),
         stats.hits_allowed,
+        stats.runs_allowed(),
         stats.earned_runs,
         stats.home_runs_allowed,
         stats.walks_issued,
         stats.struck_outs,
+        stats.struck_outs_looking,
         stats.batters_faced,
         stats.era_plus(league),
         stats.fielding_independent_pitching(league),
@@ -64,5 +90,46 @@ pub fn build_row(stats: Stats, league: Stats) -> [Value; COLS] {
         stats.walks_per_9(),
         stats.struck_outs_per_9(),
         stats.struck_outs_walks_ratio(),
+        stats.called_strike_percentage(),
+        stats.pitching_re24(league),
     ]
 }
+
+pub const OPPONENTS_COLS: usize = 10;
+
+/// The combined batting line of all opponents against a team's pitching.
+pub fn opponents_table(iter: impl Iterator<Item = Stats>) -> Table<OPPONENTS_COLS> {
+    let mut table = Table::new(
+        [
+            ("Plate Appearances", "PA"),
+            ("At Bats", "AB"),
+            ("Runs Scored", "R"),
+            ("Hits", "H"),
+            ("Home Runs", "HR"),
+            ("Bases on Balls (Walks)", "BB"),
+            ("Strikeouts", "SO"),
+            ("Batting Average", "BA"),
+            ("On-base Percentage", "OBP"),
+            ("Defensive Efficiency Ratio", "DER"),
+        ],
+        "text-right",
+        "number",
+    );
+
+    for stats in iter {
+        table.push(row![
+            stats.batters_faced,
+            stats.opponents_at_bats(),
+            stats.runs_allowed(),
+            stats.hits_allowed,
+            stats.home_runs_allowed,
+            stats.walks_issued,
+            stats.struck_outs,
+            stats.opponents_batting_average(),
+            stats.opponents_on_base_percentage(),
+            stats.defensive_efficiency(),
+        ]);
+    }
+
+    table
+}
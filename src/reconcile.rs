@@ -0,0 +1,116 @@
+//! Checks each processed game's final score against the score the schedule API reports. A
+//! mismatch usually means an event was misread or a weather effect isn't handled, so mismatches
+//! are recorded and listed on the error dashboard.
+
+use crate::game::Game;
+use crate::{notify, schedule, seasons::Season, DB};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const TREE: &str = "reconciliation_v1";
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Mismatch {
+    pub game_id: Uuid,
+    #[serde(flatten)]
+    pub season: Season,
+    pub day: u16,
+    /// Scores computed from the game's feed
+    pub away: u16,
+    pub home: u16,
+    /// Scores reported by the schedule API
+    pub official_away: f64,
+    pub official_home: f64,
+}
+
+/// Compares a processed game's score against the official score, recording a mismatch or clearing
+/// a previous one. Games whose day isn't in the schedule cache yet are left alone.
+pub fn check(game: &Game, id: Uuid) -> Result<()> {
+    let (official_away, official_home) = match schedule::final_score(&game.season, game.day, id)? {
+        Some(score) => score,
+        None => return Ok(()),
+    };
+    let tree = DB.open_tree(TREE)?;
+    match mismatch(game, id, official_away, official_home) {
+        Some(mismatch) => {
+            log::warn!(
+                "game {} scored {}-{}, but the official final is {}-{}",
+                id,
+                mismatch.away,
+                mismatch.home,
+                official_away,
+                official_home
+            );
+            let previous = tree.insert(id.as_bytes(), serde_json::to_vec(&mismatch)?)?;
+            if previous.is_none() {
+                notify::alert(
+                    format!("reconcile:{}", id),
+                    format!(
+                        "Game {} ({}, day {}) scored {}-{}, but the official final is {}-{}",
+                        id,
+                        game.season,
+                        game.day + 1,
+                        mismatch.away,
+                        mismatch.home,
+                        official_away,
+                        official_home
+                    ),
+                );
+            }
+        }
+        None => {
+            tree.remove(id.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn mismatch(game: &Game, id: Uuid, official_away: f64, official_home: f64) -> Option<Mismatch> {
+    let away = game.away.score();
+    let home = game.home.score();
+    if f64::from(away) == official_away && f64::from(home) == official_home {
+        None
+    } else {
+        Some(Mismatch {
+            game_id: id,
+            season: game.season.clone(),
+            day: game.day,
+            away,
+            home,
+            official_away,
+            official_home,
+        })
+    }
+}
+
+/// Clears any recorded mismatch for a game that is being removed.
+pub fn clear(id: Uuid) -> Result<()> {
+    DB.open_tree(TREE)?.remove(id.as_bytes())?;
+    Ok(())
+}
+
+/// Returns every recorded mismatch, in chronological order.
+pub fn load() -> Result<Vec<Mismatch>> {
+    let mut mismatches = DB
+        .open_tree(TREE)?
+        .iter()
+        .values()
+        .map(|value| Ok(serde_json::from_slice::<Mismatch>(&value?)?))
+        .collect::<Result<Vec<_>>>()?;
+    mismatches.sort_by(|a, b| a.season.cmp(&b.season).then(a.day.cmp(&b.day)));
+    Ok(mismatches)
+}
+
+#[cfg(test)]
+#[test]
+fn test_mismatch() {
+    let mut game = Game::default();
+    game.away.inning_runs.insert(1, 3);
+    game.home.inning_runs.insert(1, 5);
+    let id = Uuid::default();
+    assert_eq!(mismatch(&game, id, 3.0, 5.0), None);
+    let found = mismatch(&game, id, 3.0, 6.0).unwrap();
+    assert_eq!((found.away, found.home), (3, 5));
+    assert_eq!(found.official_home, 6.0);
+}
@@ -0,0 +1,200 @@
+//! Renders the whole site to static files, so that it can be preserved after the upstream APIs
+//! are gone. Set `BRICKS_RENDER_OUT` to an output directory; once the start task has finished (or
+//! immediately, if tasks are disabled), every page is requested from the running server and
+//! written out, then the server shuts down.
+//!
+//! Pages are written as `<path>/index.html` and everything else at its own path, so the site's
+//! absolute links work as-is from any static file server. Links with query strings (such as
+//! exhibition and qualified-only views) aren't rendered. While rendering, season tables are served
+//! whole, without pagination or server-side sorting links, so each fits at its query-free path.
+
+use crate::routes::export::{
+    rocket_uri_macro_season_games_zip, rocket_uri_macro_season_player_summary_csv,
+    rocket_uri_macro_season_player_summary_json, rocket_uri_macro_season_team_summary_csv,
+    rocket_uri_macro_season_team_summary_json,
+};
+use crate::routes::game::rocket_uri_macro_game_win_probability;
+use crate::routes::player::{
+    rocket_uri_macro_player, rocket_uri_macro_player_decisions, rocket_uri_macro_player_splits,
+};
+use crate::routes::sitemap;
+use crate::routes::team::{
+    rocket_uri_macro_team_favicon, rocket_uri_macro_team_history, rocket_uri_macro_team_leaders,
+    rocket_uri_macro_team_lineups_csv, rocket_uri_macro_team_schedule_csv,
+    rocket_uri_macro_team_schedule_ics,
+};
+use crate::{schedule, seasons::Season, summary, CLIENT};
+use anyhow::{Context, Result};
+use rocket::http::ContentType;
+use rocket::uri;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Pages and assets that don't depend on any data.
+const STATIC_PATHS: &[&str] = &[
+    "/",
+    "/attribution",
+    "/brick.svg",
+    "/changelog",
+    "/changelog.json",
+    "/glossary",
+    "/styles.css",
+    "/tablesort.min.js",
+    "/tablesort.number.min.js",
+];
+
+/// Whether the server was started to render the site.
+pub fn is_rendering() -> bool {
+    std::env::var_os("BRICKS_RENDER_OUT").is_some()
+}
+
+/// Renders every path to `out`, fetching pages from the server at `base`, and copies the Twemoji
+/// assets alongside them. Returns the number of files written.
+pub async fn render(base: &str, out: &Path, twemoji: &Path) -> Result<usize> {
+    let mut written = 0;
+    for path in paths()? {
+        let response = CLIENT.get(format!("{}{}", base, path)).send().await?;
+        if !response.status().is_success() {
+            log::debug!("skipping {} ({})", path, response.status());
+            continue;
+        }
+        let is_html = response
+            .headers()
+            .get("content-type")
+            .and_then(|value| value.to_str().ok())
+            .and_then(ContentType::parse_flexible)
+            .map_or(false, |ty| ty == ContentType::HTML);
+
+        let file = output_path(out, &path, is_html);
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&file, response.bytes().await?)
+            .with_context(|| format!("failed to write {}", file.display()))?;
+        written += 1;
+    }
+
+    written += copy_dir(twemoji, &out.join("twemoji"))?;
+    Ok(written)
+}
+
+fn paths() -> Result<BTreeSet<String>> {
+    let mut paths = STATIC_PATHS
+        .iter()
+        .map(|path| (*path).to_owned())
+        .collect::<BTreeSet<_>>();
+
+    for season in Season::recorded()? {
+        paths.extend(sitemap::season_paths(&season)?);
+        paths.insert(uri!(season_games_zip(sim = &season.sim, season = season.season)).to_string());
+        paths.insert(
+            uri!(season_player_summary_csv(
+                sim = &season.sim,
+                season = season.season
+            ))
+            .to_string(),
+        );
+        paths.insert(
+            uri!(season_player_summary_json(
+                sim = &season.sim,
+                season = season.season
+            ))
+            .to_string(),
+        );
+        paths.insert(
+            uri!(season_team_summary_csv(
+                sim = &season.sim,
+                season = season.season
+            ))
+            .to_string(),
+        );
+        paths.insert(
+            uri!(season_team_summary_json(
+                sim = &season.sim,
+                season = season.season
+            ))
+            .to_string(),
+        );
+
+        for team in summary::season_team_summary(&season)? {
+            paths.insert(uri!(team_favicon(id = team.id)).to_string());
+            paths.insert(uri!(team_history(id = team.id)).to_string());
+            paths.insert(uri!(team_leaders(id = team.id)).to_string());
+            paths.insert(
+                uri!(team_lineups_csv(
+                    id = team.id,
+                    sim = &season.sim,
+                    season = season.season
+                ))
+                .to_string(),
+            );
+            paths.insert(
+                uri!(team_schedule_csv(
+                    id = team.id,
+                    sim = &season.sim,
+                    season = season.season
+                ))
+                .to_string(),
+            );
+            paths.insert(
+                uri!(team_schedule_ics(
+                    id = team.id,
+                    sim = &season.sim,
+                    season = season.season
+                ))
+                .to_string(),
+            );
+        }
+        for (_, entry) in schedule::games(&season)? {
+            paths.insert(uri!(game_win_probability(id = entry.id)).to_string());
+        }
+    }
+
+    for player_id in sitemap::players()? {
+        paths.insert(uri!(player(id = player_id, exhibition = _)).to_string());
+        paths.insert(uri!(player_splits(id = player_id, segments = _)).to_string());
+        paths.insert(uri!(player_decisions(id = player_id)).to_string());
+    }
+
+    Ok(paths)
+}
+
+fn output_path(out: &Path, path: &str, is_html: bool) -> PathBuf {
+    let relative = path.trim_start_matches('/');
+    if is_html {
+        out.join(relative).join("index.html")
+    } else {
+        out.join(relative)
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<usize> {
+    std::fs::create_dir_all(to)?;
+    let mut copied = 0;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copied += copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+#[cfg(test)]
+#[test]
+fn test_output_path() {
+    let out = Path::new("/out");
+    assert_eq!(output_path(out, "/", true), Path::new("/out/index.html"));
+    assert_eq!(
+        output_path(out, "/glossary", true),
+        Path::new("/out/glossary/index.html")
+    );
+    assert_eq!(
+        output_path(out, "/styles.css", false),
+        Path::new("/out/styles.css")
+    );
+}
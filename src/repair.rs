@@ -0,0 +1,174 @@
+use crate::game::{Game, GAME_STATS_TREE};
+use crate::names::{self, TeamName};
+use crate::{chronicler, summary, team, DB};
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
+
+/// IDs that `repair_names` could not find a name for, shown on the error dashboard.
+pub const UNRESOLVED_TREE: &str = "unresolved_names_v1";
+
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub checked: usize,
+    pub missing: usize,
+    pub repaired_from_games: usize,
+    pub repaired_from_chronicler: usize,
+    pub repaired_summary_rows: usize,
+    pub unresolved: Vec<Uuid>,
+}
+
+#[derive(Deserialize)]
+struct Player {
+    name: String,
+}
+
+enum Name {
+    Player(String),
+    Team(TeamName),
+}
+
+/// Finds every player and team in the season summaries without a valid entry in the names tree,
+/// and looks their names up from processed box scores, then from Chronicler. Anything still
+/// missing is recorded in `UNRESOLVED_TREE`. Season summary rows recorded without a name are
+/// filled in from the repaired names tree.
+pub async fn repair_names() -> Result<Report> {
+    let names_tree = DB.open_tree(names::TREE)?;
+    let (players, teams) = summary::recorded_ids()?;
+    let mut report = Report {
+        checked: players.len() + teams.len(),
+        ..Report::default()
+    };
+
+    let mut missing_players = BTreeSet::new();
+    for id in players {
+        if names_tree.get(id.as_bytes())?.is_none() {
+            missing_players.insert(id);
+        }
+    }
+    let mut missing_teams = BTreeSet::new();
+    for id in teams {
+        // a player name stored under a team ID won't parse as a team name
+        if names::team_name(id).ok().flatten().is_none() {
+            missing_teams.insert(id);
+        }
+    }
+    report.missing = missing_players.len() + missing_teams.len();
+
+    // the box score from the latest game wins, as it would when processing games in order; the
+    // tree is keyed by game ID, so its order says nothing about when games were played
+    let mut latest = BTreeMap::new();
+    if report.missing > 0 {
+        for row in DB.open_tree(GAME_STATS_TREE)?.iter() {
+            let (_, value) = row?;
+            let game: Game = serde_json::from_slice(&value)?;
+            let played = (game.end, game.day);
+            let mut found = |id: Uuid, name: Name| match latest.entry(id) {
+                Entry::Occupied(mut entry) => {
+                    let (when, _) = entry.get();
+                    if played >= *when {
+                        entry.insert((played, name));
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((played, name));
+                }
+            };
+            for team in game.teams() {
+                if missing_teams.contains(&team.id) {
+                    found(team.id, Name::Team(team.name.clone()));
+                }
+                for (id, name) in &team.player_names {
+                    if missing_players.contains(id) {
+                        found(*id, Name::Player(name.clone()));
+                    }
+                }
+            }
+        }
+    }
+    let mut found = latest
+        .into_iter()
+        .map(|(id, (_, name))| (id, name))
+        .collect::<BTreeMap<_, _>>();
+    report.repaired_from_games = found.len();
+
+    let now = Utc::now();
+    for id in &missing_players {
+        if !found.contains_key(id) {
+            if let Some(player) = chronicler::load::<Player>("player", *id, now).await? {
+                found.insert(*id, Name::Player(player.name));
+                report.repaired_from_chronicler += 1;
+            }
+        }
+    }
+    for id in &missing_teams {
+        if !found.contains_key(id) {
+            if let Some(team) = team::load(*id, now).await? {
+                found.insert(
+                    *id,
+                    Name::Team(TeamName {
+                        name: team.full_name,
+                        nickname: team.nickname,
+                        shorthand: team.shorthand,
+                        emoji: team.emoji,
+                    }),
+                );
+                report.repaired_from_chronicler += 1;
+            }
+        }
+    }
+
+    for (id, name) in &found {
+        match name {
+            Name::Player(name) => names_tree.insert(id.as_bytes(), name.as_bytes())?,
+            Name::Team(name) => names_tree.insert(id.as_bytes(), serde_json::to_vec(name)?)?,
+        };
+    }
+
+    let unresolved_tree = DB.open_tree(UNRESOLVED_TREE)?;
+    unresolved_tree.clear()?;
+    for id in missing_players.iter().chain(&missing_teams) {
+        if !found.contains_key(id) {
+            unresolved_tree.insert(id.as_bytes(), &[])?;
+            report.unresolved.push(*id);
+        }
+    }
+
+    report.repaired_summary_rows = summary::repair_names(|id, is_team| {
+        Ok(if is_team {
+            names::team_name(id)
+                .ok()
+                .flatten()
+                .map(|name| name.nickname)
+        } else {
+            names::player_name(id)?
+        })
+    })?;
+
+    if !found.is_empty() || report.repaired_summary_rows > 0 {
+        names::invalidate();
+        crate::resolve::invalidate();
+        crate::etag::invalidate();
+    }
+
+    log::info!(
+        "repaired {} of {} missing names ({} unresolved) and {} season summary rows",
+        found.len(),
+        report.missing,
+        report.unresolved.len(),
+        report.repaired_summary_rows
+    );
+    Ok(report)
+}
+
+/// Returns the IDs the last `repair_names` run could not find a name for.
+pub fn unresolved() -> Result<Vec<Uuid>> {
+    DB.open_tree(UNRESOLVED_TREE)?
+        .iter()
+        .keys()
+        .map(|key| Ok(Uuid::from_slice(&key?)?))
+        .collect()
+}
@@ -0,0 +1,157 @@
+use crate::{names, seasons::Season, summary};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Each player's name in each season's summary, by lowercased name
+type SeasonNames = HashMap<String, Vec<(Uuid, String, Season)>>;
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref SEASON_NAMES: Mutex<Option<Arc<SeasonNames>>> = Mutex::new(None);
+}
+
+fn season_names() -> Result<Arc<SeasonNames>> {
+    if let Some(names) = SEASON_NAMES.lock().unwrap().as_ref() {
+        return Ok(names.clone());
+    }
+    let generation = GENERATION.load(Ordering::SeqCst);
+    let mut names = SeasonNames::new();
+    for season in Season::recorded()? {
+        for row in summary::season_player_summary(&season)? {
+            names.entry(row.name.to_lowercase()).or_default().push((
+                row.id,
+                row.name,
+                season.clone(),
+            ));
+        }
+    }
+    let names = Arc::new(names);
+    if GENERATION.load(Ordering::SeqCst) == generation {
+        *SEASON_NAMES.lock().unwrap() = Some(names.clone());
+    }
+    Ok(names)
+}
+
+/// Drops the index of player names in season summaries. Call whenever the summaries change.
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    *SEASON_NAMES.lock().unwrap() = None;
+}
+
+#[derive(Debug, Serialize)]
+pub struct Resolution {
+    pub id: Uuid,
+    pub name: String,
+    /// How the query matched this player: `uuid`, `name`, `name_case_insensitive`, or `alias`
+    /// (a name the player was previously recorded under)
+    pub method: &'static str,
+    /// Between 0 and 1; ambiguous names split confidence between all matching players
+    pub confidence: f64,
+    /// When more than one player matches, the player's last team and the range of seasons they
+    /// played in, e.g. "Hellmouth Sunbeams, Discipline Era/S2–Expansion Era/S14"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disambiguation: Option<String>,
+}
+
+/// Resolves a player identifier to a player ID. `query` may be a player UUID or a player's
+/// exact name as recorded in a season's box scores; `era` optionally limits name matching to
+/// seasons of a sim or era (matched against the sim ID or era name, e.g. "Gamma 2").
+///
+/// Blaseball never had numeric player IDs, so UUIDs and names are the only schemes.
+pub fn resolve_player(query: &str, era: Option<&str>) -> Result<Vec<Resolution>> {
+    let query = query.trim();
+    if let Ok(id) = query.parse::<Uuid>() {
+        return Ok(match names::player_name(id)? {
+            Some(name) => vec![Resolution {
+                id,
+                name,
+                method: "uuid",
+                confidence: 1.0,
+                disambiguation: None,
+            }],
+            None => Vec::new(),
+        });
+    }
+
+    let in_era = |season: &Season| -> Result<bool> {
+        Ok(match era {
+            Some(era) => {
+                season.sim.eq_ignore_ascii_case(era)
+                    || season
+                        .era_name()?
+                        .map_or(false, |name| name.eq_ignore_ascii_case(era))
+            }
+            None => true,
+        })
+    };
+
+    // id -> (name, method)
+    let mut matches: BTreeMap<Uuid, (String, &'static str)> = BTreeMap::new();
+    let season_names = season_names()?;
+    for (id, name, season) in season_names
+        .get(&query.to_lowercase())
+        .map_or(&[][..], Vec::as_slice)
+    {
+        if !in_era(season)? {
+            continue;
+        }
+        if name == query {
+            matches.insert(*id, (name.clone(), "name"));
+        } else {
+            matches
+                .entry(*id)
+                .or_insert((name.clone(), "name_case_insensitive"));
+        }
+    }
+    for (id, alias) in names::players_named(query)? {
+        if in_era(&alias.first_seen)? {
+            matches.entry(id).or_insert((alias.name, "alias"));
+        }
+    }
+
+    let exact = matches
+        .values()
+        .filter(|(_, method)| *method == "name")
+        .count();
+    let candidates = if exact > 0 {
+        matches.retain(|_, (_, method)| *method == "name");
+        exact
+    } else {
+        matches.len()
+    };
+    matches
+        .into_iter()
+        .map(|(id, (name, method))| {
+            Ok(Resolution {
+                id,
+                name,
+                method,
+                confidence: (if method == "name" { 1.0 } else { 0.5 }) / candidates as f64,
+                disambiguation: if candidates > 1 {
+                    disambiguation(id)?
+                } else {
+                    None
+                },
+            })
+        })
+        .collect()
+}
+
+fn disambiguation(id: Uuid) -> Result<Option<String>> {
+    let summary = summary::player_summary(id)?;
+    let (first, last) = match (summary.first(), summary.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Ok(None),
+    };
+    let team = names::team_name(last.team_id)?.unwrap_or_default().name;
+    Ok(Some(if first.season == last.season {
+        format!("{}, {:#}", team, first.season)
+    } else {
+        format!("{}, {:#}–{:#}", team, first.season, last.season)
+    }))
+}
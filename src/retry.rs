@@ -0,0 +1,104 @@
+use crate::{seasons::Season, DB};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sled::Tree;
+use uuid::Uuid;
+
+pub const TREE: &str = "retry_v1";
+
+/// Give up on a game after this many failed attempts; by then the backoff is a day long.
+const MAX_ATTEMPTS: u32 = 16;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Entry {
+    season: Season,
+    attempts: u32,
+    next_attempt: DateTime<Utc>,
+}
+
+/// Schedules a game whose upstream feed was missing or incomplete to be processed again later,
+/// backing off exponentially from two minutes up to a day between attempts.
+fn schedule(tree: &Tree, season: Season, id: Uuid) -> Result<()> {
+    let attempts = match tree.get(id.as_bytes())? {
+        Some(value) => serde_json::from_slice::<Entry>(&value)?.attempts + 1,
+        None => 1,
+    };
+    if attempts > MAX_ATTEMPTS {
+        log::warn!("giving up on game {} after {} attempts", id, MAX_ATTEMPTS);
+        tree.remove(id.as_bytes())?;
+        return Ok(());
+    }
+
+    let backoff = Duration::minutes(2 << (attempts - 1).min(10)).min(Duration::days(1));
+    let entry = Entry {
+        season,
+        attempts,
+        next_attempt: Utc::now() + backoff,
+    };
+    log::info!(
+        "will retry game {} at {} (attempt {})",
+        id,
+        entry.next_attempt,
+        attempts + 1
+    );
+    tree.insert(id.as_bytes(), serde_json::to_vec(&entry)?)?;
+    Ok(())
+}
+
+pub fn remove(id: Uuid) -> Result<()> {
+    DB.open_tree(TREE)?.remove(id.as_bytes())?;
+    Ok(())
+}
+
+/// Records a failed attempt to process a game. If upstream data was missing, the game is scheduled
+/// to be retried; otherwise retrying won't help, so any retry already scheduled is dropped.
+pub fn settle(season: Season, id: Uuid, missing_upstream: bool) -> Result<()> {
+    settle_in(&DB.open_tree(TREE)?, season, id, missing_upstream)
+}
+
+fn settle_in(tree: &Tree, season: Season, id: Uuid, missing_upstream: bool) -> Result<()> {
+    if missing_upstream {
+        schedule(tree, season, id)
+    } else {
+        tree.remove(id.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Returns the games that are due to be retried.
+pub fn due() -> Result<Vec<(Season, Uuid)>> {
+    let now = Utc::now();
+    let mut games = Vec::new();
+    for row in DB.open_tree(TREE)?.iter() {
+        let (key, value) = row?;
+        let entry: Entry = serde_json::from_slice(&value)?;
+        if entry.next_attempt <= now {
+            games.push((entry.season, Uuid::from_slice(&key)?));
+        }
+    }
+    Ok(games)
+}
+
+#[test]
+fn test_settle() {
+    let db = sled::Config::new().temporary(true).open().unwrap();
+    let tree = db.open_tree(TREE).unwrap();
+    let season = Season {
+        sim: "thisidisstaticyo".into(),
+        season: 23,
+    };
+    let id = Uuid::from_u128(1);
+    let attempts = || {
+        tree.get(id.as_bytes())
+            .unwrap()
+            .map(|value| serde_json::from_slice::<Entry>(&value).unwrap().attempts)
+    };
+
+    settle_in(&tree, season.clone(), id, true).unwrap();
+    settle_in(&tree, season.clone(), id, true).unwrap();
+    assert_eq!(attempts(), Some(2));
+    // a game scheduled for a retry that then fails for some other reason isn't retried forever
+    settle_in(&tree, season, id, false).unwrap();
+    assert_eq!(attempts(), None);
+}
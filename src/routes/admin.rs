@@ -0,0 +1,239 @@
+use crate::routes::ResponseResult;
+use crate::table::{row, Table};
+use crate::tasks::{self, Task, Trigger};
+use crate::{game, repair, seasons::Season, DB};
+use askama::Template;
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::content::Html;
+use rocket::serde::json::Json;
+use rocket::{get, post, Either};
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use uuid::Uuid;
+
+lazy_static::lazy_static! {
+    static ref ADMIN_TOKEN: Option<String> = std::env::var("BRICKS_ADMIN_TOKEN")
+        .ok()
+        .filter(|s| !s.is_empty());
+}
+
+/// Request guard for admin routes. Requests must send `Authorization: Bearer <token>`, where the
+/// token matches `BRICKS_ADMIN_TOKEN`; if that is unset, admin routes are disabled entirely.
+pub struct Admin;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Admin, ()> {
+        let token = match &*ADMIN_TOKEN {
+            Some(token) => token,
+            None => return Outcome::Failure((Status::Forbidden, ())),
+        };
+        match req
+            .headers()
+            .get_one("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+        {
+            Some(value) if value == token => Outcome::Success(Admin),
+            _ => Outcome::Failure((Status::Unauthorized, ())),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProcessResult {
+    processed: bool,
+    error: Option<String>,
+}
+
+#[post("/admin/reprocess/<id>?<sim>&<season>")]
+pub async fn reprocess(
+    _admin: Admin,
+    id: Uuid,
+    sim: Option<String>,
+    season: Option<u16>,
+) -> ResponseResult<Either<Json<ProcessResult>, Status>> {
+    // a rebuild clears the summaries, so unwinding the game's old stats from them would underflow
+    if crate::REBUILDING.load(Ordering::Relaxed) {
+        return Ok(Either::Right(Status::ServiceUnavailable));
+    }
+
+    let season = match (sim, season) {
+        (Some(sim), Some(season)) => Season { sim, season },
+        _ => match game::load(id)? {
+            Some(game) => game.season,
+            // we don't know which season an unprocessed game belongs to
+            None => return Ok(Either::Right(Status::BadRequest)),
+        },
+    };
+
+    Ok(Either::Left(Json(
+        match game::reprocess(season, id).await {
+            Ok(processed) => ProcessResult {
+                processed,
+                error: None,
+            },
+            Err(err) => ProcessResult {
+                processed: false,
+                error: Some(format!("{:#}", err)),
+            },
+        },
+    )))
+}
+
+/// Fills in missing player and team names; see `repair::repair_names`.
+#[post("/admin/names/repair")]
+pub async fn repair_names(_admin: Admin) -> ResponseResult<Json<repair::Report>> {
+    Ok(Json(repair::repair_names().await?))
+}
+
+#[derive(Serialize)]
+pub struct PurgeResult {
+    tree: String,
+    removed: usize,
+}
+
+/// Purges a cache tree (any tree named `cache_*`), either entirely or only the keys starting with
+/// `prefix`. The prefix can be a UUID (for the sachet and Chronicler caches, which are keyed by
+/// game or entity ID) or `<sim>/<season>` (for the schedule cache).
+#[post("/admin/cache/purge/<tree>?<prefix>")]
+pub fn purge_cache(
+    _admin: Admin,
+    tree: &str,
+    prefix: Option<&str>,
+) -> ResponseResult<Either<Json<PurgeResult>, Status>> {
+    if !tree.starts_with("cache_") {
+        return Ok(Either::Right(Status::BadRequest));
+    }
+    if !DB
+        .tree_names()
+        .iter()
+        .any(|name| name.as_ref() == tree.as_bytes())
+    {
+        return Ok(Either::Right(Status::NotFound));
+    }
+
+    let prefix = match prefix {
+        None => Vec::new(),
+        Some(prefix) => match (prefix.parse::<Uuid>(), prefix.split_once('/')) {
+            (Ok(id), _) => id.as_bytes().to_vec(),
+            (Err(_), Some((sim, season))) => match season.parse::<u16>() {
+                Ok(season) => {
+                    let mut key = sim.as_bytes().to_vec();
+                    key.extend_from_slice(&season.to_ne_bytes());
+                    key
+                }
+                Err(_) => return Ok(Either::Right(Status::BadRequest)),
+            },
+            (Err(_), None) => return Ok(Either::Right(Status::BadRequest)),
+        },
+    };
+
+    let cache = DB.open_tree(tree).map_err(anyhow::Error::from)?;
+    let mut removed = 0;
+    for row in cache.scan_prefix(&prefix).keys() {
+        cache
+            .remove(row.map_err(anyhow::Error::from)?)
+            .map_err(anyhow::Error::from)?;
+        removed += 1;
+    }
+    log::info!("purged {} keys from {}", removed, tree);
+
+    Ok(Either::Left(Json(PurgeResult {
+        tree: tree.into(),
+        removed,
+    })))
+}
+
+/// Background task health. Like `/status`, the page itself is public; its buttons ask for the admin
+/// token and send it with the trigger requests.
+#[get("/admin/tasks")]
+pub fn task_status() -> ResponseResult<Html<String>> {
+    let running = tasks::running();
+    let mut table = Table::new(
+        [
+            ("Task", ""),
+            ("Running", ""),
+            ("Last Started", ""),
+            ("Last Finished", ""),
+            ("Last Duration", ""),
+            ("Runs", ""),
+            ("Failures", ""),
+        ],
+        "text-right",
+        "none",
+    );
+    table.col_class[0] = "text-left";
+    let mut errors = Vec::new();
+    for task in Task::ALL {
+        let status = tasks::status(task)?;
+        table.push(row![
+            task.name(),
+            if running == Some(task) { "yes" } else { "no" },
+            time(status.last_start),
+            time(status.last_finish),
+            status
+                .last_duration_secs
+                .map(|secs| format!("{:.1}s", secs))
+                .unwrap_or_default(),
+            status.runs,
+            status.failures,
+        ]);
+        if let Some(error) = status.last_error {
+            errors.push(TaskError {
+                task: task.name(),
+                at: time(status.last_error_at),
+                error,
+            });
+        }
+    }
+    Ok(Html(
+        TasksPage { table, errors }
+            .render()
+            .map_err(anyhow::Error::from)?,
+    ))
+}
+
+fn time(time: Option<DateTime<Utc>>) -> String {
+    time.map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_default()
+}
+
+#[derive(Template)]
+#[template(path = "tasks.html")]
+struct TasksPage {
+    table: Table<7>,
+    errors: Vec<TaskError>,
+}
+
+struct TaskError {
+    task: &'static str,
+    at: String,
+    error: String,
+}
+
+/// Runs the update task as soon as the current task (if any) finishes.
+#[post("/admin/tasks/update")]
+pub fn trigger_update(_admin: Admin) -> Status {
+    trigger(Trigger::Update)
+}
+
+/// Rebuilds the database as soon as the current task (if any) finishes, as if `DB_VERSION` had
+/// changed.
+#[post("/admin/tasks/rebuild")]
+pub fn trigger_rebuild(_admin: Admin) -> Status {
+    trigger(Trigger::Rebuild)
+}
+
+fn trigger(trigger: Trigger) -> Status {
+    if tasks::trigger(trigger) {
+        log::info!("{:?} triggered by admin", trigger);
+        Status::Accepted
+    } else {
+        // background tasks are disabled
+        Status::ServiceUnavailable
+    }
+}
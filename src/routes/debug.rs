@@ -1,13 +1,19 @@
 use crate::debug::LogEntry;
-use crate::game::{DEBUG_TREE, GAME_STATS_TREE};
+use crate::game::{DEBUG_TREE, GAME_STATS_TREE, SCORE_ONLY_TREE};
+use crate::routes::season::rocket_uri_macro_season_player_batting;
 use crate::routes::ResponseResult;
+use crate::seasons::Season;
+use crate::table::{row, Table};
 use crate::DB;
+use crate::{feed, schedule};
 use anyhow::Result;
 use askama::Template;
-use rocket::get;
 use rocket::response::content::Html;
+use rocket::serde::json::Json;
+use rocket::{get, uri};
+use serde_json::Value;
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use uuid::Uuid;
 
 #[get("/errors")]
@@ -15,12 +21,95 @@ pub fn errors() -> ResponseResult<Html<String>> {
     Ok(Html(
         ErrorDashboard {
             errors: load_errors()?,
+            unresolved_names: crate::repair::unresolved()?,
+            mismatches: crate::reconcile::load()?,
         }
         .render()
         .map_err(anyhow::Error::from)?,
     ))
 }
 
+#[get("/status")]
+pub fn status() -> ResponseResult<Html<String>> {
+    Ok(Html(
+        StatusPage {
+            table: load_status()?,
+        }
+        .render()
+        .map_err(anyhow::Error::from)?,
+    ))
+}
+
+/// Counts each recorded season's games by processing status. Scheduled games are those in the
+/// cached schedule (days where every game is complete) or already processed.
+fn load_status() -> Result<Table<5>> {
+    let stats_tree = DB.open_tree(GAME_STATS_TREE)?;
+    let score_only_tree = DB.open_tree(SCORE_ONLY_TREE)?;
+    let failed = load_errors()?
+        .into_values()
+        .flatten()
+        .collect::<BTreeSet<_>>();
+
+    let mut table = Table::new(
+        [
+            ("Season", ""),
+            ("Scheduled Games", "Scheduled"),
+            ("Processed Games", "Processed"),
+            ("Failed Games", "Failed"),
+            ("Pending Games", "Pending"),
+        ],
+        "text-right",
+        "number",
+    );
+    table.col_class[0] = "text-left";
+    table.sort_method[0] = "none";
+
+    for season in Season::recorded()? {
+        let mut scheduled = schedule::cached_games(&season)?
+            .into_iter()
+            .collect::<BTreeSet<_>>();
+        scheduled.extend(schedule::games(&season)?.into_iter().map(|(_, e)| e.id));
+
+        let (mut processed, mut failures) = (0, 0);
+        for id in &scheduled {
+            if stats_tree.contains_key(id.as_bytes())?
+                || score_only_tree.contains_key(id.as_bytes())?
+            {
+                processed += 1;
+            } else if failed.contains(id) {
+                failures += 1;
+            }
+        }
+        table.push(row![
+            season.to_string(),
+            scheduled.len(),
+            processed,
+            failures,
+            scheduled.len() - processed - failures,
+        ]);
+        table.set_href(
+            0,
+            uri!(season_player_batting(
+                sim = &season.sim,
+                season = season.season,
+                qualified = _,
+                exhibition = _,
+                sort = _,
+                order = _,
+                page = _
+            )),
+        );
+    }
+
+    Ok(table)
+}
+
+#[derive(Template)]
+#[template(path = "status.html")]
+struct StatusPage {
+    table: Table<5>,
+}
+
 fn load_errors() -> Result<BTreeMap<String, Vec<Uuid>>> {
     let debug_tree = DB.open_tree(DEBUG_TREE)?;
     let stats_tree = DB.open_tree(GAME_STATS_TREE)?;
@@ -44,6 +133,8 @@ fn load_errors() -> Result<BTreeMap<String, Vec<Uuid>>> {
 #[template(path = "error_dashboard.html")]
 struct ErrorDashboard {
     errors: BTreeMap<String, Vec<Uuid>>,
+    unresolved_names: Vec<Uuid>,
+    mismatches: Vec<crate::reconcile::Mismatch>,
 }
 
 #[get("/game/<id>/debug")]
@@ -58,6 +149,35 @@ pub fn debug(id: Uuid) -> ResponseResult<Option<Html<String>>> {
     })
 }
 
+/// The game's debug log as JSON, including the state patch for each event. With one or more `ty`
+/// parameters (`?ty=10&ty=11`), only entries for those event types are returned.
+#[get("/game/<id>/debug.json?<ty>")]
+pub fn debug_json(id: Uuid, ty: Vec<u16>) -> ResponseResult<Option<Json<Vec<LogEntry>>>> {
+    Ok(load_debug(id)?.map(|mut log| {
+        if !ty.is_empty() {
+            log.retain(|entry| entry.ty().map_or(false, |t| ty.contains(&t.into())));
+        }
+        Json(log)
+    }))
+}
+
+/// The game's feed as it was processed: the cached sachet feed, in processing order. Only games
+/// that have been processed (or failed to) are served, so this never fetches arbitrary IDs from
+/// upstream.
+#[get("/game/<id>/feed.json")]
+pub async fn feed_json(id: Uuid) -> ResponseResult<Option<Json<Vec<Value>>>> {
+    Ok(if is_known(id)? {
+        Some(Json(feed::load_raw(id).await?))
+    } else {
+        None
+    })
+}
+
+fn is_known(id: Uuid) -> Result<bool> {
+    Ok(DB.open_tree(GAME_STATS_TREE)?.contains_key(id.as_bytes())?
+        || DB.open_tree(DEBUG_TREE)?.contains_key(id.as_bytes())?)
+}
+
 fn load_debug(id: Uuid) -> Result<Option<Vec<LogEntry>>> {
     let tree = DB.open_tree(DEBUG_TREE)?;
     Ok(match tree.get(id.as_bytes())? {
@@ -74,6 +194,13 @@ impl LogEntry {
         }
     }
 
+    fn event_type(&self) -> String {
+        match self.ty() {
+            Some(ty) => format!("[{}] ", ty),
+            None => String::new(),
+        }
+    }
+
     fn info(&self) -> Cow<'_, str> {
         match self {
             LogEntry::Ok { patch, .. } => patch
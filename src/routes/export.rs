@@ -1,11 +1,32 @@
+use crate::csv::{stream_csv, Csv};
 use crate::export::{Export, WithLeagueStats};
-use crate::summary::{self, SeasonSummary};
-use crate::{csv::Csv, routes::ResponseResult, seasons::Season};
+use crate::routes::{ResponseResult, SeasonParam};
+use crate::snapshot::{self, Snapshot, Version};
+use crate::summary::{self, CareerSummary, SeasonSummary};
+use crate::{etag, game, names, schedule, seasons::Season};
 use anyhow::Result;
+use indexmap::IndexMap;
 use rocket::get;
+use rocket::http::ContentType;
+use rocket::response::stream::TextStream;
 use rocket::serde::json::Json;
+use rocket::tokio::task::spawn_blocking;
 use std::collections::BTreeMap;
+use std::io::{Cursor, Write};
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
+use zip::write::{FileOptions, ZipWriter};
+
+/// The number of seasons' game archives to keep built.
+const GAMES_ZIP_CAPACITY: usize = 2;
+
+/// A built game archive, with the data generation (see `etag::generation`) it was built at.
+type BuiltZip = (u64, Arc<Vec<u8>>);
+
+lazy_static::lazy_static! {
+    /// Least recently used first.
+    static ref GAMES_ZIPS: Mutex<IndexMap<Season, BuiltZip>> = Mutex::new(IndexMap::new());
+}
 
 // "art is supposed to provoke strong emotions and this sure does" -- allie
 macro_rules! export {
@@ -59,7 +80,9 @@ macro_rules! season_inner {
 export! {
     season_player_summary_csv => "/season/<sim>/<season>/export.csv",
     season_player_summary_json => "/season/<sim>/<season>/export.json",
-    |sim: String, season: u16| -> (Uuid, Export<WithLeagueStats<SeasonSummary>>) {
+    |sim: String, season: u16, _valid: SeasonParam|
+        -> (Uuid, Export<WithLeagueStats<SeasonSummary>>)
+    {
         season_inner!(season_player_summary, Season { sim, season })
     }
 }
@@ -67,7 +90,187 @@ export! {
 export! {
     season_team_summary_csv => "/season/team/<sim>/<season>/export.csv",
     season_team_summary_json => "/season/team/<sim>/<season>/export.json",
-    |sim: String, season: u16| -> (Uuid, Export<WithLeagueStats<SeasonSummary>>) {
+    |sim: String, season: u16, _valid: SeasonParam|
+        -> (Uuid, Export<WithLeagueStats<SeasonSummary>>)
+    {
         season_inner!(season_team_summary, Season { sim, season })
     }
 }
+
+export! {
+    team_player_summary_csv => "/team/<id>/<sim>/<season>/export.csv",
+    team_player_summary_json => "/team/<id>/<sim>/<season>/export.json",
+    |id: Uuid, sim: String, season: u16, _valid: SeasonParam|
+        -> (Uuid, Export<WithLeagueStats<SeasonSummary>>)
+    {
+        let season = Season { sim, season };
+        let recorded = match names::team_name(id)? {
+            Some(name) => name.all_seasons()?.into_iter().any(|(s, _)| s == season),
+            None => false,
+        };
+        if !recorded {
+            return Ok(None);
+        }
+
+        let summary = summary::team_player_summary(id, &season)?;
+        let league = summary::league_totals(&season)?;
+        summary.into_iter().map(move |summary| {
+            Ok((
+                summary.id,
+                Export(WithLeagueStats {
+                    inner: summary,
+                    league,
+                }),
+            ))
+        })
+    }
+}
+
+/// Career regular season totals for every player, optionally limited to one sim or season. Rows
+/// are read and serialized as they're sent rather than all at once.
+#[get("/players/export.csv?<sim>&<season>")]
+pub async fn players_export_csv(
+    sim: Option<String>,
+    season: Option<u16>,
+) -> ResponseResult<(ContentType, TextStream![String])> {
+    let careers = spawn_blocking(move || career_summary(sim.as_deref(), season))
+        .await
+        .map_err(anyhow::Error::from)??;
+    let rows = stream_csv(careers.map(|career| career.map(Export)));
+    Ok((
+        ContentType::CSV,
+        TextStream! {
+            for row in rows {
+                match row {
+                    Ok(row) => yield row,
+                    Err(err) => {
+                        log::error!("while streaming player export: {:#}", err);
+                        break;
+                    }
+                }
+            }
+        },
+    ))
+}
+
+/// The same totals as `players_export_csv`, keyed by player ID.
+#[get("/players/export.json?<sim>&<season>")]
+pub async fn players_export_json(
+    sim: Option<String>,
+    season: Option<u16>,
+) -> ResponseResult<Json<BTreeMap<Uuid, Export<CareerSummary>>>> {
+    let careers = spawn_blocking(move || career_summary(sim.as_deref(), season))
+        .await
+        .map_err(anyhow::Error::from)??;
+    Ok(Json(
+        careers
+            .map(|career| career.map(|career| (career.id, Export(career))))
+            .collect::<Result<_>>()?,
+    ))
+}
+
+fn career_summary(
+    sim: Option<&str>,
+    season: Option<u16>,
+) -> Result<impl Iterator<Item = Result<CareerSummary>>> {
+    let mut seasons = Season::recorded()?
+        .into_iter()
+        .filter(|s| sim.map_or(true, |sim| s.sim.eq_ignore_ascii_case(sim)))
+        .filter(|s| season.map_or(true, |season| s.season == season))
+        .collect::<Vec<_>>();
+    seasons.sort();
+    summary::career_player_summary(seasons)
+}
+
+/// Lists the frozen snapshots of a season's exports. Snapshot URLs never change once created.
+#[get("/season/<sim>/<season>/snapshots.json")]
+pub fn snapshots(
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Json<Vec<Snapshot>>>> {
+    let season = Season { sim, season };
+    if !Season::recorded()?.contains(&season) {
+        return Ok(None);
+    }
+    Ok(Some(Json(snapshot::list(&season)?)))
+}
+
+/// Serves one file of a snapshot: `players.csv`, `players.json`, `teams.csv`, or `teams.json`.
+#[get("/season/<sim>/<season>/<version>/<file>", rank = 2)]
+pub fn snapshot_file(
+    sim: String,
+    season: u16,
+    version: Version,
+    file: &str,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<(ContentType, String)>> {
+    let files = match snapshot::load(&Season { sim, season }, version.0)? {
+        Some(files) => files,
+        None => return Ok(None),
+    };
+    Ok(match file {
+        "players.csv" => Some((ContentType::CSV, files.players_csv)),
+        "players.json" => Some((ContentType::JSON, files.players_json)),
+        "teams.csv" => Some((ContentType::CSV, files.teams_csv)),
+        "teams.json" => Some((ContentType::JSON, files.teams_json)),
+        _ => None,
+    })
+}
+
+/// Every processed game in a season as a zip archive of JSON box scores, in the same format as
+/// `bricks process` prints, named `day-<day>/<game id>.json`. Archives are built once per data
+/// generation and kept for the most recently requested seasons.
+#[get("/season/<sim>/<season>/games.zip")]
+pub async fn season_games_zip(
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<(ContentType, Vec<u8>)>> {
+    let season = Season { sim, season };
+    if !Season::recorded()?.contains(&season) {
+        return Ok(None);
+    }
+    let zip = spawn_blocking(move || cached_games_zip(season))
+        .await
+        .map_err(anyhow::Error::from)??;
+    Ok(Some((ContentType::ZIP, zip.to_vec())))
+}
+
+fn cached_games_zip(season: Season) -> Result<Arc<Vec<u8>>> {
+    let generation = etag::generation();
+    {
+        let mut cache = GAMES_ZIPS.lock().unwrap();
+        if let Some((built, zip)) = cache.shift_remove(&season) {
+            if built == generation {
+                cache.insert(season, (built, zip.clone()));
+                return Ok(zip);
+            }
+        }
+    }
+
+    let zip = Arc::new(games_zip(&season)?);
+    // a zip built while the data changed is served, but not kept
+    if etag::generation() == generation {
+        let mut cache = GAMES_ZIPS.lock().unwrap();
+        if cache.len() >= GAMES_ZIP_CAPACITY {
+            cache.shift_remove_index(0);
+        }
+        cache.insert(season, (generation, zip.clone()));
+    }
+    Ok(zip)
+}
+
+fn games_zip(season: &Season) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (_, entry) in schedule::games(season)? {
+        // score-only games have no box score
+        if let Some(game) = game::load(entry.id)? {
+            zip.start_file(format!("day-{}/{}.json", entry.day + 1, entry.id), options)?;
+            serde_json::to_writer_pretty(&mut zip, &game)?;
+            zip.write_all(b"\n")?;
+        }
+    }
+    Ok(zip.finish()?.into_inner())
+}
@@ -1,71 +1,148 @@
-use crate::game::{Game, Stats, Team, DEBUG_TREE, GAME_STATS_TREE};
-use crate::names::box_names;
+use crate::game::{process_live, Game, Stats, Team, DEBUG_TREE, GAME_STATS_TREE};
+use crate::live::{scheduled, subscribe};
+use crate::names::{box_names, TeamName};
 use crate::routes::player::rocket_uri_macro_player;
 use crate::routes::ResponseResult;
+use crate::schedule;
 use crate::table::{row, Table};
+use crate::tombstone::{self, Tombstone};
+use crate::win_probability::{self, WinProbability};
 use crate::DB;
 use anyhow::Result;
 use askama::Template;
 use rocket::response::content::Html;
-use rocket::{get, uri};
+use rocket::response::stream::{Event, EventStream};
+use rocket::serde::json::Json;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{get, uri, Shutdown};
 use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 #[get("/game/<id>")]
-pub fn game(id: Uuid) -> ResponseResult<Option<Html<String>>> {
+pub async fn game(id: Uuid) -> ResponseResult<Option<Html<String>>> {
     Ok(match load_game(id)? {
-        GameLoad::Ok(game) => {
-            let mut names = HashMap::new();
-            let mut short_names = HashMap::new();
-            for team in game.teams() {
-                names.extend(box_names(&team.player_names, true));
-                short_names.extend(box_names(&team.player_names, false));
-            }
-
-            Some(Html(
-                GamePage {
-                    id,
-                    winning_pitcher: short_names
-                        .get(&game.winner().pitcher_of_record)
-                        .cloned()
-                        .unwrap_or_default(),
-                    losing_pitcher: short_names
-                        .get(&game.loser().pitcher_of_record)
-                        .cloned()
-                        .unwrap_or_default(),
-                    saving_pitcher: game
-                        .winner()
-                        .saving_pitcher
-                        .map(|pitcher| short_names.get(&pitcher).cloned().unwrap_or_default()),
-                    batters_tables: [
-                        batters_table(&game.away, &names),
-                        batters_table(&game.home, &names),
-                    ],
-                    batting_lines: [
-                        batting_lines(&game.away, &short_names),
-                        batting_lines(&game.home, &short_names),
-                    ],
-                    baserunning_lines: [
-                        baserunning_lines(&game.away, &short_names),
-                        baserunning_lines(&game.home, &short_names),
-                    ],
-                    pitchers_tables: [
-                        pitchers_table(&game.away, &names),
-                        pitchers_table(&game.home, &names),
-                    ],
-                    end_lines: end_lines(&game, &short_names),
-                    game,
-                }
-                .render()
-                .map_err(anyhow::Error::from)?,
-            ))
-        }
+        GameLoad::Ok(game) => Some(render_game(id, game, false)?),
         GameLoad::Failed => Some(Html(
             GameFailedPage { id }
                 .render()
                 .map_err(anyhow::Error::from)?,
         )),
-        GameLoad::NotFound => None,
+        // only games in the current schedule are fetched from upstream, so that requests for
+        // unknown IDs can't spend the update task's upstream budget
+        GameLoad::NotFound => match scheduled(id) {
+            Some(season) => match process_live(season, id).await {
+                Ok(Some((game, finished))) => Some(render_game(id, game, !finished)?),
+                Ok(None) => None,
+                Err(err) => {
+                    log::warn!("failed to process live game {}: {:#}", id, err);
+                    None
+                }
+            },
+            None => None,
+        },
+    })
+}
+
+fn render_game(id: Uuid, game: Game, in_progress: bool) -> Result<Html<String>> {
+    let mut names = HashMap::new();
+    let mut short_names = HashMap::new();
+    for team in game.teams() {
+        names.extend(box_names(&team.player_names, true));
+        short_names.extend(box_names(&team.player_names, false));
+    }
+
+    Ok(Html(
+        GamePage {
+            id,
+            in_progress,
+            removed: tombstone::load(id)?,
+            winning_pitcher: short_names
+                .get(&game.winner().pitcher_of_record)
+                .cloned()
+                .unwrap_or_default(),
+            losing_pitcher: short_names
+                .get(&game.loser().pitcher_of_record)
+                .cloned()
+                .unwrap_or_default(),
+            saving_pitcher: game
+                .winner()
+                .saving_pitcher
+                .map(|pitcher| short_names.get(&pitcher).cloned().unwrap_or_default()),
+            batters_tables: [
+                batters_table(&game.away, &names),
+                batters_table(&game.home, &names),
+            ],
+            batting_lines: [
+                batting_lines(&game.away, &short_names),
+                batting_lines(&game.home, &short_names),
+            ],
+            baserunning_lines: [
+                baserunning_lines(&game.away, &short_names),
+                baserunning_lines(&game.home, &short_names),
+            ],
+            pitchers_tables: [
+                pitchers_table(&game.away, &names),
+                pitchers_table(&game.home, &names),
+            ],
+            end_lines: end_lines(&game, &short_names),
+            line_score: line_score(&game),
+            team_nav: game
+                .teams()
+                .map(|team| team_nav(&game, id, team))
+                .collect::<Result<_>>()?,
+            stars: game
+                .stars
+                .iter()
+                .filter_map(|id| {
+                    let team = game.teams().find(|team| team.stats.contains_key(id))?;
+                    Some(Star {
+                        id: *id,
+                        name: team.player_names.get(id).cloned().unwrap_or_default(),
+                        team: team.name.shorthand.clone(),
+                    })
+                })
+                .collect(),
+            win_probability_points: win_probability::svg_points(&game.win_probability),
+            game,
+        }
+        .render()?,
+    ))
+}
+
+/// The home team's win probability after each play that changed it (see `win_probability`).
+#[get("/api/game/<id>/win_probability.json")]
+pub fn game_win_probability(id: Uuid) -> ResponseResult<Option<Json<Vec<WinProbability>>>> {
+    Ok(match load_game(id)? {
+        GameLoad::Ok(game) => Some(Json(game.win_probability)),
+        GameLoad::Failed | GameLoad::NotFound => None,
+    })
+}
+
+/// Streams score updates for a game in progress as server-sent events. Updates are published by
+/// the background update task, so they arrive every couple of minutes. Only games in the current
+/// schedule that aren't complete yet can be subscribed to.
+#[get("/live/<id>")]
+pub fn live(id: Uuid, mut shutdown: Shutdown) -> Option<EventStream![]> {
+    let (last, mut receiver) = subscribe(id)?;
+    Some(EventStream! {
+        let mut finished = false;
+        if let Some(update) = last {
+            finished = update.finished;
+            yield Event::json(&update);
+        }
+        while !finished {
+            let update = select! {
+                update = receiver.recv() => match update {
+                    Ok(update) => update,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+            finished = update.finished;
+            yield Event::json(&update);
+        }
     })
 }
 
@@ -73,6 +150,8 @@ pub fn game(id: Uuid) -> ResponseResult<Option<Html<String>>> {
 #[template(path = "game.html")]
 struct GamePage {
     id: Uuid,
+    in_progress: bool,
+    removed: Option<Tombstone>,
     game: Game,
     winning_pitcher: String,
     losing_pitcher: String,
@@ -80,8 +159,39 @@ struct GamePage {
     batters_tables: [Table<8>; 2],
     batting_lines: [Vec<Line>; 2],
     baserunning_lines: [Vec<Line>; 2],
-    pitchers_tables: [Table<7>; 2],
+    pitchers_tables: [Table<8>; 2],
     end_lines: Vec<Line>,
+    line_score: LineScore,
+    stars: Vec<Star>,
+    team_nav: Vec<TeamNav>,
+    win_probability_points: String,
+}
+
+struct TeamNav {
+    shorthand: String,
+    prev: Option<Uuid>,
+    next: Option<Uuid>,
+}
+
+/// Finds a team's previous and next games in the season around this game.
+fn team_nav(game: &Game, id: Uuid, team: &Team) -> Result<TeamNav> {
+    let schedule = schedule::schedule(team.id, &game.season)?;
+    let index = schedule.iter().position(|(_, entry)| entry.id == id);
+    Ok(TeamNav {
+        shorthand: team.name.shorthand.clone(),
+        prev: index
+            .and_then(|index| index.checked_sub(1))
+            .map(|index| schedule[index].1.id),
+        next: index
+            .and_then(|index| schedule.get(index + 1))
+            .map(|(_, entry)| entry.id),
+    })
+}
+
+struct Star {
+    id: Uuid,
+    name: String,
+    team: String,
 }
 
 #[derive(Template)]
@@ -92,6 +202,52 @@ struct GameFailedPage {
 
 // =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
 
+struct LineScore {
+    innings: Vec<u16>,
+    rows: [LineScoreRow; 2],
+}
+
+struct InningLine {
+    runs: u16,
+    hits: u16,
+}
+
+struct LineScoreRow {
+    id: Uuid,
+    name: TeamName,
+    /// `None` if the team did not bat in that inning
+    innings: Vec<Option<InningLine>>,
+    runs: u16,
+    hits: u32,
+    errors: u16,
+}
+
+fn line_score(game: &Game) -> LineScore {
+    let innings = game.away.inning_runs.keys().copied().collect::<Vec<_>>();
+    let row = |team: &Team| LineScoreRow {
+        id: team.id,
+        name: team.name.clone(),
+        innings: innings
+            .iter()
+            .map(|inning| {
+                team.inning_runs.get(inning).map(|runs| InningLine {
+                    runs: *runs,
+                    hits: team.inning_hits.get(inning).copied().unwrap_or_default(),
+                })
+            })
+            .collect(),
+        runs: team.runs(),
+        hits: team.hits(),
+        errors: team.errors,
+    };
+    LineScore {
+        rows: [row(&game.away), row(&game.home)],
+        innings,
+    }
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
 fn batters_table(team: &Team, names: &HashMap<Uuid, String>) -> Table<8> {
     let mut table = Table::new(
         [
@@ -141,7 +297,7 @@ fn batters_table(team: &Team, names: &HashMap<Uuid, String>) -> Table<8> {
                         table.set_class("pl-4");
                     }
                     seen.insert(*batter);
-                    table.set_href(0, uri!(player(id = batter)));
+                    table.set_href(0, uri!(player(id = batter, exhibition = _)));
                 }
             }
         }
@@ -150,13 +306,14 @@ fn batters_table(team: &Team, names: &HashMap<Uuid, String>) -> Table<8> {
     table
 }
 
-fn pitchers_table(team: &Team, names: &HashMap<Uuid, String>) -> Table<7> {
+fn pitchers_table(team: &Team, names: &HashMap<Uuid, String>) -> Table<8> {
     let mut table = Table::new(
         [
             ("", ""),
             ("Innings Pitched", "IP"),
             ("Hits Allowed", "H"),
             ("Runs Allowed", "R"),
+            ("Earned Runs Allowed", "ER"),
             ("Bases on Balls (Walks)", "BB"),
             ("Strikeouts", "SO"),
             ("Home Runs Allowed", "HR"),
@@ -173,12 +330,13 @@ fn pitchers_table(team: &Team, names: &HashMap<Uuid, String>) -> Table<7> {
                 names.get(pitcher).cloned().unwrap_or_default(),
                 stats.innings_pitched(),
                 stats.hits_allowed,
+                stats.runs_allowed(),
                 stats.earned_runs,
                 stats.walks_issued,
                 stats.struck_outs,
                 stats.home_runs_allowed,
             ]);
-            table.set_href(0, uri!(player(id = pitcher)));
+            table.set_href(0, uri!(player(id = pitcher, exhibition = _)));
         }
     }
 
@@ -257,6 +415,26 @@ fn batting_lines(team: &Team, names: &HashMap<Uuid, String>) -> Vec<Line> {
             abbr: "GIDP",
             data: build_line(&team.stats, names, |s| s.double_plays_grounded_into, false),
         },
+        Line {
+            title: "Pitches Seen",
+            abbr: "",
+            data: build_line(&team.stats, names, |s| s.pitches_seen, false),
+        },
+        Line {
+            title: "Called Strikes Taken",
+            abbr: "",
+            data: build_line(&team.stats, names, |s| s.strikes_taken, false),
+        },
+        Line {
+            title: "Swinging Strikes",
+            abbr: "",
+            data: build_line(&team.stats, names, |s| s.swinging_strikes, false),
+        },
+        Line {
+            title: "Foul Balls",
+            abbr: "",
+            data: build_line(&team.stats, names, |s| s.foul_balls, false),
+        },
     ];
     lines.retain(|line| !line.data.is_empty());
 
@@ -295,11 +473,41 @@ fn baserunning_lines(team: &Team, names: &HashMap<Uuid, String>) -> Vec<Line> {
             abbr: "SB",
             data: build_line(&team.stats, names, |s| s.stolen_bases, false),
         },
+        Line {
+            title: "Stolen Bases of Second",
+            abbr: "SB2",
+            data: build_line(&team.stats, names, |s| s.stolen_bases_second, false),
+        },
+        Line {
+            title: "Stolen Bases of Third",
+            abbr: "SB3",
+            data: build_line(&team.stats, names, |s| s.stolen_bases_third, false),
+        },
+        Line {
+            title: "Stolen Bases of Home",
+            abbr: "SBH",
+            data: build_line(&team.stats, names, |s| s.stolen_bases_home, false),
+        },
         Line {
             title: "Caught Stealing",
             abbr: "CS",
             data: build_line(&team.stats, names, |s| s.caught_stealing, false),
         },
+        Line {
+            title: "Caught Stealing Second",
+            abbr: "CS2",
+            data: build_line(&team.stats, names, |s| s.caught_stealing_second, false),
+        },
+        Line {
+            title: "Caught Stealing Third",
+            abbr: "CS3",
+            data: build_line(&team.stats, names, |s| s.caught_stealing_third, false),
+        },
+        Line {
+            title: "Caught Stealing Home",
+            abbr: "CSH",
+            data: build_line(&team.stats, names, |s| s.caught_stealing_home, false),
+        },
         Line {
             title: "Cold Runner in Scoring Position",
             abbr: "CRiSP",
@@ -337,6 +545,16 @@ fn end_lines(game: &Game, names: &HashMap<Uuid, String>) -> Vec<Line> {
                 false,
             ),
         },
+        Line {
+            title: "Inherited runners-scored",
+            abbr: "",
+            data: build_line(
+                game.away.stats.iter().chain(&game.home.stats),
+                names,
+                |s| s.inherited_runners_scored(),
+                false,
+            ),
+        },
         Line {
             title: "Batters faced",
             abbr: "",
@@ -347,6 +565,20 @@ fn end_lines(game: &Game, names: &HashMap<Uuid, String>) -> Vec<Line> {
                 false,
             ),
         },
+        Line {
+            title: "Time of game",
+            abbr: "T",
+            data: game.time_of_game().unwrap_or_default(),
+        },
+        Line {
+            title: "Feed events",
+            abbr: "",
+            data: if game.event_count > 0 {
+                game.event_count.to_string()
+            } else {
+                String::new()
+            },
+        },
     ];
     lines.retain(|line| !line.data.is_empty());
     lines
@@ -1,26 +1,127 @@
+pub mod admin;
 pub mod debug;
 pub mod export;
 pub mod game;
 pub mod player;
 pub mod season;
+pub mod sitemap;
 pub mod team;
 
+use crate::changelog::CHANGELOG;
+use crate::compression;
 use crate::seasons::Season;
+use crate::summary;
 use askama::Template;
-use rocket::http::{uri::Origin, ContentType};
-use rocket::response::{content::Html, status::BadRequest, Debug, Redirect};
-use rocket::{get, Either};
+use rocket::http::{uri::Origin, ContentType, RawStr, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{self, content::Html, status::BadRequest, Debug, Redirect, Responder};
+use rocket::serde::json::Json;
+use rocket::{get, Either, Response};
+use std::fmt::Write;
+use std::io::Cursor;
 
 type ResponseResult<T> = std::result::Result<T, Debug<anyhow::Error>>;
 
+/// Request guard for routes with `<sim>` and `<season>` parameters. Succeeds only if they name a
+/// known season exactly; case mismatches and era-name aliases (e.g. `/batting/Gamma%202/1`) are
+/// redirected to the canonical URI by `redirect_canonical`, and anything else is a 404.
+pub struct SeasonParam;
+
+struct CanonicalUri(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SeasonParam {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<SeasonParam, ()> {
+        let route = match req.route() {
+            Some(route) => route,
+            None => return Outcome::Forward(()),
+        };
+        let position = |name| {
+            route
+                .uri
+                .path()
+                .split('/')
+                .filter(|s| !s.is_empty())
+                .position(|s| s == name)
+        };
+        let (sim_idx, season_idx) = match (position("<sim>"), position("<season>")) {
+            (Some(sim_idx), Some(season_idx)) => (sim_idx, season_idx),
+            _ => return Outcome::Forward(()),
+        };
+
+        let path = req.uri().path();
+        let segments = path.segments();
+        let (sim, season) = match (
+            segments.get(sim_idx),
+            segments.get(season_idx).and_then(|s| s.parse().ok()),
+        ) {
+            (Some(sim), Some(season)) => (sim, season),
+            _ => return Outcome::Failure((Status::NotFound, ())),
+        };
+
+        let canonical = match Season::resolve(sim, season) {
+            Ok(Some(canonical)) => canonical,
+            Ok(None) => return Outcome::Failure((Status::NotFound, ())),
+            Err(err) => {
+                log::error!("{:#}", err);
+                return Outcome::Failure((Status::InternalServerError, ()));
+            }
+        };
+        if canonical.sim == sim && canonical.season == season {
+            return Outcome::Success(SeasonParam);
+        }
+
+        let mut uri = String::new();
+        for (i, segment) in path.segments().enumerate() {
+            uri.push('/');
+            if i == sim_idx {
+                uri.push_str(&canonical.sim);
+            } else if i == season_idx {
+                write!(uri, "{}", canonical.season).ok();
+            } else {
+                uri.push_str(RawStr::new(segment).percent_encode().as_str());
+            }
+        }
+        if let Some(query) = req.uri().query() {
+            uri.push('?');
+            uri.push_str(query.as_str());
+        }
+        req.local_cache(|| CanonicalUri(Some(uri)));
+        // Rocket only allows catchers for error statuses, so this fails as a 404 and is rewritten
+        // into a redirect by `redirect_canonical`
+        Outcome::Failure((Status::NotFound, ()))
+    }
+}
+
+/// Response fairing callback that turns a 404 from `SeasonParam` into a permanent redirect to the
+/// canonical URI it found.
+pub fn redirect_canonical(req: &Request<'_>, response: &mut Response<'_>) {
+    if response.status() != Status::NotFound {
+        return;
+    }
+    if let Some(uri) = &req.local_cache(|| CanonicalUri(None)).0 {
+        response.set_status(Status::MovedPermanently);
+        response.remove_header("Content-Type");
+        response.set_raw_header("Location", uri.clone());
+        response.set_sized_body(0, std::io::Cursor::new(Vec::new()));
+    }
+}
+
+/// Redirects to the latest season with regular season games, if there is one; tournaments are
+/// listed separately in the season menu.
 #[get("/")]
 pub fn index() -> ResponseResult<Option<Redirect>> {
-    Ok(Season::recorded()
-        .map_err(anyhow::Error::from)?
-        .into_iter()
-        .rev()
-        .next()
-        .map(|season| Redirect::to(season.uri(&true, &true))))
+    let seasons = Season::recorded().map_err(anyhow::Error::from)?;
+    let mut latest = seasons.last();
+    for season in seasons.iter().rev() {
+        if summary::has_regular_season(season)? {
+            latest = Some(season);
+            break;
+        }
+    }
+    Ok(latest.map(|season| Redirect::to(season.uri(&true, &true))))
 }
 
 #[get("/attribution")]
@@ -32,6 +133,28 @@ pub fn attribution() -> ResponseResult<Html<String>> {
     Ok(Html(Attribution.render().map_err(anyhow::Error::from)?))
 }
 
+#[get("/changelog")]
+pub fn changelog() -> ResponseResult<Html<String>> {
+    #[derive(Template)]
+    #[template(path = "changelog.html")]
+    struct Changelog {
+        changelog: &'static [crate::changelog::Entry],
+    }
+
+    Ok(Html(
+        Changelog {
+            changelog: &CHANGELOG,
+        }
+        .render()
+        .map_err(anyhow::Error::from)?,
+    ))
+}
+
+#[get("/changelog.json")]
+pub fn changelog_json() -> Json<&'static [crate::changelog::Entry]> {
+    Json(&CHANGELOG)
+}
+
 #[get("/glossary")]
 pub fn glossary() -> ResponseResult<Html<String>> {
     #[derive(Template)]
@@ -41,41 +164,95 @@ pub fn glossary() -> ResponseResult<Html<String>> {
     Ok(Html(Glossary.render().map_err(anyhow::Error::from)?))
 }
 
+/// A static asset, along with a copy gzipped by `build.rs` for clients that accept it.
+pub struct Asset {
+    content_type: ContentType,
+    body: &'static str,
+    gzip: &'static [u8],
+}
+
+impl<'r> Responder<'r, 'static> for Asset {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut builder = Response::build();
+        builder
+            .header(self.content_type)
+            .raw_header("Vary", "Accept-Encoding");
+        if compression::accepts_gzip(req) {
+            builder
+                .raw_header("Content-Encoding", "gzip")
+                .sized_body(self.gzip.len(), Cursor::new(self.gzip));
+        } else {
+            builder.sized_body(self.body.len(), Cursor::new(self.body.as_bytes()));
+        }
+        Ok(builder.finalize())
+    }
+}
+
+/// An asset from the given directory, and its gzipped copy, which `build.rs` names after the
+/// asset's file name.
 macro_rules! asset {
-    ($path:expr) => {
-        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path))
+    ($content_type:expr, $dir:expr, $path:literal, $name:literal) => {
+        Asset {
+            content_type: $content_type,
+            body: include_str!(concat!(env!($dir), "/", $path)),
+            gzip: include_bytes!(concat!(env!("OUT_DIR"), "/", $name, ".gz")),
+        }
     };
 }
 
 #[get("/styles.css")]
-pub fn css() -> (ContentType, &'static str) {
-    (
-        ContentType::CSS,
-        include_str!(concat!(env!("OUT_DIR"), "/styles.css")),
-    )
+pub fn css() -> Asset {
+    asset!(ContentType::CSS, "OUT_DIR", "styles.css", "styles.css")
 }
 
 #[get("/brick.svg")]
-pub fn brick() -> (ContentType, &'static str) {
-    (ContentType::SVG, asset!("brick.svg"))
+pub fn brick() -> Asset {
+    asset!(
+        ContentType::SVG,
+        "CARGO_MANIFEST_DIR",
+        "brick.svg",
+        "brick.svg"
+    )
 }
 
 #[get("/tablesort.min.js")]
-pub fn tablesort() -> (ContentType, &'static str) {
-    (
+pub fn tablesort() -> Asset {
+    asset!(
         ContentType::JavaScript,
-        asset!("node_modules/tablesort/dist/tablesort.min.js"),
+        "CARGO_MANIFEST_DIR",
+        "node_modules/tablesort/dist/tablesort.min.js",
+        "tablesort.min.js"
     )
 }
 
 #[get("/tablesort.number.min.js")]
-pub fn tablesort_number() -> (ContentType, &'static str) {
-    (
+pub fn tablesort_number() -> Asset {
+    asset!(
         ContentType::JavaScript,
-        asset!("node_modules/tablesort/dist/sorts/tablesort.number.min.js"),
+        "CARGO_MANIFEST_DIR",
+        "node_modules/tablesort/dist/sorts/tablesort.number.min.js",
+        "tablesort.number.min.js"
     )
 }
 
+#[get("/metrics")]
+pub fn metrics() -> ResponseResult<(ContentType, String)> {
+    Ok((
+        ContentType::with_params("text", "plain", ("version", "0.0.4")),
+        crate::metrics::render().map_err(anyhow::Error::from)?,
+    ))
+}
+
+/// Readiness probe; fails if the database could not be opened at startup or is still warming up.
+#[get("/ready")]
+pub fn ready() -> Status {
+    if crate::db::is_ready() && crate::db::is_warm() {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    }
+}
+
 #[get("/jump?<path>")]
 pub fn jump(path: String) -> Either<Redirect, BadRequest<()>> {
     match Origin::try_from(path) {
@@ -1,27 +1,120 @@
+use crate::decisions;
+use crate::game::{self, Stats};
+use crate::heatmap::{self, Side, Strip};
+use crate::inhabiting;
+use crate::matchup::{self, MatchupStats};
+use crate::resolve::{self, Resolution};
+use crate::routes::game::rocket_uri_macro_game;
 use crate::routes::team::rocket_uri_macro_team;
-use crate::table::{Table, TotalsTable};
-use crate::{batting, game::Stats, names, pitching, routes::ResponseResult, summary};
+use crate::routes::{ResponseResult, SeasonParam};
+use crate::seasons::Season;
+use crate::splits::{self, Splits};
+use crate::stars::{self, Stars};
+use crate::summary::{self, Summary};
+use crate::table::{row, Table, TotalsTable};
+use crate::{batting, fielding, names, opponents, pitching};
 use anyhow::Result;
 use askama::Template;
-use rocket::response::content::Html;
-use rocket::{get, uri};
+use rocket::response::{content::Html, status::BadRequest};
+use rocket::serde::json::Json;
+use rocket::{get, uri, Either};
 use uuid::Uuid;
 
-#[get("/player/<id>")]
-pub fn player(id: Uuid) -> ResponseResult<Option<Html<String>>> {
-    Ok(match load_player(id)? {
+/// With `?exhibition=true`, stats from special games are listed alongside regular season stats.
+#[get("/player/<id>?<exhibition>")]
+pub async fn player(id: Uuid, exhibition: Option<bool>) -> ResponseResult<Option<Html<String>>> {
+    let stars = stars::player_stars(id).await?;
+    Ok(match load_player(id, exhibition.unwrap_or(false), stars)? {
         Some(player) => Some(Html(player.render().map_err(anyhow::Error::from)?)),
         None => None,
     })
 }
 
-fn load_player(id: Uuid) -> Result<Option<PlayerPage>> {
+#[get("/player/<id>/splits?<segments>")]
+pub fn player_splits(id: Uuid, segments: Option<u16>) -> ResponseResult<Option<Html<String>>> {
+    Ok(
+        match load_player_splits(id, segments.unwrap_or(3).clamp(1, 10))? {
+            Some(page) => Some(Html(page.render().map_err(anyhow::Error::from)?)),
+            None => None,
+        },
+    )
+}
+
+#[get("/player/<id>/vs/<pitcher>/<sim>/<season>")]
+pub fn player_vs_pitcher(
+    id: Uuid,
+    pitcher: Uuid,
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    Ok(
+        match load_player_vs_pitcher(id, pitcher, Season { sim, season })? {
+            Some(page) => Some(Html(page.render().map_err(anyhow::Error::from)?)),
+            None => None,
+        },
+    )
+}
+
+/// Compares up to `MAX_COMPARED` players, given as comma-separated IDs. `add` looks up one more
+/// player by name or ID, for the form on the page.
+#[get("/compare?<players>&<add>")]
+pub fn compare(
+    players: Option<&str>,
+    add: Option<&str>,
+) -> ResponseResult<Option<Either<Html<String>, BadRequest<()>>>> {
+    let mut ids = match parse_players(players.unwrap_or_default()) {
+        Some(ids) => ids,
+        None => return Ok(Some(Either::Right(BadRequest(None)))),
+    };
+    let mut not_found = None;
+    if let Some(add) = add.map(str::trim).filter(|add| !add.is_empty()) {
+        match resolve::resolve_player(add, None)?.first() {
+            Some(resolution) => {
+                if !ids.contains(&resolution.id) {
+                    ids.push(resolution.id);
+                }
+            }
+            None => not_found = Some(add.to_owned()),
+        }
+    }
+    if ids.len() > MAX_COMPARED {
+        return Ok(Some(Either::Right(BadRequest(None))));
+    }
+    Ok(match load_compare(&ids, not_found)? {
+        Some(page) => Some(Either::Left(Html(
+            page.render().map_err(anyhow::Error::from)?,
+        ))),
+        None => None,
+    })
+}
+
+#[get("/api/player/<id>/decisions.json")]
+pub fn player_decisions(id: Uuid) -> ResponseResult<Option<Json<Vec<decisions::Entry>>>> {
+    Ok(match names::player_name(id)? {
+        Some(_) => Some(Json(decisions::load(id)?)),
+        None => None,
+    })
+}
+
+#[get("/api/player/resolve?<q>&<era>")]
+pub fn resolve_player(q: &str, era: Option<&str>) -> ResponseResult<Json<Vec<Resolution>>> {
+    Ok(Json(resolve::resolve_player(q, era)?))
+}
+
+fn load_player(
+    id: Uuid,
+    exhibition: bool,
+    stars: Vec<(Season, Stars)>,
+) -> Result<Option<PlayerPage>> {
     let name = match names::player_name(id)? {
         Some(name) => name,
         None => return Ok(None),
     };
 
     let summary = summary::player_summary(id)?;
+    let daily = summary::player_daily(id)?;
+    let standard = |s: &&Summary| !s.is_postseason && (exhibition || !s.is_special);
 
     macro_rules! tabler {
         ($tabler:ident, $filter:expr) => {{
@@ -29,16 +122,37 @@ fn load_player(id: Uuid) -> Result<Option<PlayerPage>> {
             let mut stats_table = $tabler::table(std::iter::empty(), Stats::default());
             let mut totals = Stats::default();
             let mut league_totals = Stats::default();
+            let mut era_totals = Stats::default();
+            let mut era_league_totals = Stats::default();
+
+            // rows are already in sim order, so each era's rows are contiguous; only group rows
+            // for players who appear in more than one era
+            let rows = summary
+                .iter()
+                .filter($filter)
+                .map(|row| Ok((era(&row.season)?, row)))
+                .collect::<Result<Vec<_>>>()?;
+            let grouped = rows.windows(2).any(|w| w[0].0 != w[1].0);
+
+            for (i, (era, row)) in rows.iter().enumerate() {
+                if grouped && (i == 0 || &rows[i - 1].0 != era) {
+                    stats_table.group(era);
+                }
 
-            for row in summary.iter().filter($filter) {
                 let team = names::team_name(row.team_id)?.unwrap_or_default();
-                ident_table.push([format!("{:#}", row.season).into(), team.shorthand.into()]);
+                let season = if row.is_special {
+                    format!("{:#} (exhibition)", row.season)
+                } else {
+                    format!("{:#}", row.season)
+                };
+                ident_table.push([season.into(), team.shorthand.into()]);
                 ident_table.set_href(
                     1,
                     uri!(team(
                         id = row.team_id,
                         sim = &row.season.sim,
-                        season = row.season.season
+                        season = row.season.season,
+                        exhibition = _
                     )),
                 );
 
@@ -46,6 +160,15 @@ fn load_player(id: Uuid) -> Result<Option<PlayerPage>> {
                 stats_table.push($tabler::build_row(row.stats, league));
                 totals += row.stats;
                 league_totals += league;
+                era_totals += row.stats;
+                era_league_totals += league;
+
+                if grouped && rows.get(i + 1).map_or(true, |(next, _)| next != era) {
+                    ident_table.push_subtotal([format!("{} total", era).into(), "".into()]);
+                    stats_table.push_subtotal($tabler::build_row(era_totals, era_league_totals));
+                    era_totals = Stats::default();
+                    era_league_totals = Stats::default();
+                }
             }
 
             TotalsTable {
@@ -56,26 +179,391 @@ fn load_player(id: Uuid) -> Result<Option<PlayerPage>> {
     }
 
     let mut page = PlayerPage {
+        aliases: names::name_history(id)?
+            .into_iter()
+            .filter(|alias| alias.name != name)
+            .collect(),
+        inhabiting: load_inhabiting(id)?,
         name,
         id,
-        standard_batting: tabler!(batting, |s| !s.is_postseason && s.stats.is_batting()),
+        has_exhibition: summary.iter().any(|s| s.is_special),
+        exhibition,
+        standard_batting: tabler!(batting, |s| standard(s) && s.stats.is_batting()),
         postseason_batting: tabler!(batting, |s| s.is_postseason && s.stats.is_batting()),
-        standard_pitching: tabler!(pitching, |s| !s.is_postseason && s.stats.is_pitching()),
+        standard_pitching: tabler!(pitching, |s| standard(s) && s.stats.is_pitching()),
         postseason_pitching: tabler!(pitching, |s| s.is_postseason && s.stats.is_pitching()),
+        fielding: tabler!(fielding, |s| standard(s) && s.stats.fielding.is_fielding()),
+        daily_batting: heatmap::strips(&daily, Side::Batting)?,
+        daily_pitching: heatmap::strips(&daily, Side::Pitching)?,
+        stars: stars_table(stars),
+        pitching_splits: splits::table(
+            summary
+                .iter()
+                .filter(|s| standard(s))
+                .map(|s| s.stats.lineup_slot_splits)
+                .sum::<Splits>(),
+        ),
+        opponents: opponents::table(
+            id,
+            summary
+                .iter()
+                .filter(|s| !s.is_postseason && !s.is_special && s.stats.is_pitching()),
+        )?,
     };
     page.postseason_batting.table.skip("OPS+");
+    page.postseason_batting.table.skip("wRC+");
     page.postseason_pitching.table.skip("ERA+");
     page.postseason_pitching.table.skip("FIP");
     Ok(Some(page))
 }
 
+fn stars_table(rows: Vec<(Season, Stars)>) -> Table<5> {
+    let mut table = Table::new(
+        [
+            ("Season", ""),
+            ("Batting", ""),
+            ("Pitching", ""),
+            ("Baserunning", ""),
+            ("Defense", ""),
+        ],
+        "text-right",
+        "number",
+    );
+    for (season, stars) in rows {
+        table.push(row![
+            format!("{:#}", season),
+            stars::to_pct(stars.batting),
+            stars::to_pct(stars.pitching),
+            stars::to_pct(stars.baserunning),
+            stars::to_pct(stars.defense),
+        ]);
+    }
+    table
+}
+
+fn load_inhabiting(id: Uuid) -> Result<Vec<InhabitingLink>> {
+    inhabiting::load(id)?
+        .into_iter()
+        .map(|entry| {
+            let (verb, other) = if entry.inhabiting.ghost == id {
+                ("Inhabited", entry.inhabiting.host)
+            } else {
+                ("Inhabited by", entry.inhabiting.ghost)
+            };
+            Ok(InhabitingLink {
+                verb,
+                other_uri: uri!(player(id = other, exhibition = _)).to_string(),
+                other_name: names::player_name(other)?.unwrap_or_default(),
+                game_uri: uri!(game(id = entry.game_id)).to_string(),
+                game: format!("{:#}, Day {}", entry.season, entry.day + 1),
+            })
+        })
+        .collect()
+}
+
+struct InhabitingLink {
+    verb: &'static str,
+    other_uri: String,
+    other_name: String,
+    game_uri: String,
+    game: String,
+}
+
+fn load_player_vs_pitcher(
+    id: Uuid,
+    pitcher: Uuid,
+    season: Season,
+) -> Result<Option<PlayerVsPitcherPage>> {
+    let (name, pitcher_name) = match (names::player_name(id)?, names::player_name(pitcher)?) {
+        (Some(name), Some(pitcher_name)) => (name, pitcher_name),
+        _ => return Ok(None),
+    };
+
+    let mut games = matchup::games(&season, id, pitcher)?
+        .into_iter()
+        .map(|(game_id, stats)| {
+            let day = game::load(game_id)?
+                .map(|game| game.day)
+                .unwrap_or_default();
+            Ok((day, game_id, stats))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    games.sort_by_key(|(day, _, _)| *day);
+
+    let mut table = Table::new(
+        [
+            ("Day", ""),
+            ("Plate Appearances", "PA"),
+            ("Hits", "H"),
+            ("Home Runs", "HR"),
+            ("Bases on Balls (Walks)", "BB"),
+            ("Strikeouts", "SO"),
+        ],
+        "text-right",
+        "number",
+    );
+    let mut totals = MatchupStats::default();
+    for (day, game_id, stats) in games {
+        table.push(row![
+            u32::from(day + 1),
+            stats.plate_appearances,
+            stats.hits,
+            stats.home_runs,
+            stats.walks,
+            stats.strike_outs,
+        ]);
+        table.set_href(0, uri!(game(id = game_id)));
+        totals += stats;
+    }
+
+    Ok(Some(PlayerVsPitcherPage {
+        name,
+        id,
+        pitcher_name,
+        pitcher,
+        season,
+        table: TotalsTable {
+            table,
+            totals: row![
+                totals.plate_appearances,
+                totals.hits,
+                totals.home_runs,
+                totals.walks,
+                totals.strike_outs,
+            ],
+        },
+    }))
+}
+
+#[derive(Template)]
+#[template(path = "player_vs_pitcher.html")]
+struct PlayerVsPitcherPage {
+    name: String,
+    id: Uuid,
+    pitcher_name: String,
+    pitcher: Uuid,
+    season: Season,
+    table: TotalsTable<6, 5>,
+}
+
+const MAX_COMPARED: usize = 4;
+
+/// Parses a comma-separated list of player IDs, ignoring repeats. Returns `None` if an ID is
+/// invalid or there are too many players.
+fn parse_players(players: &str) -> Option<Vec<Uuid>> {
+    let mut ids = Vec::new();
+    for id in players
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+    {
+        let id = Uuid::parse_str(id).ok()?;
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    if ids.len() <= MAX_COMPARED {
+        Some(ids)
+    } else {
+        None
+    }
+}
+
+fn join_ids<'a>(ids: impl Iterator<Item = &'a Uuid>) -> String {
+    ids.map(Uuid::to_string).collect::<Vec<_>>().join(",")
+}
+
+fn load_compare(ids: &[Uuid], not_found: Option<String>) -> Result<Option<ComparePage>> {
+    let mut players = Vec::new();
+    for id in ids {
+        let name = match names::player_name(*id)? {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        players.push(ComparedPlayer {
+            id: *id,
+            name,
+            without: join_ids(ids.iter().filter(|other| *other != id)),
+            summary: summary::player_summary(*id)?,
+        });
+    }
+    let standard = |s: &Summary| !s.is_postseason && !s.is_special;
+
+    // career totals side by side, then every season's rows, with players in the order given for
+    // seasons they played in together
+    macro_rules! tabler {
+        ($tabler:ident, $filter:expr) => {{
+            let mut career_ident = Table::new([("Player", "")], "text-left", "none");
+            let mut career = $tabler::table(std::iter::empty(), Stats::default());
+            let mut rows = Vec::new();
+            for player in &players {
+                let mut totals = Stats::default();
+                let mut league_totals = Stats::default();
+                for row in player.summary.iter().filter(|s| $filter(s)) {
+                    let league = summary::league_totals(&row.season)?;
+                    totals += row.stats;
+                    league_totals += league;
+                    rows.push((player, row, league));
+                }
+                if totals != Stats::default() {
+                    career_ident.push([player.name.clone().into()]);
+                    career_ident.set_href(0, uri!(player(id = player.id, exhibition = _)));
+                    career.push($tabler::build_row(totals, league_totals));
+                }
+            }
+            rows.sort_by(|a, b| a.1.season.cmp(&b.1.season));
+
+            let mut seasons_ident = Table::new(
+                [("Season", ""), ("Player", ""), ("Team", "")],
+                "text-left",
+                "none",
+            );
+            let mut seasons = $tabler::table(std::iter::empty(), Stats::default());
+            for (player, row, league) in rows {
+                let team = names::team_name(row.team_id)?.unwrap_or_default();
+                seasons_ident.push([
+                    format!("{:#}", row.season).into(),
+                    player.name.clone().into(),
+                    team.shorthand.into(),
+                ]);
+                seasons_ident.set_href(1, uri!(player(id = player.id, exhibition = _)));
+                seasons_ident.set_href(
+                    2,
+                    uri!(team(
+                        id = row.team_id,
+                        sim = &row.season.sim,
+                        season = row.season.season,
+                        exhibition = _
+                    )),
+                );
+                seasons.push($tabler::build_row(row.stats, league));
+            }
+            (
+                career.insert(0, career_ident),
+                seasons.insert(0, seasons_ident),
+            )
+        }};
+    }
+
+    let (career_batting, season_batting) =
+        tabler!(batting, |s: &Summary| standard(s) && s.stats.is_batting());
+    let (career_pitching, season_pitching) =
+        tabler!(pitching, |s: &Summary| standard(s) && s.stats.is_pitching());
+    Ok(Some(ComparePage {
+        ids: join_ids(ids.iter()),
+        full: ids.len() >= MAX_COMPARED,
+        not_found,
+        career_batting,
+        season_batting,
+        career_pitching,
+        season_pitching,
+        players,
+    }))
+}
+
+struct ComparedPlayer {
+    id: Uuid,
+    name: String,
+    /// The other players' IDs, for removing this player from the comparison
+    without: String,
+    summary: Vec<Summary>,
+}
+
+#[derive(Template)]
+#[template(path = "compare.html")]
+struct ComparePage {
+    players: Vec<ComparedPlayer>,
+    ids: String,
+    full: bool,
+    not_found: Option<String>,
+    career_batting: Table<{ batting::COLS + 1 }>,
+    season_batting: Table<{ batting::COLS + 3 }>,
+    career_pitching: Table<{ pitching::COLS + 1 }>,
+    season_pitching: Table<{ pitching::COLS + 3 }>,
+}
+
+/// The era name a season is displayed under, or its sim ID if it has none.
+fn era(season: &Season) -> Result<String> {
+    Ok(season.era_name()?.unwrap_or_else(|| season.sim.clone()))
+}
+
 #[derive(Template)]
 #[template(path = "player.html")]
 struct PlayerPage {
     name: String,
     id: Uuid,
+    aliases: Vec<names::Alias>,
+    inhabiting: Vec<InhabitingLink>,
+    has_exhibition: bool,
+    exhibition: bool,
     standard_batting: TotalsTable<{ batting::COLS + 2 }, { batting::COLS }>,
     postseason_batting: TotalsTable<{ batting::COLS + 2 }, { batting::COLS }>,
     standard_pitching: TotalsTable<{ pitching::COLS + 2 }, { pitching::COLS }>,
     postseason_pitching: TotalsTable<{ pitching::COLS + 2 }, { pitching::COLS }>,
+    fielding: TotalsTable<{ fielding::COLS + 2 }, { fielding::COLS }>,
+    daily_batting: Vec<Strip>,
+    daily_pitching: Vec<Strip>,
+    stars: Table<5>,
+    pitching_splits: Table<7>,
+    opponents: Table<{ opponents::COLS }>,
+}
+
+fn load_player_splits(id: Uuid, segments: u16) -> Result<Option<PlayerSplitsPage>> {
+    let name = match names::player_name(id)? {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+
+    let splits = splits::day_splits(id, segments)?;
+
+    macro_rules! tabler {
+        ($tabler:ident, $filter:expr) => {{
+            let mut ident_table = Table::new([("Season", ""), ("Days", "")], "text-left", "none");
+            let mut stats_table = $tabler::table(std::iter::empty(), Stats::default());
+            for split in splits.iter().filter($filter) {
+                ident_table.push([
+                    format!("{:#}", split.season).into(),
+                    split.days_label().into(),
+                ]);
+                stats_table.push($tabler::build_row(
+                    split.stats,
+                    summary::league_totals(&split.season)?,
+                ));
+            }
+            stats_table.insert(0, ident_table)
+        }};
+    }
+
+    let mut baserunning_ident = Table::new([("Season", ""), ("Days", "")], "text-left", "none");
+    let mut baserunning_stats = Vec::new();
+    for split in &splits {
+        if split.stats.stolen_bases + split.stats.caught_stealing > 0 {
+            baserunning_ident.push([
+                format!("{:#}", split.season).into(),
+                split.days_label().into(),
+            ]);
+            baserunning_stats.push(split.stats);
+        }
+    }
+
+    Ok(Some(PlayerSplitsPage {
+        name,
+        id,
+        segments,
+        batting: tabler!(batting, |s| s.stats.is_batting()),
+        pitching: tabler!(pitching, |s| s.stats.is_pitching()),
+        baserunning: batting::baserunning_table(baserunning_stats.into_iter())
+            .insert(0, baserunning_ident),
+    }))
+}
+
+#[derive(Template)]
+#[template(path = "player_splits.html")]
+struct PlayerSplitsPage {
+    name: String,
+    id: Uuid,
+    segments: u16,
+    batting: Table<{ batting::COLS + 2 }>,
+    pitching: Table<{ pitching::COLS + 2 }>,
+    baserunning: Table<{ batting::BASERUNNING_COLS + 2 }>,
 }
@@ -1,65 +1,339 @@
+use crate::bracket::{self, Matchup};
+use crate::milestones::{self, Milestone};
+use crate::names::TeamName;
+use crate::routes::game::rocket_uri_macro_game;
 use crate::routes::player::rocket_uri_macro_player;
 use crate::routes::team::rocket_uri_macro_team;
-use crate::{batting, pitching, routes::ResponseResult, seasons::Season, summary, table::Table};
+use crate::table::{row, Table};
+use crate::{
+    batting, names, pitching, routes::ResponseResult, routes::SeasonParam, schedule,
+    seasons::Season, summary, summary::Qualifier,
+};
 use anyhow::Result;
 use askama::Template;
 use rocket::response::content::Html;
 use rocket::{get, uri};
+use std::collections::HashMap;
 use uuid::Uuid;
 
-#[get("/batting/<sim>/<season>")]
-pub fn season_player_batting(sim: String, season: u16) -> ResponseResult<Option<Html<String>>> {
-    Ok(match load_player_batting(Season { sim, season })? {
-        Some(season) => Some(Html(season.render().map_err(anyhow::Error::from)?)),
+/// Lists every game played on a day of a season. `day` is as displayed, starting from 1.
+#[get("/day/<sim>/<season>/<day>")]
+pub fn day(
+    sim: String,
+    season: u16,
+    day: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    Ok(match load_day(Season { sim, season }, day)? {
+        Some(page) => Some(Html(page.render().map_err(anyhow::Error::from)?)),
         None => None,
     })
 }
 
-#[get("/pitching/<sim>/<season>")]
-pub fn season_player_pitching(sim: String, season: u16) -> ResponseResult<Option<Html<String>>> {
-    Ok(match load_player_pitching(Season { sim, season })? {
-        Some(season) => Some(Html(season.render().map_err(anyhow::Error::from)?)),
-        None => None,
-    })
+/// Rows per page of season tables. Most seasons fit on one page; sims with thousands of players
+/// are split up.
+const PAGE_SIZE: usize = 500;
+
+/// Server-side sorting and pagination of a season table, from `?sort=hr&order=desc&page=2`.
+/// `sort` names a column by its abbreviation (or its header, for columns without one), ignoring
+/// case. `order` is `asc` or `desc`, defaulting to descending for stats and ascending for names.
+struct View {
+    base: String,
+    qualified: bool,
+    exhibition: bool,
+    sort: Option<String>,
+    order: Option<String>,
+    page: usize,
 }
 
-#[get("/batting/team/<sim>/<season>")]
-pub fn season_team_batting(sim: String, season: u16) -> ResponseResult<Option<Html<String>>> {
-    Ok(match load_team_batting(Season { sim, season })? {
+impl View {
+    fn new(
+        season: &Season,
+        is_batting: bool,
+        is_players: bool,
+        qualified: bool,
+        sort: Option<String>,
+        order: Option<String>,
+        page: Option<usize>,
+    ) -> View {
+        View {
+            base: season.uri(&is_batting, &is_players),
+            qualified,
+            exhibition: false,
+            sort,
+            order,
+            page: page.unwrap_or(1).max(1),
+        }
+    }
+
+    /// Keeps `?exhibition=true` in sorting and pagination links.
+    fn exhibition(mut self, exhibition: bool) -> View {
+        self.exhibition = exhibition;
+        self
+    }
+
+    fn uri(&self, sort: Option<(&str, &str)>, page: usize) -> String {
+        let mut query = Vec::new();
+        if self.qualified {
+            query.push(("qualified", "true".to_string()));
+        }
+        if self.exhibition {
+            query.push(("exhibition", "true".to_string()));
+        }
+        if let Some((sort, order)) = sort {
+            query.push(("sort", sort.to_lowercase()));
+            query.push(("order", order.to_string()));
+        }
+        if page > 1 {
+            query.push(("page", page.to_string()));
+        }
+        match serde_urlencoded::to_string(query) {
+            Ok(query) if !query.is_empty() => format!("{}?{}", self.base, query),
+            _ => self.base.clone(),
+        }
+    }
+
+    /// Sorts and paginates the table, and links its column headers to sort by each column. Static
+    /// renders keep the whole table on one page and leave sorting to the browser.
+    fn apply<const N: usize>(self, table: &mut Table<N>) -> Pages {
+        if crate::render::is_rendering() {
+            return Pages {
+                page: 1,
+                pages: 1,
+                prev: None,
+                next: None,
+            };
+        }
+
+        let sorted = self
+            .sort
+            .as_deref()
+            .and_then(|sort| table.column_ignore_case(sort))
+            .map(|index| match self.order.as_deref() {
+                Some("asc") => (index, false),
+                Some("desc") => (index, true),
+                _ => (index, default_descending(table, index)),
+            });
+        if let Some((index, descending)) = sorted {
+            table.sort_by(index, descending);
+        }
+
+        table.header_href = (0..N)
+            .map(|index| {
+                let descending = match sorted {
+                    Some((sorted, descending)) if sorted == index => !descending,
+                    _ => default_descending(table, index),
+                };
+                self.uri(Some((column_name(table, index), order(descending))), 1)
+            })
+            .collect();
+
+        let pages = table.paginate(self.page, PAGE_SIZE);
+        let sort = sorted.map(|(index, descending)| (column_name(table, index), order(descending)));
+        Pages {
+            page: self.page,
+            pages,
+            prev: (self.page > 1).then(|| self.uri(sort, (self.page - 1).min(pages))),
+            next: (self.page < pages).then(|| self.uri(sort, self.page + 1)),
+        }
+    }
+}
+
+/// Names and text columns sort ascending by default; stats sort descending.
+fn default_descending<const N: usize>(table: &Table<N>, index: usize) -> bool {
+    table.col_class[index] != "text-left"
+}
+
+fn column_name<const N: usize>(table: &Table<N>, index: usize) -> &str {
+    if table.abbr[index].is_empty() {
+        &table.header[index]
+    } else {
+        &table.abbr[index]
+    }
+}
+
+fn order(descending: bool) -> &'static str {
+    if descending {
+        "desc"
+    } else {
+        "asc"
+    }
+}
+
+struct Pages {
+    page: usize,
+    pages: usize,
+    prev: Option<String>,
+    next: Option<String>,
+}
+
+/// With `?qualified=true`, only lists players with enough plate appearances for their team's
+/// games played (see `summary::Qualifier`). See `View` for sorting and pagination.
+#[get("/batting/<sim>/<season>?<qualified>&<exhibition>&<sort>&<order>&<page>")]
+#[allow(clippy::too_many_arguments)]
+pub fn season_player_batting(
+    sim: String,
+    season: u16,
+    qualified: Option<bool>,
+    exhibition: Option<bool>,
+    sort: Option<String>,
+    order: Option<String>,
+    page: Option<usize>,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    let season = Season { sim, season };
+    let exhibition = is_exhibition(&season, exhibition)?;
+    // qualifying is relative to regular season games played
+    let qualified = qualified.unwrap_or(false) && !exhibition;
+    let view = View::new(&season, true, true, qualified, sort, order, page).exhibition(exhibition);
+    Ok(
+        match load_player_batting(season, qualified, exhibition, view)? {
+            Some(season) => Some(Html(season.render().map_err(anyhow::Error::from)?)),
+            None => None,
+        },
+    )
+}
+
+/// With `?qualified=true`, only lists pitchers with enough innings pitched for their team's games
+/// played (see `summary::Qualifier`). See `View` for sorting and pagination.
+#[get("/pitching/<sim>/<season>?<qualified>&<exhibition>&<sort>&<order>&<page>")]
+#[allow(clippy::too_many_arguments)]
+pub fn season_player_pitching(
+    sim: String,
+    season: u16,
+    qualified: Option<bool>,
+    exhibition: Option<bool>,
+    sort: Option<String>,
+    order: Option<String>,
+    page: Option<usize>,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    let season = Season { sim, season };
+    let exhibition = is_exhibition(&season, exhibition)?;
+    // qualifying is relative to regular season games played
+    let qualified = qualified.unwrap_or(false) && !exhibition;
+    let view = View::new(&season, false, true, qualified, sort, order, page).exhibition(exhibition);
+    Ok(
+        match load_player_pitching(season, qualified, exhibition, view)? {
+            Some(season) => Some(Html(season.render().map_err(anyhow::Error::from)?)),
+            None => None,
+        },
+    )
+}
+
+#[get("/batting/team/<sim>/<season>?<exhibition>&<sort>&<order>&<page>")]
+pub fn season_team_batting(
+    sim: String,
+    season: u16,
+    exhibition: Option<bool>,
+    sort: Option<String>,
+    order: Option<String>,
+    page: Option<usize>,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    let season = Season { sim, season };
+    let exhibition = is_exhibition(&season, exhibition)?;
+    let view = View::new(&season, true, false, false, sort, order, page).exhibition(exhibition);
+    Ok(match load_team_batting(season, exhibition, view)? {
         Some(season) => Some(Html(season.render().map_err(anyhow::Error::from)?)),
         None => None,
     })
 }
 
-#[get("/pitching/team/<sim>/<season>")]
-pub fn season_team_pitching(sim: String, season: u16) -> ResponseResult<Option<Html<String>>> {
-    Ok(match load_team_pitching(Season { sim, season })? {
+#[get("/pitching/team/<sim>/<season>?<exhibition>&<sort>&<order>&<page>")]
+pub fn season_team_pitching(
+    sim: String,
+    season: u16,
+    exhibition: Option<bool>,
+    sort: Option<String>,
+    order: Option<String>,
+    page: Option<usize>,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    let season = Season { sim, season };
+    let exhibition = is_exhibition(&season, exhibition)?;
+    let view = View::new(&season, false, false, false, sort, order, page).exhibition(exhibition);
+    Ok(match load_team_pitching(season, exhibition, view)? {
         Some(season) => Some(Html(season.render().map_err(anyhow::Error::from)?)),
         None => None,
     })
 }
 
+/// Season tables show special games with `?exhibition=true`. Tournaments, which have no regular
+/// season games, always show them.
+fn is_exhibition(season: &Season, exhibition: Option<bool>) -> Result<bool> {
+    Ok(exhibition.unwrap_or(false) || !summary::has_regular_season(season)?)
+}
+
 macro_rules! load {
-    ($season:expr, $summary_func:ident, $is_batting:expr, $tabler:expr, $filter:expr) => {{
+    (
+        $season:expr,
+        $summary_func:ident,
+        $is_batting:expr,
+        $qualified:expr,
+        $exhibition:expr,
+        $view:expr,
+        $tabler:ident,
+        $filter:expr
+    ) => {{
         let seasons = Season::recorded()?;
         if !seasons.iter().any(|s| s == &$season) {
             return Ok(None);
         }
 
-        let summary = summary::$summary_func(&$season)?;
+        let summary = if $exhibition {
+            load!(@exhibition $summary_func, &$season)?
+        } else {
+            summary::$summary_func(&$season)?
+        };
         let league = summary::league_totals(&$season)?;
-        let stats_table = $tabler(summary.iter().filter($filter).map(|row| row.stats), league);
+        let mut stats_table =
+            $tabler::table(summary.iter().filter($filter).map(|row| row.stats), league);
+        for (column, higher_is_better) in $tabler::HEAT {
+            stats_table.heat(column, *higher_is_better);
+        }
+        if $exhibition {
+            // the league baseline is regular season play, which tournaments don't have
+            for column in ["OPS+", "wRC+", "ERA+", "FIP"] {
+                stats_table.skip(column);
+            }
+        }
 
+        let mut table = load!(@inner $summary_func, summary, stats_table, $season, $filter);
+        let pages = $view.apply(&mut table);
         Ok(Some(SeasonPage {
-            table: load!(@inner $summary_func, summary, stats_table, $season, $filter),
+            table,
+            pages,
             is_players: stringify!($summary_func) == "season_player_summary",
             is_batting: $is_batting,
+            qualified: $qualified,
+            exhibition: $exhibition,
+            has_exhibition: $exhibition
+                || !summary::season_exhibition_team_summary(&$season)?.is_empty(),
+            has_regular_season: summary::has_regular_season(&$season)?,
             what: if $is_batting { "Batting" } else { "Pitching" },
+            tournaments: seasons
+                .iter()
+                .filter(|s| !summary::has_regular_season(s).unwrap_or(true))
+                .cloned()
+                .collect(),
+            seasons: seasons
+                .iter()
+                .filter(|s| summary::has_regular_season(s).unwrap_or(true))
+                .cloned()
+                .collect(),
             season: $season,
-            seasons,
         }))
     }};
 
+    (@exhibition season_player_summary, $season:expr) => {
+        summary::season_exhibition_player_summary($season)
+    };
+
+    (@exhibition season_team_summary, $season:expr) => {
+        summary::season_exhibition_team_summary($season)
+    };
+
     (@inner season_player_summary, $summary:expr, $table:expr, $season:expr, $filter:expr) => {{
         let mut ident_table = Table::new(
             [("Player", ""), ("Current Team", "Team")],
@@ -68,13 +342,14 @@ macro_rules! load {
         );
         for row in $summary.iter().filter($filter) {
             ident_table.push([row.name.clone().into(), row.team_abbr.clone().into()]);
-            ident_table.set_href(0, uri!(player(id = row.id)));
+            ident_table.set_href(0, uri!(player(id = row.id, exhibition = _)));
             ident_table.set_href(
                 1,
                 uri!(team(
                     id = row.team_id,
                     sim = &$season.sim,
-                    season = $season.season
+                    season = $season.season,
+                    exhibition = _
                 )),
             );
         }
@@ -90,7 +365,8 @@ macro_rules! load {
                 uri!(team(
                     id = row.id,
                     sim = &$season.sim,
-                    season = $season.season
+                    season = $season.season,
+                    exhibition = _
                 )),
             );
         }
@@ -98,28 +374,126 @@ macro_rules! load {
     }};
 }
 
-fn load_player_batting(season: Season) -> Result<Option<SeasonPage<{ batting::COLS + 2 }>>> {
-    load!(season, season_player_summary, true, batting::table, |s| s
-        .stats
-        .is_batting())
+fn load_player_batting(
+    season: Season,
+    qualified: bool,
+    exhibition: bool,
+    view: View,
+) -> Result<Option<SeasonPage<{ batting::COLS + 2 }>>> {
+    let qualifier = Qualifier::new(&season, qualified)?;
+    load!(
+        season,
+        season_player_summary,
+        true,
+        qualified,
+        exhibition,
+        view,
+        batting,
+        |s| s.stats.is_batting() && qualifier.batting(s)
+    )
 }
 
-fn load_player_pitching(season: Season) -> Result<Option<SeasonPage<{ pitching::COLS + 2 }>>> {
-    load!(season, season_player_summary, false, pitching::table, |s| s
-        .stats
-        .is_pitching())
+fn load_player_pitching(
+    season: Season,
+    qualified: bool,
+    exhibition: bool,
+    view: View,
+) -> Result<Option<SeasonPage<{ pitching::COLS + 2 }>>> {
+    let qualifier = Qualifier::new(&season, qualified)?;
+    load!(
+        season,
+        season_player_summary,
+        false,
+        qualified,
+        exhibition,
+        view,
+        pitching,
+        |s| s.stats.is_pitching() && qualifier.pitching(s)
+    )
 }
 
-fn load_team_batting(season: Season) -> Result<Option<SeasonPage<{ batting::COLS + 1 }>>> {
-    load!(season, season_team_summary, true, batting::table, |s| s
-        .stats
-        .is_batting())
+fn load_team_batting(
+    season: Season,
+    exhibition: bool,
+    view: View,
+) -> Result<Option<SeasonPage<{ batting::COLS + 1 }>>> {
+    load!(
+        season,
+        season_team_summary,
+        true,
+        false,
+        exhibition,
+        view,
+        batting,
+        |s| s.stats.is_batting()
+    )
 }
 
-fn load_team_pitching(season: Season) -> Result<Option<SeasonPage<{ pitching::COLS + 1 }>>> {
-    load!(season, season_team_summary, false, pitching::table, |s| s
-        .stats
-        .is_pitching())
+fn load_team_pitching(
+    season: Season,
+    exhibition: bool,
+    view: View,
+) -> Result<Option<SeasonPage<{ pitching::COLS + 1 }>>> {
+    load!(
+        season,
+        season_team_summary,
+        false,
+        false,
+        exhibition,
+        view,
+        pitching,
+        |s| s.stats.is_pitching()
+    )
+}
+
+fn load_day(season: Season, day: u16) -> Result<Option<DayPage>> {
+    let games = match day.checked_sub(1) {
+        Some(day) => schedule::day(&season, day)?,
+        None => return Ok(None),
+    };
+    if games.is_empty() {
+        return Ok(None);
+    }
+
+    let mut table = Table::new(
+        [
+            ("Away", ""),
+            ("Runs", "R"),
+            ("Home", ""),
+            ("Runs", "R"),
+            ("", ""),
+        ],
+        "text-left",
+        "none",
+    );
+    table.col_class[1] = "text-right";
+    table.col_class[3] = "text-right";
+    for (home, entry) in games {
+        table.push(row![
+            entry.opponent.shorthand,
+            u32::from(entry.opponent_score),
+            names::team_name(home)?.unwrap_or_default().shorthand,
+            u32::from(entry.score),
+            "Box score",
+        ]);
+        table.set_href(4, uri!(game(id = entry.id)));
+    }
+
+    Ok(Some(DayPage {
+        has_next: schedule::has_day(&season, day)?,
+        season,
+        day,
+        table,
+    }))
+}
+
+#[derive(Template)]
+#[template(path = "day.html")]
+struct DayPage {
+    season: Season,
+    day: u16,
+    has_next: bool,
+    table: Table<5>,
 }
 
 #[derive(Template)]
@@ -129,6 +503,311 @@ struct SeasonPage<const N: usize> {
     seasons: Vec<Season>,
     is_players: bool,
     is_batting: bool,
+    qualified: bool,
+    exhibition: bool,
+    has_exhibition: bool,
+    has_regular_season: bool,
+    tournaments: Vec<Season>,
     what: &'static str,
     table: Table<N>,
+    pages: Pages,
+}
+
+#[get("/milestones/<sim>/<season>")]
+pub fn season_milestones(
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    let season = Season { sim, season };
+    if !Season::recorded()?.contains(&season) {
+        return Ok(None);
+    }
+    Ok(Some(Html(
+        load_milestones(season)?
+            .render()
+            .map_err(anyhow::Error::from)?,
+    )))
+}
+
+fn load_milestones(season: Season) -> Result<MilestonesPage> {
+    let mut streaks = Table::new(
+        [
+            ("Player", ""),
+            ("Games", "G"),
+            ("First Day", ""),
+            ("Last Day", ""),
+        ],
+        "text-right",
+        "number",
+    );
+    streaks.col_class[0] = "text-left";
+    for streak in milestones::hitting_streaks(&season)? {
+        streaks.push(row![
+            names::player_name(streak.player)?.unwrap_or_default(),
+            streak.games,
+            u32::from(streak.first_day + 1),
+            u32::from(streak.last_day + 1),
+        ]);
+        streaks.set_href(0, uri!(player(id = streak.player, exhibition = _)));
+    }
+
+    let mut games = Table::new(
+        [("Day", ""), ("Milestone", ""), ("", "")],
+        "text-left",
+        "none",
+    );
+    games.col_class[0] = "text-right";
+    for entry in milestones::load(&season)? {
+        // shutouts are common enough to crowd out everything else; they're on the achievements
+        // page instead
+        for milestone in entry
+            .milestones
+            .iter()
+            .filter(|milestone| !matches!(milestone, Milestone::Shutout { .. }))
+        {
+            games.push(row![
+                u32::from(entry.day + 1),
+                describe(milestone)?,
+                "Box score",
+            ]);
+            games.set_href(2, uri!(game(id = entry.game_id)));
+        }
+    }
+
+    Ok(MilestonesPage {
+        season,
+        min_streak: milestones::MIN_STREAK,
+        streaks,
+        games,
+    })
+}
+
+fn describe(milestone: &Milestone) -> Result<String> {
+    let player = |id| -> Result<String> { Ok(names::player_name(id)?.unwrap_or_default()) };
+    Ok(match milestone {
+        Milestone::MultiHomeRun {
+            player: id,
+            home_runs,
+        } => {
+            format!("{} hit {} home runs", player(*id)?, home_runs)
+        }
+        Milestone::Cycle { player: id } => format!("{} hit for the cycle", player(*id)?),
+        Milestone::CycleAttempt {
+            player: id,
+            missing,
+        } => {
+            format!("{} was a {} short of the cycle", player(*id)?, missing)
+        }
+        Milestone::NoHitter { team, pitchers } => format!(
+            "{} no-hitter ({})",
+            names::team_name(*team)?.unwrap_or_default().name,
+            pitchers
+                .iter()
+                .map(|id| player(*id))
+                .collect::<Result<Vec<_>>>()?
+                .join(", ")
+        ),
+        Milestone::PerfectGame { pitcher: id, .. } => {
+            format!("{} threw a perfect game", player(*id)?)
+        }
+        Milestone::Shutout { pitcher: id, .. } => {
+            format!("{} threw a complete game shutout", player(*id)?)
+        }
+    })
+}
+
+#[derive(Template)]
+#[template(path = "milestones.html")]
+struct MilestonesPage {
+    season: Season,
+    min_streak: u32,
+    streaks: Table<4>,
+    games: Table<3>,
+}
+
+/// A season's perfect games, no-hitters, complete game shutouts, and cycles, from the milestones
+/// recorded for each game.
+#[get("/achievements/<sim>/<season>")]
+pub fn season_achievements(
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    let season = Season { sim, season };
+    if !Season::recorded()?.contains(&season) {
+        return Ok(None);
+    }
+    Ok(Some(Html(
+        load_achievements(season)?
+            .render()
+            .map_err(anyhow::Error::from)?,
+    )))
+}
+
+fn load_achievements(season: Season) -> Result<AchievementsPage> {
+    let table = |player: &'static str| {
+        let mut table = Table::new([("Day", ""), (player, ""), ("", "")], "text-left", "none");
+        table.col_class[0] = "text-right";
+        table
+    };
+    let mut perfect_games = table("Pitcher");
+    let mut no_hitters = table("Pitchers");
+    let mut shutouts = table("Pitcher");
+    let mut cycles = table("Batter");
+
+    for entry in milestones::load(&season)? {
+        for milestone in &entry.milestones {
+            let (table, players) = match milestone {
+                Milestone::PerfectGame { pitcher, .. } => (&mut perfect_games, vec![*pitcher]),
+                Milestone::NoHitter { pitchers, .. } => (&mut no_hitters, pitchers.clone()),
+                Milestone::Shutout { pitcher, .. } => (&mut shutouts, vec![*pitcher]),
+                Milestone::Cycle { player } => (&mut cycles, vec![*player]),
+                Milestone::MultiHomeRun { .. } | Milestone::CycleAttempt { .. } => continue,
+            };
+            let names = players
+                .iter()
+                .map(|id| Ok(names::player_name(*id)?.unwrap_or_default()))
+                .collect::<Result<Vec<_>>>()?;
+            table.push(row![
+                u32::from(entry.day + 1),
+                names.join(", "),
+                "Box score",
+            ]);
+            // combined no-hitters have no single player to link to
+            if let [player] = players.as_slice() {
+                table.set_href(1, uri!(player(id = *player, exhibition = _)));
+            }
+            table.set_href(2, uri!(game(id = entry.game_id)));
+        }
+    }
+
+    Ok(AchievementsPage {
+        season,
+        sections: vec![
+            ("Perfect Games", perfect_games),
+            ("No-Hitters", no_hitters),
+            ("Complete Game Shutouts", shutouts),
+            ("Cycles", cycles),
+        ],
+    })
+}
+
+#[derive(Template)]
+#[template(path = "achievements.html")]
+struct AchievementsPage {
+    season: Season,
+    sections: Vec<(&'static str, Table<3>)>,
+}
+
+/// Every game in a season's cached schedule, as a grid of days. Games that haven't been processed
+/// are listed too, since the cached schedule covers every day that was fully played.
+#[get("/season/<sim>/<season>/schedule")]
+pub fn season_schedule(
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    let season = Season { sim, season };
+    if !Season::recorded()?.contains(&season) {
+        return Ok(None);
+    }
+    Ok(Some(Html(
+        load_schedule(season)?
+            .render()
+            .map_err(anyhow::Error::from)?,
+    )))
+}
+
+fn load_schedule(season: Season) -> Result<SchedulePage> {
+    let mut team_names: HashMap<Uuid, TeamName> = HashMap::new();
+    let mut team_name = |id: Uuid| -> Result<TeamName> {
+        if let Some(name) = team_names.get(&id) {
+            return Ok(name.clone());
+        }
+        let name = names::team_name(id)?.unwrap_or_default();
+        team_names.insert(id, name.clone());
+        Ok(name)
+    };
+
+    let mut days = Vec::new();
+    for (day, games) in schedule::cached_days(&season)? {
+        let mut scheduled = Vec::new();
+        for game in games {
+            let score = |score: Option<f64>| score.map(|s| s.to_string()).unwrap_or_default();
+            scheduled.push(ScheduleGame {
+                href: uri!(game(id = game.id)).to_string(),
+                away: team_name(game.away)?,
+                home: team_name(game.home)?,
+                away_score: score(game.away_score),
+                home_score: score(game.home_score),
+                away_won: game.away_score > game.home_score,
+                home_won: game.home_score > game.away_score,
+                is_postseason: game.is_postseason,
+            });
+        }
+        days.push(ScheduleDay {
+            href: uri!(day(
+                sim = &season.sim,
+                season = season.season,
+                day = day + 1
+            ))
+            .to_string(),
+            day: day + 1,
+            games: scheduled,
+        });
+    }
+    Ok(SchedulePage { season, days })
+}
+
+#[derive(Template)]
+#[template(path = "schedule.html")]
+struct SchedulePage {
+    season: Season,
+    days: Vec<ScheduleDay>,
+}
+
+struct ScheduleDay {
+    href: String,
+    day: u16,
+    games: Vec<ScheduleGame>,
+}
+
+struct ScheduleGame {
+    href: String,
+    away: TeamName,
+    home: TeamName,
+    away_score: String,
+    home_score: String,
+    away_won: bool,
+    home_won: bool,
+    is_postseason: bool,
+}
+
+#[get("/postseason/<sim>/<season>")]
+pub fn postseason(
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    let season = Season { sim, season };
+    if !Season::recorded()?.contains(&season) {
+        return Ok(None);
+    }
+
+    Ok(Some(Html(
+        PostseasonPage {
+            rounds: bracket::bracket(&season)?,
+            season,
+        }
+        .render()
+        .map_err(anyhow::Error::from)?,
+    )))
+}
+
+#[derive(Template)]
+#[template(path = "postseason.html")]
+struct PostseasonPage {
+    season: Season,
+    rounds: Vec<Vec<Matchup>>,
 }
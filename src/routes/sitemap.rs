@@ -0,0 +1,162 @@
+use crate::routes::game::rocket_uri_macro_game;
+use crate::routes::player::rocket_uri_macro_player;
+use crate::routes::season::{
+    rocket_uri_macro_day, rocket_uri_macro_postseason, rocket_uri_macro_season_achievements,
+    rocket_uri_macro_season_milestones, rocket_uri_macro_season_schedule,
+};
+use crate::routes::{ResponseResult, SeasonParam};
+use crate::{names, schedule, seasons::Season, summary};
+use anyhow::Result;
+use askama::Template;
+use rocket::get;
+use rocket::http::ContentType;
+use rocket::uri;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// The maximum number of URLs in a single sitemap file, per the sitemaps protocol.
+const PAGE_SIZE: usize = 50_000;
+
+lazy_static::lazy_static! {
+    /// Sitemaps (and calendars) must list absolute URLs; `BRICKS_BASE_URL` overrides the public
+    /// site URL.
+    static ref BASE_URL: String = std::env::var("BRICKS_BASE_URL")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "https://bricks.sibr.dev".into())
+        .trim_end_matches('/')
+        .into();
+}
+
+#[get("/robots.txt")]
+pub fn robots() -> (ContentType, String) {
+    (
+        ContentType::Plain,
+        format!(
+            "User-agent: *\nDisallow: /admin/\nSitemap: {}/sitemap.xml\n",
+            *BASE_URL
+        ),
+    )
+}
+
+/// Sitemap index listing one sitemap per recorded season, plus as many pages of player sitemaps
+/// as needed.
+#[get("/sitemap.xml")]
+pub fn sitemap_index() -> ResponseResult<(ContentType, String)> {
+    let mut sitemaps = Vec::new();
+    let pages = (players()?.len() + PAGE_SIZE - 1) / PAGE_SIZE;
+    for i in 0..pages {
+        sitemaps.push(absolute(uri!(sitemap_players(page = i)).to_string()));
+    }
+    for season in Season::recorded()? {
+        sitemaps.push(absolute(
+            uri!(sitemap_season(sim = season.sim, season = season.season)).to_string(),
+        ));
+    }
+
+    Ok((
+        ContentType::XML,
+        SitemapIndex { sitemaps }
+            .render()
+            .map_err(anyhow::Error::from)?,
+    ))
+}
+
+/// Player pages, `PAGE_SIZE` at a time.
+#[get("/sitemap/players/<page>")]
+pub fn sitemap_players(page: usize) -> ResponseResult<Option<(ContentType, String)>> {
+    let urls = players()?
+        .into_iter()
+        .skip(page.saturating_mul(PAGE_SIZE))
+        .take(PAGE_SIZE)
+        .map(|player_id| absolute(uri!(player(id = player_id, exhibition = _)).to_string()))
+        .collect::<Vec<_>>();
+    if urls.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(render(urls)?))
+}
+
+/// Season leaderboards, team pages, day pages, and box scores for a season.
+#[get("/sitemap/season/<sim>/<season>")]
+pub fn sitemap_season(
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<(ContentType, String)>> {
+    let season = Season { sim, season };
+    if !Season::recorded()?.contains(&season) {
+        return Ok(None);
+    }
+
+    let urls = season_paths(&season)?.into_iter().map(absolute).collect();
+    Ok(Some(render(urls)?))
+}
+
+/// Paths to a season's leaderboards, team pages, day pages, and box scores.
+pub(crate) fn season_paths(season: &Season) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for (is_batting, is_players) in [(true, true), (false, true), (true, false), (false, false)] {
+        paths.push(season.uri(&is_batting, &is_players));
+    }
+    paths.push(uri!(season_milestones(sim = &season.sim, season = season.season)).to_string());
+    paths.push(
+        uri!(season_achievements(
+            sim = &season.sim,
+            season = season.season
+        ))
+        .to_string(),
+    );
+    paths.push(uri!(season_schedule(sim = &season.sim, season = season.season)).to_string());
+    if !summary::season_postseason_team_summary(season)?.is_empty() {
+        paths.push(uri!(postseason(sim = &season.sim, season = season.season)).to_string());
+    }
+    for team in summary::season_team_summary(season)? {
+        paths.push(season.team_uri(&&team.id));
+    }
+
+    let games = schedule::games(season)?;
+    let days = games
+        .iter()
+        .map(|(_, entry)| entry.day)
+        .collect::<BTreeSet<_>>();
+    for day in days {
+        paths.push(
+            uri!(day(
+                sim = &season.sim,
+                season = season.season,
+                day = day + 1
+            ))
+            .to_string(),
+        );
+    }
+    for (_, entry) in games {
+        paths.push(uri!(game(id = entry.id)).to_string());
+    }
+    Ok(paths)
+}
+
+/// Lists every player ID in the names tree, in key order.
+pub(crate) fn players() -> Result<Vec<Uuid>> {
+    names::player_ids()
+}
+
+pub(crate) fn absolute(path: String) -> String {
+    format!("{}{}", *BASE_URL, path)
+}
+
+fn render(urls: Vec<String>) -> Result<(ContentType, String)> {
+    Ok((ContentType::XML, Sitemap { urls }.render()?))
+}
+
+#[derive(Template)]
+#[template(path = "sitemap.xml")]
+struct Sitemap {
+    urls: Vec<String>,
+}
+
+#[derive(Template)]
+#[template(path = "sitemap_index.xml")]
+struct SitemapIndex {
+    sitemaps: Vec<String>,
+}
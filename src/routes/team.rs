@@ -1,23 +1,245 @@
+use crate::calendar::{Calendar, Event, Ics};
+use crate::csv::Csv;
+use crate::game::{self, Kind, Stats};
+use crate::lineup::{self, lineup_log};
 use crate::names::{self, TeamName};
+use crate::per_game::{self, Side};
+use crate::percentage::Pct;
+use crate::routes::game::rocket_uri_macro_game;
 use crate::routes::player::rocket_uri_macro_player;
+use crate::routes::sitemap;
 use crate::schedule::{self, Entry, Record};
-use crate::table::{Table, TotalsTable};
-use crate::{batting, pitching, routes::ResponseResult, seasons::Season, summary};
+use crate::table::{row, Table, TotalsTable};
+use crate::{
+    batting, favicon, fielding, leaders, pitching, routes::ResponseResult, routes::SeasonParam,
+    seasons::Season, summary, summary::Summary, weather,
+};
 use anyhow::Result;
 use askama::Template;
+use chrono::{DateTime, Utc};
+use rocket::http::ContentType;
 use rocket::response::content::Html;
 use rocket::{get, uri};
+use serde::Serialize;
 use uuid::Uuid;
 
-#[get("/team/<id>/<sim>/<season>")]
-pub fn team(id: Uuid, sim: String, season: u16) -> ResponseResult<Option<Html<String>>> {
-    Ok(match load_team(id, Season { sim, season })? {
-        Some(team) => Some(Html(team.render().map_err(anyhow::Error::from)?)),
+/// With `?exhibition=true`, stats from special games are listed alongside regular season stats.
+#[get("/team/<id>/<sim>/<season>?<exhibition>")]
+pub fn team(
+    id: Uuid,
+    sim: String,
+    season: u16,
+    exhibition: Option<bool>,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    Ok(
+        match load_team(id, Season { sim, season }, exhibition.unwrap_or(false))? {
+            Some(team) => Some(Html(team.render().map_err(anyhow::Error::from)?)),
+            None => None,
+        },
+    )
+}
+
+#[get("/team/<id>/history")]
+pub fn team_history(id: Uuid) -> ResponseResult<Option<Html<String>>> {
+    Ok(match load_team_history(id)? {
+        Some(page) => Some(Html(page.render().map_err(anyhow::Error::from)?)),
+        None => None,
+    })
+}
+
+#[get("/team/<id>/leaders")]
+pub fn team_leaders(id: Uuid) -> ResponseResult<Option<Html<String>>> {
+    Ok(match load_team_leaders(id)? {
+        Some(page) => Some(Html(page.render().map_err(anyhow::Error::from)?)),
         None => None,
     })
 }
 
-fn load_team(id: Uuid, season: Season) -> Result<Option<TeamPage>> {
+/// The team's emoji, as the favicon for its pages.
+#[get("/team/<id>/favicon.svg")]
+pub fn team_favicon(id: Uuid) -> ResponseResult<Option<(ContentType, String)>> {
+    Ok(names::team_name(id)?.map(|team| (ContentType::SVG, favicon::emoji(&team.emoji))))
+}
+
+#[get("/matchup/<team_a>/<team_b>/<sim>/<season>")]
+pub fn matchup(
+    team_a: Uuid,
+    team_b: Uuid,
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Html<String>>> {
+    Ok(
+        match load_matchup(team_a, team_b, Season { sim, season })? {
+            Some(page) => Some(Html(page.render().map_err(anyhow::Error::from)?)),
+            None => None,
+        },
+    )
+}
+
+#[get("/team/<id>/<sim>/<season>/lineups.csv")]
+pub fn team_lineups_csv(
+    id: Uuid,
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Csv<Vec<LineupRow>>>> {
+    Ok(load_lineups(id, Season { sim, season })?.map(Csv))
+}
+
+#[get("/team/<id>/<sim>/<season>/schedule.csv")]
+pub fn team_schedule_csv(
+    id: Uuid,
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Csv<Vec<ScheduleRow>>>> {
+    Ok(
+        load_schedule(id, &Season { sim, season })?.map(|(_, schedule)| {
+            Csv(schedule
+                .into_iter()
+                .map(|(_, entry)| ScheduleRow {
+                    day: entry.day + 1,
+                    game: entry.game_index + 1,
+                    game_id: entry.id,
+                    kind: entry.kind,
+                    home: entry.home,
+                    opponent: entry.opponent.name,
+                    opponent_id: entry.opponent_id,
+                    won: entry.won,
+                    score: entry.score,
+                    opponent_score: entry.opponent_score,
+                    start: entry.start,
+                    end: entry.end,
+                })
+                .collect())
+        }),
+    )
+}
+
+/// Every game the team played in the season, as calendar events. Games processed before feed
+/// timestamps were recorded are left out until the next rebuild.
+#[get("/team/<id>/<sim>/<season>/schedule.ics")]
+pub fn team_schedule_ics(
+    id: Uuid,
+    sim: String,
+    season: u16,
+    _valid: SeasonParam,
+) -> ResponseResult<Option<Ics>> {
+    let season = Season { sim, season };
+    let (name, schedule) = match load_schedule(id, &season)? {
+        Some(x) => x,
+        None => return Ok(None),
+    };
+
+    let mut events = Vec::new();
+    for (_, entry) in schedule {
+        let (start, end) = match (entry.start, entry.end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => continue,
+        };
+        let (away, home) = if entry.home {
+            (&entry.opponent.nickname, &name.nickname)
+        } else {
+            (&name.nickname, &entry.opponent.nickname)
+        };
+        events.push(Event {
+            uid: format!("{}@bricks.sibr.dev", entry.id),
+            start,
+            end,
+            summary: format!(
+                "{} @ {} ({} {}–{})",
+                away,
+                home,
+                if entry.won { "W" } else { "L" },
+                entry.score,
+                entry.opponent_score
+            ),
+            description: format!(
+                "{}, day {}{}",
+                season,
+                entry.day + 1,
+                match entry.kind {
+                    Kind::Regular => "",
+                    Kind::Postseason => " (postseason)",
+                    Kind::Special => " (special)",
+                }
+            ),
+            url: Some(sitemap::absolute(uri!(game(id = entry.id)).to_string())),
+        });
+    }
+
+    Ok(Some(Ics(Calendar {
+        name: format!("{}: {}", name.name, season),
+        events,
+    })))
+}
+
+#[derive(Serialize)]
+pub struct ScheduleRow {
+    day: u16,
+    /// The game's number on its day, which is 1 unless the team played more than once that day
+    game: u8,
+    game_id: Uuid,
+    kind: Kind,
+    home: bool,
+    opponent: String,
+    opponent_id: Uuid,
+    won: bool,
+    score: u16,
+    opponent_score: u16,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+}
+
+type Schedule = Vec<(Record, Entry)>;
+
+fn load_schedule(id: Uuid, season: &Season) -> Result<Option<(TeamName, Schedule)>> {
+    let name = match names::team_name(id)? {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    if !name.all_seasons()?.iter().any(|(s, _)| s == season) {
+        return Ok(None);
+    }
+    Ok(Some((name, schedule::schedule(id, season)?)))
+}
+
+#[derive(Serialize)]
+pub struct LineupRow {
+    day: u16,
+    game_id: Uuid,
+    changed: bool,
+    lineup: String,
+}
+
+fn load_lineups(id: Uuid, season: Season) -> Result<Option<Vec<LineupRow>>> {
+    let schedule = match load_schedule(id, &season)? {
+        Some((_, schedule)) => schedule,
+        None => return Ok(None),
+    };
+    lineup_log(&schedule)
+        .entries
+        .into_iter()
+        .map(|entry| {
+            Ok(LineupRow {
+                day: entry.day + 1,
+                game_id: entry.id,
+                changed: entry.changed,
+                lineup: entry
+                    .lineup
+                    .iter()
+                    .map(|id| Ok(names::player_name(*id)?.unwrap_or_default()))
+                    .collect::<Result<Vec<_>>>()?
+                    .join("; "),
+            })
+        })
+        .collect::<Result<_>>()
+        .map(Some)
+}
+
+fn load_team(id: Uuid, season: Season, exhibition: bool) -> Result<Option<TeamPage>> {
     let name = match names::team_name(id)? {
         Some(name) => name,
         None => return Ok(None),
@@ -42,21 +264,33 @@ fn load_team(id: Uuid, season: Season) -> Result<Option<TeamPage>> {
         .unwrap_or_default()
         .min(0);
 
+    let lineups = lineup_log(&schedule);
+    let (distinct_lineups, lineup_changes) = (lineups.distinct, lineups.changes);
+
     let summary = summary::team_summary(id, &season)?;
     let league = summary::league_totals(&season)?;
+    let standard = |s: &&Summary| !s.is_postseason && (exhibition || !s.is_special);
+    let mut standard_totals = summary::team_totals(&season, id, Kind::Regular)?;
+    let exhibition_totals = summary::team_totals(&season, id, Kind::Special)?;
+    if exhibition {
+        standard_totals += exhibition_totals;
+    }
+    let postseason_totals = summary::team_totals(&season, id, Kind::Postseason)?;
 
     macro_rules! tabler {
-        ($tabler:ident, $is_postseason:expr, $filter:expr) => {{
+        ($tabler:ident, $totals:expr, $filter:expr) => {{
             let mut ident_table = Table::new([("Player", "")], "text-left", "none");
             for row in summary.iter().filter($filter) {
-                let player = names::player_name(row.player_id)?.unwrap_or_default();
+                let mut player = names::player_name(row.player_id)?.unwrap_or_default();
+                if row.is_special {
+                    player.push_str(" (exhibition)");
+                }
                 ident_table.push([player.into()]);
-                ident_table.set_href(0, uri!(player(id = row.player_id)));
+                ident_table.set_href(0, uri!(player(id = row.player_id, exhibition = _)));
             }
             let stats_table =
                 $tabler::table(summary.iter().filter($filter).map(|row| row.stats), league);
-            let totals =
-                $tabler::build_row(summary::team_totals(&season, id, $is_postseason)?, league);
+            let totals = $tabler::build_row($totals, league);
             TotalsTable {
                 table: stats_table.insert(0, ident_table),
                 totals,
@@ -64,36 +298,354 @@ fn load_team(id: Uuid, season: Season) -> Result<Option<TeamPage>> {
         }};
     }
 
+    let mut opponents_ident = Table::new([("", "")], "text-left", "none");
+    let mut opponents_stats = Vec::new();
+    let standard_label = if exhibition {
+        "Regular Season and Exhibition"
+    } else {
+        "Regular Season"
+    };
+    let opponents_rows = [
+        (standard_label, standard_totals),
+        ("Postseason", postseason_totals),
+    ];
+    for (label, stats) in opponents_rows {
+        if stats.is_pitching() {
+            opponents_ident.push([label.into()]);
+            opponents_stats.push(stats);
+        }
+    }
+    let opponents =
+        pitching::opponents_table(opponents_stats.into_iter()).insert(0, opponents_ident);
+
+    let games =
+        |f: fn(Kind) -> bool| schedule.iter().filter(|(_, entry)| f(entry.kind)).count() as u32;
+    let per_game_rows = [
+        (
+            standard_label,
+            standard_totals,
+            if exhibition {
+                games(|kind| kind != Kind::Postseason)
+            } else {
+                games(|kind| kind == Kind::Regular)
+            },
+        ),
+        (
+            "Postseason",
+            postseason_totals,
+            games(|kind| kind == Kind::Postseason),
+        ),
+    ];
+    let per_game = |side| {
+        let mut ident = Table::new([("", "")], "text-left", "none");
+        let mut rows = Vec::new();
+        for (label, stats, games) in per_game_rows {
+            if games > 0 {
+                ident.push([label.into()]);
+                rows.push((stats, games));
+            }
+        }
+        per_game::table(side, rows.into_iter()).insert(0, ident)
+    };
+    let weather = weather::table(summary::team_weather_totals(&season, id)?.into_iter());
+    let lineup_slots = lineup::slot_table(
+        summary::team_lineup_totals(&season, id)?.into_iter(),
+        league,
+    );
+
     let mut page = TeamPage {
+        team_id: id,
         team: name,
         seasons,
+        day_labels: schedule::day_labels(&schedule),
         schedule,
         ceiling,
         floor,
-        standard_batting: tabler!(batting, false, |s| !s.is_postseason && s.stats.is_batting()),
-        postseason_batting: tabler!(batting, true, |s| s.is_postseason && s.stats.is_batting()),
-        standard_pitching: tabler!(pitching, false, |s| !s.is_postseason
+        distinct_lineups,
+        lineup_changes,
+        has_exhibition: exhibition_totals.is_batting() || exhibition_totals.is_pitching(),
+        exhibition,
+        standard_batting: tabler!(batting, standard_totals, |s| standard(s)
+            && s.stats.is_batting()),
+        postseason_batting: tabler!(batting, postseason_totals, |s| s.is_postseason
+            && s.stats.is_batting()),
+        standard_pitching: tabler!(pitching, standard_totals, |s| standard(s)
+            && s.stats.is_pitching()),
+        postseason_pitching: tabler!(pitching, postseason_totals, |s| s.is_postseason
             && s.stats.is_pitching()),
-        postseason_pitching: tabler!(pitching, true, |s| s.is_postseason && s.stats.is_pitching()),
+        fielding: tabler!(fielding, standard_totals, |s| standard(s)
+            && s.stats.fielding.is_fielding()),
+        per_game_batting: per_game(Side::Batting),
+        per_game_pitching: per_game(Side::Pitching),
+        opponents,
+        weather,
+        lineup_slots,
         season,
     };
     page.postseason_batting.table.skip("OPS+");
+    page.postseason_batting.table.skip("wRC+");
     page.postseason_pitching.table.skip("ERA+");
     page.postseason_pitching.table.skip("FIP");
     Ok(Some(page))
 }
 
+fn load_team_history(id: Uuid) -> Result<Option<TeamHistoryPage>> {
+    let team = match names::team_name(id)? {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+
+    let mut table = Table::new(
+        [
+            ("Season", ""),
+            ("Team", ""),
+            ("Wins", "W"),
+            ("Losses", "L"),
+            ("Win-Loss Percentage", "W-L%"),
+            ("Finish", ""),
+            ("Postseason", ""),
+            ("Team Batting Average", "BA"),
+            ("Team Earned Run Average", "ERA"),
+        ],
+        "text-right",
+        "number",
+    );
+    table.col_class[0] = "text-left";
+    table.col_class[1] = "text-left";
+    table.sort_method[0] = "none";
+    table.sort_method[1] = "none";
+
+    for (season, team_id) in team.all_seasons()? {
+        let teams = summary::season_team_summary(&season)?;
+        let stats = match teams.iter().find(|row| row.id == team_id) {
+            Some(row) => row.stats,
+            None => continue,
+        };
+        let records = teams
+            .iter()
+            .map(|row| Ok((row.id, schedule::regular_season_record(row.id, &season)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let record = records
+            .iter()
+            .find(|(id, _)| *id == team_id)
+            .map(|(_, record)| *record)
+            .unwrap_or_default();
+        let finish = 1 + records
+            .iter()
+            .filter(|(_, other)| other.wins > record.wins)
+            .count();
+        let postseason = summary::season_postseason_team_summary(&season)?
+            .iter()
+            .any(|row| row.id == team_id);
+
+        table.push(row![
+            format!("{:#}", season),
+            names::team_name(team_id)?.unwrap_or_default().shorthand,
+            u32::from(record.wins),
+            u32::from(record.losses),
+            Pct::<3>::new(record.wins, record.wins + record.losses),
+            format!("{} of {}", ordinal(finish), teams.len()),
+            if postseason { "Yes" } else { "" },
+            stats.batting_average(),
+            stats.earned_run_average(),
+        ]);
+        table.set_href(
+            1,
+            uri!(team(
+                id = team_id,
+                sim = &season.sim,
+                season = season.season,
+                exhibition = _
+            )),
+        );
+    }
+
+    Ok(Some(TeamHistoryPage {
+        team,
+        team_id: id,
+        table,
+    }))
+}
+
+fn load_team_leaders(id: Uuid) -> Result<Option<TeamLeadersPage>> {
+    let team = match names::team_name(id)? {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    let totals = summary::franchise_player_totals(&team.all_seasons()?)?;
+    let leaderboards = |categories: &[leaders::Category]| {
+        categories
+            .iter()
+            .map(|category| Ok((category.name, leaders::table(&totals, category)?)))
+            .collect::<Result<Vec<_>>>()
+    };
+    Ok(Some(TeamLeadersPage {
+        team_id: id,
+        batting: leaderboards(leaders::BATTING)?,
+        pitching: leaderboards(leaders::PITCHING)?,
+        team,
+    }))
+}
+
+fn load_matchup(team_a: Uuid, team_b: Uuid, season: Season) -> Result<Option<MatchupPage>> {
+    let (a, b) = match (names::team_name(team_a)?, names::team_name(team_b)?) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return Ok(None),
+    };
+    if !a.all_seasons()?.iter().any(|(s, _)| s == &season) {
+        return Ok(None);
+    }
+
+    let mut games = Table::new(
+        [
+            ("Day", ""),
+            ("", ""),
+            ("Opponent", ""),
+            ("Result", ""),
+            ("Score", ""),
+        ],
+        "text-left",
+        "none",
+    );
+    games.col_class[0] = "text-right";
+    games.sort_method[0] = "number";
+
+    let mut record = Record::default();
+    let mut runs = (0, 0);
+    let mut stats = (Stats::default(), Stats::default());
+    for (_, entry) in schedule::schedule(team_a, &season)? {
+        if entry.opponent_id != team_b {
+            continue;
+        }
+        if entry.won {
+            record.wins += 1;
+        } else {
+            record.losses += 1;
+        }
+        runs.0 += u32::from(entry.score);
+        runs.1 += u32::from(entry.opponent_score);
+        if let Some(game) = game::load(entry.id)? {
+            for team in game.teams() {
+                let total = team.stats.values().copied().sum::<Stats>();
+                if team.id == team_a {
+                    stats.0 += total;
+                } else if team.id == team_b {
+                    stats.1 += total;
+                }
+            }
+        }
+
+        games.push(row![
+            u32::from(entry.day + 1),
+            if entry.home { "vs." } else { "@" },
+            entry.opponent.shorthand,
+            if entry.won { "W" } else { "L" },
+            format!("{}-{}", entry.score, entry.opponent_score),
+        ]);
+        games.set_href(4, uri!(game(id = entry.id)));
+    }
+    if games.rows.is_empty() {
+        return Ok(None);
+    }
+
+    let league = summary::league_totals(&season)?;
+    let mut ident = Table::new([("Team", "")], "text-left", "none");
+    ident.push([a.shorthand.as_str().into()]);
+    ident.push([b.shorthand.as_str().into()]);
+    let batting = batting::table([stats.0, stats.1].into_iter(), league).insert(0, ident);
+
+    let mut ident = Table::new([("Team", "")], "text-left", "none");
+    ident.push([a.shorthand.as_str().into()]);
+    ident.push([b.shorthand.as_str().into()]);
+    let mut pitching = pitching::table([stats.0, stats.1].into_iter(), league).insert(0, ident);
+    pitching.skip("W").skip("L").skip("W-L%");
+
+    Ok(Some(MatchupPage {
+        team_uri: uri!(team(
+            id = team_a,
+            sim = &season.sim,
+            season = season.season,
+            exhibition = _
+        ))
+        .to_string(),
+        a,
+        b,
+        season,
+        record,
+        runs_a: runs.0,
+        runs_b: runs.1,
+        games,
+        batting,
+        pitching,
+    }))
+}
+
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+#[derive(Template)]
+#[template(path = "team_history.html")]
+struct TeamHistoryPage {
+    team: TeamName,
+    team_id: Uuid,
+    table: Table<9>,
+}
+
+#[derive(Template)]
+#[template(path = "team_leaders.html")]
+struct TeamLeadersPage {
+    team: TeamName,
+    team_id: Uuid,
+    batting: Vec<(&'static str, Table<3>)>,
+    pitching: Vec<(&'static str, Table<3>)>,
+}
+
+#[derive(Template)]
+#[template(path = "matchup.html")]
+struct MatchupPage {
+    a: TeamName,
+    b: TeamName,
+    team_uri: String,
+    season: Season,
+    record: Record,
+    runs_a: u32,
+    runs_b: u32,
+    games: Table<5>,
+    batting: Table<{ batting::COLS + 1 }>,
+    pitching: Table<{ pitching::COLS + 1 }>,
+}
+
 #[derive(Template)]
 #[template(path = "team.html")]
 struct TeamPage {
+    team_id: Uuid,
     team: TeamName,
     season: Season,
     seasons: Vec<(Season, Uuid)>,
     schedule: Vec<(Record, Entry)>,
+    day_labels: Vec<String>,
     ceiling: i32,
     floor: i32,
+    distinct_lineups: usize,
+    lineup_changes: usize,
+    has_exhibition: bool,
+    exhibition: bool,
     standard_batting: TotalsTable<{ batting::COLS + 1 }, { batting::COLS }>,
     postseason_batting: TotalsTable<{ batting::COLS + 1 }, { batting::COLS }>,
     standard_pitching: TotalsTable<{ pitching::COLS + 1 }, { pitching::COLS }>,
     postseason_pitching: TotalsTable<{ pitching::COLS + 1 }, { pitching::COLS }>,
+    fielding: TotalsTable<{ fielding::COLS + 1 }, { fielding::COLS }>,
+    per_game_batting: Table<{ per_game::COLS + 1 }>,
+    per_game_pitching: Table<{ per_game::COLS + 1 }>,
+    opponents: Table<{ pitching::OPPONENTS_COLS + 1 }>,
+    weather: Table<{ weather::COLS }>,
+    lineup_slots: Table<{ lineup::COLS }>,
 }
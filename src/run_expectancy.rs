@@ -0,0 +1,154 @@
+//! Base-out run expectancy and RE24.
+//!
+//! Each season's run expectancy matrix is built from its own games: every plate appearance is
+//! credited to the batter as an occurrence of its starting base-out state, along with the runs
+//! scored from then until the end of the half-inning. Summed over the league, those give the
+//! expected runs for each state.
+//!
+//! RE24 is the change in run expectancy over a player's plate appearances, plus the runs scored
+//! on them. Since the matrix isn't known until the season's games are processed, each player's
+//! stats instead record the net number of times each state started (-1) and ended (+1) one of
+//! their plate appearances; RE24 is computed from those and the league matrix when displayed.
+
+use crate::fraction::Fraction;
+use crate::percentage::Pct;
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, AddAssign, SubAssign};
+
+/// Three out counts times eight base states. Bases use bit 0 for first, bit 1 for second, and bit
+/// 2 for third.
+pub const STATES: usize = 24;
+
+pub fn state(outs: u16, bases: u8) -> Option<usize> {
+    if outs < 3 {
+        Some(usize::from(outs) * 8 + usize::from(bases & 0b111))
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RunExpectancy {
+    /// Plate appearances starting in each state, for completed half-innings
+    pub occurrences: [i32; STATES],
+    /// Runs scored from the start of those plate appearances to the end of the half-inning
+    pub runs_after: [i32; STATES],
+    /// Net states started (-1) and ended (+1) by this player's plate appearances as a batter
+    pub batting: [i32; STATES],
+    pub batting_runs: i32,
+    /// The same, for plate appearances by batters this player pitched to
+    pub pitching: [i32; STATES],
+    pub pitching_runs: i32,
+}
+
+impl RunExpectancy {
+    pub fn is_empty(&self) -> bool {
+        self == &RunExpectancy::default()
+    }
+
+    /// Expected runs for the rest of the half-inning from each state. `self` should be league
+    /// totals.
+    pub fn matrix(&self) -> [f64; STATES] {
+        let mut matrix = [0.0; STATES];
+        for (i, value) in matrix.iter_mut().enumerate() {
+            if self.occurrences[i] > 0 {
+                *value = f64::from(self.runs_after[i]) / f64::from(self.occurrences[i]);
+            }
+        }
+        matrix
+    }
+
+    pub fn batting_re24(&self, matrix: &[f64; STATES]) -> Pct<1> {
+        to_pct(re24(&self.batting, self.batting_runs, matrix))
+    }
+
+    /// Runs prevented, so higher is better.
+    pub fn pitching_re24(&self, matrix: &[f64; STATES]) -> Pct<1> {
+        to_pct(-re24(&self.pitching, self.pitching_runs, matrix))
+    }
+}
+
+fn re24(net: &[i32; STATES], runs: i32, matrix: &[f64; STATES]) -> f64 {
+    net.iter()
+        .zip(matrix)
+        .map(|(n, re)| f64::from(*n) * re)
+        .sum::<f64>()
+        + f64::from(runs)
+}
+
+fn to_pct(value: f64) -> Pct<1> {
+    Pct(Fraction::new((value * 1000.0).round() as i64, 1000))
+}
+
+impl Add for RunExpectancy {
+    type Output = RunExpectancy;
+
+    fn add(mut self, rhs: RunExpectancy) -> RunExpectancy {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for RunExpectancy {
+    fn add_assign(&mut self, rhs: RunExpectancy) {
+        for (a, b) in [
+            (&mut self.occurrences, rhs.occurrences),
+            (&mut self.runs_after, rhs.runs_after),
+            (&mut self.batting, rhs.batting),
+            (&mut self.pitching, rhs.pitching),
+        ] {
+            for (a, b) in a.iter_mut().zip(b) {
+                *a += b;
+            }
+        }
+        self.batting_runs += rhs.batting_runs;
+        self.pitching_runs += rhs.pitching_runs;
+    }
+}
+
+impl SubAssign for RunExpectancy {
+    fn sub_assign(&mut self, rhs: RunExpectancy) {
+        for (a, b) in [
+            (&mut self.occurrences, rhs.occurrences),
+            (&mut self.runs_after, rhs.runs_after),
+            (&mut self.batting, rhs.batting),
+            (&mut self.pitching, rhs.pitching),
+        ] {
+            for (a, b) in a.iter_mut().zip(b) {
+                *a -= b;
+            }
+        }
+        self.batting_runs -= rhs.batting_runs;
+        self.pitching_runs -= rhs.pitching_runs;
+    }
+}
+
+impl std::iter::Sum for RunExpectancy {
+    fn sum<I: Iterator<Item = RunExpectancy>>(iter: I) -> RunExpectancy {
+        iter.fold(RunExpectancy::default(), |a, b| a + b)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    let mut league = RunExpectancy::default();
+    // bases empty, nobody out: 2 occurrences, 1 run scored after
+    league.occurrences[0] = 2;
+    league.runs_after[0] = 1;
+    // runner on first, nobody out: 1 occurrence, 1 run scored after
+    league.occurrences[1] = 1;
+    league.runs_after[1] = 1;
+    let matrix = league.matrix();
+    assert_eq!(matrix[0], 0.5);
+    assert_eq!(matrix[1], 1.0);
+
+    // a leadoff single moves from 0.5 to 1.0 run expected
+    let mut player = RunExpectancy::default();
+    player.batting[0] -= 1;
+    player.batting[1] += 1;
+    assert_eq!(player.batting_re24(&matrix).to_string(), "0.5");
+    assert_eq!(state(2, 0b101), Some(21));
+    assert_eq!(state(3, 0), None);
+}
@@ -1,14 +1,32 @@
-use crate::game::Kind;
+use crate::event_type::EventType;
+use crate::game::{self, Kind, Series};
 use crate::names::TeamName;
-use crate::{seasons::Season, API_BASE, CLIENT, DB};
+use crate::{seasons::Season, upstream, API_BASE, DB};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::value::RawValue;
-use std::collections::BTreeMap;
-use std::mem::size_of_val;
+use sled::transaction::{
+    ConflictableTransactionError, ConflictableTransactionResult, TransactionalTree,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::mem::{size_of, size_of_val};
+use std::sync::Mutex;
 use uuid::Uuid;
 
-pub const TREE: &str = "schedule_v1";
+pub const TREE: &str = "schedule_v2";
+/// The home team's entry for each game, by season and day, so a day's games can be found without
+/// scanning the season's schedule. Keys are `day_key` followed by the home team's ID and game
+/// index, and values are empty.
+pub const DAY_TREE: &str = "schedule_days_v1";
+const CACHE_TREE: &str = "cache_schedule_v1";
+
+lazy_static::lazy_static! {
+    /// Schedules for days that aren't over yet, as last fetched by `load_with_incomplete`, by
+    /// cache key. These aren't written to `CACHE_TREE`, but games in progress on those days still
+    /// look themselves up in them.
+    static ref UNFINISHED: Mutex<HashMap<Vec<u8>, Vec<Game>>> = Mutex::new(HashMap::new());
+}
 
 #[derive(Debug, Default, Clone, Copy, Serialize)]
 pub struct Record {
@@ -17,6 +35,17 @@ pub struct Record {
 }
 
 impl Record {
+    /// Counts a game, along with any wins set or swallowed by the weather.
+    pub fn push(&mut self, entry: &Entry) {
+        if entry.won {
+            self.wins += 1;
+        } else {
+            self.losses += 1;
+        }
+        let wins = i64::from(self.wins) + i64::from(entry.weather_wins);
+        self.wins = wins.clamp(0, i64::from(u16::MAX)) as u16;
+    }
+
     pub fn diff(&self) -> i32 {
         i32::from(self.wins) - i32::from(self.losses)
     }
@@ -26,13 +55,30 @@ impl Record {
 pub struct Entry {
     pub id: Uuid,
     pub day: u16,
+    /// The game's position among the team's games on this day, which is only nonzero when a day
+    /// has more than one game (such as when the sim pauses and a game is replayed)
+    #[serde(default)]
+    pub game_index: u8,
     #[serde(default)]
     pub kind: Kind,
     pub home: bool,
     pub opponent: TeamName,
+    #[serde(default)]
+    pub opponent_id: Uuid,
     pub won: bool,
+    /// Wins set upon (Sun 2) or swallowed from (Black Hole) this team during the game
+    #[serde(default)]
+    pub weather_wins: i16,
     pub score: u16,
     pub opponent_score: u16,
+    /// The first player in each lineup position
+    #[serde(default)]
+    pub lineup: Vec<Uuid>,
+    /// When the first and last events in the game's feed were created
+    #[serde(default)]
+    pub start: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub end: Option<DateTime<Utc>>,
 }
 
 impl Entry {
@@ -41,6 +87,86 @@ impl Entry {
     }
 }
 
+/// The key of a team's entry for a game in the schedule tree. Keys end with the day and the game
+/// index, so a team's games are in the order they were played.
+pub fn entry_key(season: &Season, team: Uuid, day: u16, game_index: u8) -> Vec<u8> {
+    let mut key = Vec::with_capacity(
+        season.sim.len()
+            + size_of_val(&season.season)
+            + size_of_val(&team)
+            + size_of_val(&day)
+            + size_of_val(&game_index),
+    );
+    key.extend_from_slice(season.sim.as_bytes());
+    key.extend_from_slice(&season.season.to_ne_bytes());
+    key.extend_from_slice(team.as_bytes());
+    key.extend_from_slice(&day.to_be_bytes());
+    key.push(game_index);
+    key
+}
+
+fn day_key(season: &Season, day: u16) -> Vec<u8> {
+    let mut key =
+        Vec::with_capacity(season.sim.len() + size_of_val(&season.season) + size_of_val(&day));
+    key.extend_from_slice(season.sim.as_bytes());
+    key.extend_from_slice(&season.season.to_ne_bytes());
+    key.extend_from_slice(&day.to_be_bytes());
+    key
+}
+
+/// The key of a home team's entry in `DAY_TREE`.
+pub fn day_index_key(season: &Season, home: Uuid, day: u16, game_index: u8) -> Vec<u8> {
+    let mut key = day_key(season, day);
+    key.extend_from_slice(home.as_bytes());
+    key.push(game_index);
+    key
+}
+
+/// Finds the game index for a team's entry for a game: the index it's already stored under, or
+/// else the first free index on that day.
+pub fn find_game_index(
+    tree: &TransactionalTree,
+    season: &Season,
+    team: Uuid,
+    day: u16,
+    id: Uuid,
+) -> ConflictableTransactionResult<u8, serde_json::Error> {
+    for game_index in 0..u8::MAX {
+        match tree.get(entry_key(season, team, day, game_index))? {
+            None => return Ok(game_index),
+            Some(value) => {
+                let entry: Entry =
+                    serde_json::from_slice(&value).map_err(ConflictableTransactionError::Abort)?;
+                if entry.id == id {
+                    return Ok(game_index);
+                }
+            }
+        }
+    }
+    Ok(u8::MAX)
+}
+
+/// Labels each game in a team's schedule with its day, and its game number on days with more than
+/// one game (e.g. "Day 12, Game 2").
+pub fn day_labels(schedule: &[(Record, Entry)]) -> Vec<String> {
+    schedule
+        .iter()
+        .enumerate()
+        .map(|(i, (_, entry))| {
+            let same_day = |other: Option<&(Record, Entry)>| {
+                other.map_or(false, |(_, other)| other.day == entry.day)
+            };
+            if same_day(i.checked_sub(1).and_then(|i| schedule.get(i)))
+                || same_day(schedule.get(i + 1))
+            {
+                format!("Day {}, Game {}", entry.day + 1, entry.game_index + 1)
+            } else {
+                format!("Day {}", entry.day + 1)
+            }
+        })
+        .collect()
+}
+
 pub fn schedule(team: Uuid, season: &Season) -> Result<Vec<(Record, Entry)>> {
     let tree = DB.open_tree(TREE)?;
     let mut search_key =
@@ -54,20 +180,122 @@ pub fn schedule(team: Uuid, season: &Season) -> Result<Vec<(Record, Entry)>> {
         let (_, value) = row?;
         let entry: Entry = serde_json::from_slice(&value)?;
         if entry.kind != Kind::Special {
-            if entry.won {
-                record.wins += 1;
-            } else {
-                record.losses += 1;
-            }
+            record.push(&entry);
         }
         v.push((record, entry));
     }
     Ok(v)
 }
 
+/// Returns the games played on a day of a season, as the home team's ID and schedule entry.
+pub fn day(season: &Season, day: u16) -> Result<Vec<(Uuid, Entry)>> {
+    let tree = DB.open_tree(TREE)?;
+    let prefix = day_key(season, day);
+    let mut v = Vec::new();
+    for row in DB.open_tree(DAY_TREE)?.scan_prefix(&prefix) {
+        let (key, _) = row?;
+        let (home, game_index) = key[prefix.len()..].split_at(size_of::<Uuid>());
+        let home = Uuid::from_slice(home)?;
+        if let Some(value) = tree.get(entry_key(season, home, day, game_index[0]))? {
+            let entry: Entry = serde_json::from_slice(&value)?;
+            if entry.home {
+                v.push((home, entry));
+            }
+        }
+    }
+    Ok(v)
+}
+
+/// Whether any games were played on a day of a season.
+pub fn has_day(season: &Season, day: u16) -> Result<bool> {
+    Ok(DB
+        .open_tree(DAY_TREE)?
+        .scan_prefix(day_key(season, day))
+        .next()
+        .transpose()?
+        .is_some())
+}
+
+/// Returns every game played in a season, as the home team's ID and schedule entry.
+pub fn games(season: &Season) -> Result<Vec<(Uuid, Entry)>> {
+    let tree = DB.open_tree(TREE)?;
+    let mut search_key = Vec::with_capacity(season.sim.len() + size_of_val(&season.season));
+    search_key.extend_from_slice(season.sim.as_bytes());
+    search_key.extend_from_slice(&season.season.to_ne_bytes());
+    let mut v = Vec::new();
+    for row in tree.scan_prefix(&search_key) {
+        let (key, value) = row?;
+        let entry: Entry = serde_json::from_slice(&value)?;
+        if entry.home {
+            let team = &key[search_key.len()..key.len() - size_of::<u16>() - size_of::<u8>()];
+            v.push((Uuid::from_slice(team)?, entry));
+        }
+    }
+    Ok(v)
+}
+
+/// Counts a team's regular season record, excluding postseason and special games.
+pub fn regular_season_record(team: Uuid, season: &Season) -> Result<Record> {
+    let mut record = Record::default();
+    for (_, entry) in schedule(team, season)? {
+        if entry.kind == Kind::Regular {
+            record.push(&entry);
+        }
+    }
+    Ok(record)
+}
+
+/// Determines where a postseason game falls in its series, based on the games between the two
+/// teams already recorded in the schedule tree.
+pub fn series(game: &game::Game) -> Result<Option<Series>> {
+    if !game.is_postseason() {
+        return Ok(None);
+    }
+
+    let mut series = Series {
+        game_number: 1,
+        home_field: game.home.id,
+        away_wins: 0,
+        home_wins: 0,
+    };
+    let previous = schedule(game.home.id, &game.season)?
+        .into_iter()
+        .map(|(_, entry)| entry)
+        .filter(|entry| {
+            entry.kind == Kind::Postseason
+                && entry.opponent_id == game.away.id
+                && entry.day < game.day
+        })
+        .collect::<Vec<_>>();
+    if let Some(first) = previous.first() {
+        series.game_number += u16::try_from(previous.len())?;
+        if !first.home {
+            series.home_field = game.away.id;
+        }
+    }
+    for entry in previous {
+        if entry.won {
+            series.home_wins += 1;
+        } else {
+            series.away_wins += 1;
+        }
+    }
+    Ok(Some(series))
+}
+
 // =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
 
 pub async fn load(season: &Season, start_day: u16, end_day: u16) -> Result<Vec<Uuid>> {
+    Ok(load_with_incomplete(season, start_day, end_day).await?.0)
+}
+
+/// Like `load`, but also returns the games in the range that aren't complete yet (in progress or
+/// not yet started).
+pub async fn load_with_incomplete(
+    season: &Season,
+    start_day: u16,
+    end_day: u16,
+) -> Result<(Vec<Uuid>, Vec<Uuid>)> {
     #[derive(Debug, Serialize)]
     #[serde(rename_all = "camelCase")]
     struct Query<'a> {
@@ -77,9 +305,10 @@ pub async fn load(season: &Season, start_day: u16, end_day: u16) -> Result<Vec<U
         end_day: u16,
     }
 
-    let cache_tree = DB.open_tree("cache_schedule_v1")?;
+    let cache_tree = DB.open_tree(CACHE_TREE)?;
 
     let mut cached: BTreeMap<u16, Vec<Game>> = BTreeMap::new();
+    let mut incomplete = Vec::new();
     for day in start_day..=end_day {
         if let Some(value) = cache_tree.get(&build_cache_key(season, day))? {
             cached.insert(day, filter_complete(serde_json::from_slice(&value)?));
@@ -91,30 +320,95 @@ pub async fn load(season: &Season, start_day: u16, end_day: u16) -> Result<Vec<U
             .rev()
             .find(|day| !cached.contains_key(day))
             .unwrap();
-        let response: BTreeMap<u16, Box<RawValue>> = CLIENT
-            .get(format!(
-                "{}/api/games/schedule?{}",
-                API_BASE,
-                serde_urlencoded::to_string(&Query {
-                    season,
-                    start_day: start_missing,
-                    end_day: end_missing
-                })?
-            ))
-            .send()
-            .await?
-            .json()
-            .await?;
+        let response: BTreeMap<u16, Box<RawValue>> = upstream::get(format!(
+            "{}/api/games/schedule?{}",
+            API_BASE,
+            serde_urlencoded::to_string(&Query {
+                season,
+                start_day: start_missing,
+                end_day: end_missing
+            })?
+        ))
+        .await?
+        .json()?;
+        let mut unfinished = HashMap::new();
         for (day, raw_schedule) in response {
             let schedule: Vec<Game> = serde_json::from_str(raw_schedule.get())?;
             if schedule.iter().all(|game| game.game_complete) {
                 cache_tree.insert(&build_cache_key(season, day), raw_schedule.get())?;
+            } else {
+                unfinished.insert(build_cache_key(season, day), schedule.clone());
             }
+            incomplete.extend(
+                schedule
+                    .iter()
+                    .filter(|game| !game.game_complete)
+                    .map(|game| game.id),
+            );
             cached.insert(day, filter_complete(schedule));
         }
+        *UNFINISHED.lock().unwrap() = unfinished;
+    }
+
+    Ok((
+        cached.values().flatten().map(|game| game.id).collect(),
+        incomplete,
+    ))
+}
+
+/// Returns every game in the season's cached schedule, which covers each day that has been fully
+/// played, whether or not the games were processed.
+pub fn cached_games(season: &Season) -> Result<Vec<Uuid>> {
+    let mut search_key = Vec::with_capacity(season.sim.len() + size_of_val(&season.season));
+    search_key.extend_from_slice(season.sim.as_bytes());
+    search_key.extend_from_slice(&season.season.to_ne_bytes());
+    let mut v = Vec::new();
+    for row in DB.open_tree(CACHE_TREE)?.scan_prefix(&search_key) {
+        let (_, value) = row?;
+        v.extend(
+            serde_json::from_slice::<Vec<Game>>(&value)?
+                .into_iter()
+                .map(|game| game.id),
+        );
     }
+    Ok(v)
+}
 
-    Ok(cached.values().flatten().map(|game| game.id).collect())
+/// A game in the season's cached schedule, for the schedule grid.
+#[derive(Debug)]
+pub struct ScheduledGame {
+    pub id: Uuid,
+    pub away: Uuid,
+    pub home: Uuid,
+    pub away_score: Option<f64>,
+    pub home_score: Option<f64>,
+    pub is_postseason: bool,
+}
+
+/// Returns the games in the season's cached schedule, by day (starting from 0).
+pub fn cached_days(season: &Season) -> Result<BTreeMap<u16, Vec<ScheduledGame>>> {
+    let mut search_key = Vec::with_capacity(season.sim.len() + size_of_val(&season.season));
+    search_key.extend_from_slice(season.sim.as_bytes());
+    search_key.extend_from_slice(&season.season.to_ne_bytes());
+    let mut days = BTreeMap::new();
+    for row in DB.open_tree(CACHE_TREE)?.scan_prefix(&search_key) {
+        let (key, value) = row?;
+        // keys end with the day in native byte order, so they aren't scanned in day order
+        let day = u16::from_ne_bytes(key[search_key.len()..].try_into()?);
+        let games = serde_json::from_slice::<Vec<Game>>(&value)?
+            .into_iter()
+            .map(|game| ScheduledGame {
+                id: game.id,
+                away: game.away_team,
+                home: game.home_team,
+                away_score: game.away_score,
+                home_score: game.home_score,
+                is_postseason: game.is_postseason.unwrap_or_default(),
+            })
+            .collect();
+        days.insert(day, games);
+    }
+    Ok(days)
 }
 
 fn filter_complete(schedule: Vec<Game>) -> Vec<Game> {
@@ -124,11 +418,87 @@ fn filter_complete(schedule: Vec<Game>) -> Vec<Game> {
         .collect()
 }
 
-#[derive(Debug, Deserialize)]
+/// Whether the schedule API marks a game as a postseason game, if its day's schedule is cached
+/// (which it is once every game that day is complete, or while the day is being played).
+pub fn is_postseason(season: &Season, day: u16, game_id: Uuid) -> Result<Option<bool>> {
+    Ok(cached_game(season, day, game_id)?.and_then(|game| game.is_postseason))
+}
+
+/// The first day of a season's cached schedule with postseason games, if any have been played.
+pub fn postseason_start(season: &Season) -> Result<Option<u16>> {
+    Ok(cached_days(season)?
+        .into_iter()
+        .find(|(_, games)| games.iter().any(|game| game.is_postseason))
+        .map(|(day, _)| day))
+}
+
+/// Whether a day is set aside for special games, which is when the schedule API lists title
+/// matches on it, if its schedule is cached.
+pub fn is_special_day(season: &Season, day: u16) -> Result<Option<bool>> {
+    Ok(scheduled_day(season, day)?.map(|games| {
+        games
+            .iter()
+            .any(|game| game.is_title_match.unwrap_or_default())
+    }))
+}
+
+/// The away and home scores the schedule API reports for a game, if its day's schedule is cached.
+pub fn final_score(season: &Season, day: u16, game_id: Uuid) -> Result<Option<(f64, f64)>> {
+    Ok(cached_game(season, day, game_id)?
+        .and_then(|game| Some((game.away_score?, game.home_score?))))
+}
+
+/// Returns a day's schedule as the schedule API sent it, if it's cached.
+pub fn cached_day(season: &Season, day: u16) -> Result<Option<Box<RawValue>>> {
+    Ok(
+        match DB
+            .open_tree(CACHE_TREE)?
+            .get(build_cache_key(season, day))?
+        {
+            Some(value) => Some(serde_json::from_slice(&value)?),
+            None => None,
+        },
+    )
+}
+
+/// Adds a day's schedule to the cache, so that it's used without asking the schedule API.
+#[cfg(test)]
+pub fn insert_cached_day(season: &Season, day: u16, schedule: &RawValue) -> Result<()> {
+    DB.open_tree(CACHE_TREE)?
+        .insert(build_cache_key(season, day), schedule.get())?;
+    Ok(())
+}
+
+fn cached_game(season: &Season, day: u16, game_id: Uuid) -> Result<Option<Game>> {
+    Ok(scheduled_day(season, day)?
+        .and_then(|games| games.into_iter().find(|game| game.id == game_id)))
+}
+
+fn scheduled_day(season: &Season, day: u16) -> Result<Option<Vec<Game>>> {
+    let key = build_cache_key(season, day);
+    Ok(match DB.open_tree(CACHE_TREE)?.get(&key)? {
+        Some(value) => Some(serde_json::from_slice(&value)?),
+        None => UNFINISHED.lock().unwrap().get(&key).cloned(),
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Game {
     id: Uuid,
     game_complete: bool,
+    #[serde(default)]
+    is_postseason: Option<bool>,
+    #[serde(default)]
+    is_title_match: Option<bool>,
+    #[serde(default)]
+    away_score: Option<f64>,
+    #[serde(default)]
+    home_score: Option<f64>,
+    #[serde(default)]
+    away_team: Uuid,
+    #[serde(default)]
+    home_team: Uuid,
 }
 
 // =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
@@ -163,22 +533,50 @@ pub async fn last_day(season: &Season) -> Result<Option<u16>> {
         day: u16,
     }
 
-    let response: Vec<FeedEvent> = CLIENT
-        .get(format!(
-            "{}/database/feed/global?{}",
-            API_BASE,
-            serde_urlencoded::to_string(&Query {
-                ty: 11,
-                sim: &season.sim,
-                season_start: season.season,
-                season_end: season.season,
-                sort: 0,
-                limit: 1,
-            })?
-        ))
-        .send()
-        .await?
-        .json()
-        .await?;
+    let response: Vec<FeedEvent> = upstream::get(format!(
+        "{}/database/feed/global?{}",
+        API_BASE,
+        serde_urlencoded::to_string(&Query {
+            ty: EventType::GameEnd.into(),
+            sim: &season.sim,
+            season_start: season.season,
+            season_end: season.season,
+            sort: 0,
+            limit: 1,
+        })?
+    ))
+    .await?
+    .json()?;
     Ok(response.into_iter().next().map(|event| event.day))
 }
+
+#[cfg(test)]
+#[test]
+fn test_day_labels() {
+    let entry = |day: u16, game_index: u8| {
+        (
+            Record::default(),
+            Entry {
+                id: Uuid::from_u128(u128::from(day) << 8 | u128::from(game_index)),
+                day,
+                game_index,
+                kind: Kind::Regular,
+                home: true,
+                opponent: TeamName::default(),
+                opponent_id: Uuid::default(),
+                won: true,
+                weather_wins: 0,
+                score: 0,
+                opponent_score: 0,
+                lineup: Vec::new(),
+                start: None,
+                end: None,
+            },
+        )
+    };
+    let schedule = [entry(0, 0), entry(1, 0), entry(1, 1), entry(2, 0)];
+    assert_eq!(
+        day_labels(&schedule),
+        ["Day 1", "Day 2, Game 1", "Day 2, Game 2", "Day 3"]
+    );
+}
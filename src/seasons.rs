@@ -3,7 +3,7 @@ use crate::routes::season::{
     rocket_uri_macro_season_team_batting, rocket_uri_macro_season_team_pitching,
 };
 use crate::routes::team::rocket_uri_macro_team;
-use crate::DB;
+use crate::{upstream, API_BASE, DB};
 use anyhow::{Context, Result};
 use rocket::uri;
 use serde::{Deserialize, Serialize};
@@ -17,6 +17,25 @@ const NAME_TREE: &str = "sim_names_v1";
 const SORT_TREE: &str = "sim_order_v1";
 pub const RECORDED_TREE: &str = "recorded_seasons_v1";
 
+/// How the league baseline for adjusted stats (OPS+, ERA+, FIP) is computed for a season.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Baseline {
+    /// The season's own regular season league totals
+    Season,
+    /// League totals pooled across every recorded season in the same era, for sims whose seasons
+    /// are too short to produce a stable league average on their own
+    Era,
+}
+
+/// Sims that use something other than `Baseline::Season`.
+const BASELINES: &[(&str, Baseline)] = &[("gamma4", Baseline::Era)];
+
+/// Sims with feeds the state machine doesn't fully understand. Their games are still processed,
+/// but any game that fails is recorded score-only (see `game::process_score_only`) instead of
+/// being retried, and a sim whose schedule can't be loaded is skipped rather than failing the
+/// start task.
+const SCORE_ONLY_FALLBACK: &[&str] = &["thisidisstaticyo", "gamma4"];
+
 pub async fn load() -> Result<()> {
     let name_tree = DB.open_tree(NAME_TREE)?;
     let sort_tree = DB.open_tree(SORT_TREE)?;
@@ -35,6 +54,28 @@ pub async fn load() -> Result<()> {
     Ok(())
 }
 
+/// Fetches the current season and day from the Blaseball API.
+pub async fn current() -> Result<(Season, u16)> {
+    #[derive(Debug, Deserialize)]
+    struct SimData {
+        #[serde(rename = "id")]
+        sim: String,
+        season: u16,
+        day: u16,
+    }
+
+    let now: SimData = upstream::get(format!("{}/database/simulationData", API_BASE))
+        .await?
+        .json()?;
+    Ok((
+        Season {
+            sim: now.sim,
+            season: now.season,
+        },
+        now.day,
+    ))
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Response {
@@ -87,6 +128,49 @@ impl Season {
         Season::read_from_tree(RECORDED_TREE)
     }
 
+    /// Resolves a sim and season from a URL to a known season. `sim` may differ in case from the
+    /// sim ID, or may be an era name (e.g. "Gamma 2"), in which case `season` is the season number
+    /// as displayed for that era.
+    pub fn resolve(sim: &str, season: u16) -> Result<Option<Season>> {
+        let mut seasons = Season::recorded()?;
+        seasons.extend(Season::known()?);
+
+        if let Some(found) = seasons
+            .iter()
+            .find(|s| s.sim.eq_ignore_ascii_case(sim) && s.season == season)
+        {
+            return Ok(Some(found.clone()));
+        }
+        for s in seasons {
+            if s.season + 1 == season
+                && s.era_name()?
+                    .map_or(false, |name| name.eq_ignore_ascii_case(sim))
+            {
+                return Ok(Some(s));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn baseline(&self) -> Baseline {
+        BASELINES
+            .iter()
+            .find(|(sim, _)| *sim == self.sim)
+            .map_or(Baseline::Season, |(_, baseline)| *baseline)
+    }
+
+    pub fn score_only_fallback(&self) -> bool {
+        SCORE_ONLY_FALLBACK.contains(&self.sim.as_str())
+    }
+
+    /// This season's key in `RECORDED_TREE`.
+    pub fn recorded_key(&self) -> Vec<u8> {
+        let mut key = Vec::with_capacity(self.sim.len() + size_of_val(&self.season));
+        key.extend_from_slice(self.sim.as_bytes());
+        key.extend_from_slice(&self.season.to_be_bytes());
+        key
+    }
+
     pub fn era_name(&self) -> Result<Option<String>> {
         let tree = DB.open_tree(NAME_TREE)?;
         let mut key = Vec::with_capacity(self.sim.len() + size_of_val(&self.season));
@@ -122,23 +206,56 @@ impl Season {
     pub fn uri(&self, is_batting: &bool, is_players: &bool) -> String {
         if *is_players {
             if *is_batting {
-                uri!(season_player_batting(sim = &self.sim, season = self.season))
+                uri!(season_player_batting(
+                    sim = &self.sim,
+                    season = self.season,
+                    qualified = _,
+                    exhibition = _,
+                    sort = _,
+                    order = _,
+                    page = _
+                ))
             } else {
                 uri!(season_player_pitching(
                     sim = &self.sim,
-                    season = self.season
+                    season = self.season,
+                    qualified = _,
+                    exhibition = _,
+                    sort = _,
+                    order = _,
+                    page = _
                 ))
             }
         } else if *is_batting {
-            uri!(season_team_batting(sim = &self.sim, season = self.season))
+            uri!(season_team_batting(
+                sim = &self.sim,
+                season = self.season,
+                exhibition = _,
+                sort = _,
+                order = _,
+                page = _
+            ))
         } else {
-            uri!(season_team_pitching(sim = &self.sim, season = self.season))
+            uri!(season_team_pitching(
+                sim = &self.sim,
+                season = self.season,
+                exhibition = _,
+                sort = _,
+                order = _,
+                page = _
+            ))
         }
         .to_string()
     }
 
     pub fn team_uri(&self, id: &&Uuid) -> String {
-        uri!(team(id = **id, sim = &self.sim, season = self.season)).to_string()
+        uri!(team(
+            id = **id,
+            sim = &self.sim,
+            season = self.season,
+            exhibition = _
+        ))
+        .to_string()
     }
 
     pub fn selected(&self, other: &Season) -> &'static str {
@@ -0,0 +1,183 @@
+use crate::csv::write_csv;
+use crate::export::{Export, WithLeagueStats};
+use crate::seasons::Season;
+use crate::summary::{self, SeasonSummary};
+use crate::DB;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rocket::request::FromParam;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+use std::mem::size_of_val;
+
+/// Snapshot versions of each season: keyed by season and version, valued by `Snapshot`. Like
+/// `DATA_TREE`, this is never cleared on rebuild.
+pub const TREE: &str = "season_snapshots_v1";
+/// Snapshot contents, keyed by their hash, so that identical exports are stored once.
+pub const DATA_TREE: &str = "season_snapshot_data_v1";
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub hash: String,
+    pub created: DateTime<Utc>,
+}
+
+/// A season's exports as of a snapshot, in the same formats as the `export.csv` and
+/// `export.json` routes.
+#[derive(Deserialize, Serialize)]
+pub struct Files {
+    pub players_csv: String,
+    pub players_json: String,
+    pub teams_csv: String,
+    pub teams_json: String,
+}
+
+impl Files {
+    fn render(season: &Season) -> Result<Files> {
+        let league = summary::league_totals(season)?;
+        let export = |summary: Vec<SeasonSummary>| {
+            summary
+                .into_iter()
+                .map(|summary| {
+                    (
+                        summary.id,
+                        Export(WithLeagueStats {
+                            inner: summary,
+                            league,
+                        }),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let players = export(summary::season_player_summary(season)?);
+        let teams = export(summary::season_team_summary(season)?);
+        Ok(Files {
+            players_json: serde_json::to_string(
+                &players
+                    .iter()
+                    .map(|(k, v)| (k, v))
+                    .collect::<BTreeMap<_, _>>(),
+            )?,
+            players_csv: write_csv(players.into_iter().map(|(_, v)| v).collect())?,
+            teams_json: serde_json::to_string(
+                &teams
+                    .iter()
+                    .map(|(k, v)| (k, v))
+                    .collect::<BTreeMap<_, _>>(),
+            )?,
+            teams_csv: write_csv(teams.into_iter().map(|(_, v)| v).collect())?,
+        })
+    }
+
+    fn hash(&self) -> String {
+        let mut hasher = twox_hash::XxHash64::default();
+        for file in [
+            &self.players_csv,
+            &self.players_json,
+            &self.teams_csv,
+            &self.teams_json,
+        ] {
+            hasher.write_usize(file.len());
+            hasher.write(file.as_bytes());
+        }
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+/// A `v<N>` path segment.
+pub struct Version(pub u32);
+
+impl<'a> FromParam<'a> for Version {
+    type Error = ();
+
+    fn from_param(param: &'a str) -> Result<Version, ()> {
+        match param.strip_prefix('v').map(str::parse) {
+            Some(Ok(version)) => Ok(Version(version)),
+            _ => Err(()),
+        }
+    }
+}
+
+fn build_key(season: &Season) -> Vec<u8> {
+    let mut key = Vec::with_capacity(season.sim.len() + size_of_val(&season.season) + 4);
+    key.extend_from_slice(season.sim.as_bytes());
+    key.extend_from_slice(&season.season.to_ne_bytes());
+    key
+}
+
+/// Lists a season's snapshots, oldest first.
+pub fn list(season: &Season) -> Result<Vec<Snapshot>> {
+    DB.open_tree(TREE)?
+        .scan_prefix(build_key(season))
+        .values()
+        .map(|value| Ok(serde_json::from_slice(&value?)?))
+        .collect()
+}
+
+pub fn load(season: &Season, version: u32) -> Result<Option<Files>> {
+    let mut key = build_key(season);
+    key.extend_from_slice(&version.to_be_bytes());
+    let snapshot: Snapshot = match DB.open_tree(TREE)?.get(key)? {
+        Some(value) => serde_json::from_slice(&value)?,
+        None => return Ok(None),
+    };
+    Ok(
+        match DB.open_tree(DATA_TREE)?.get(snapshot.hash.as_bytes())? {
+            Some(value) => Some(serde_json::from_slice(&value)?),
+            None => None,
+        },
+    )
+}
+
+/// Freezes a season's current exports as a new snapshot version, unless they are identical to the
+/// latest snapshot's. Returns the new version, if one was created.
+fn take(season: &Season) -> Result<Option<u32>> {
+    let files = Files::render(season)?;
+    let hash = files.hash();
+    let latest = list(season)?.pop();
+    if latest.as_ref().map(|s| &s.hash) == Some(&hash) {
+        return Ok(None);
+    }
+
+    let version = latest.map_or(1, |s| s.version + 1);
+    let data_tree = DB.open_tree(DATA_TREE)?;
+    if !data_tree.contains_key(hash.as_bytes())? {
+        data_tree.insert(hash.as_bytes(), serde_json::to_vec(&files)?)?;
+    }
+    let mut key = build_key(season);
+    key.extend_from_slice(&version.to_be_bytes());
+    DB.open_tree(TREE)?.insert(
+        key,
+        serde_json::to_vec(&Snapshot {
+            version,
+            hash,
+            created: Utc::now(),
+        })?,
+    )?;
+    log::info!("froze {} exports as snapshot v{}", season, version);
+    Ok(Some(version))
+}
+
+/// Snapshots every recorded season other than `current`, which may still be in progress. With
+/// `refresh`, seasons whose exports have changed since their latest snapshot (e.g. after a
+/// rebuild) get a new version; otherwise only seasons without any snapshot are considered.
+pub fn update(current: &Season, refresh: bool) -> Result<()> {
+    let mut seasons = Vec::new();
+    for season in Season::recorded()? {
+        if &season != current && (refresh || list(&season)?.is_empty()) {
+            seasons.push(season);
+        }
+    }
+    if seasons.is_empty() {
+        return Ok(());
+    }
+
+    // the last games of a season may still be waiting to be folded into its summaries
+    summary::fold()?;
+    for season in seasons {
+        take(&season)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,241 @@
+use crate::game::{self, Kind, Stats};
+use crate::percentage::Pct;
+use crate::table::{row, Table};
+use crate::{schedule, seasons::Season, summary};
+use anyhow::Result;
+use derive_more::{Add, AddAssign, SubAssign, Sum};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::ops::RangeInclusive;
+use uuid::Uuid;
+
+/// Number of lineup slots tracked; batters in later slots are counted in the last one.
+pub const SLOTS: usize = 9;
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Deserialize,
+    Serialize,
+    Add,
+    AddAssign,
+    SubAssign,
+    Sum,
+)]
+#[serde(default)]
+pub struct Split {
+    pub batters_faced: u32,
+    pub struck_outs: u32,
+    pub walks_issued: u32,
+}
+
+impl Split {
+    pub fn struck_out_percentage(&self) -> Pct<1> {
+        Pct::new(self.struck_outs * 100, self.batters_faced)
+    }
+
+    pub fn walk_percentage(&self) -> Pct<1> {
+        Pct::new(self.walks_issued * 100, self.batters_faced)
+    }
+
+    pub fn struck_outs_walks_ratio(&self) -> Pct<2> {
+        Pct::new(self.struck_outs, self.walks_issued)
+    }
+}
+
+/// Pitching splits by the lineup slot of the batter faced.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Splits(pub [Split; SLOTS]);
+
+impl Splits {
+    pub fn slot_mut(&mut self, slot: usize) -> &mut Split {
+        &mut self.0[slot.min(SLOTS - 1)]
+    }
+}
+
+impl std::ops::Add for Splits {
+    type Output = Splits;
+
+    fn add(mut self, rhs: Splits) -> Splits {
+        self += rhs;
+        self
+    }
+}
+
+impl std::ops::AddAssign for Splits {
+    fn add_assign(&mut self, rhs: Splits) {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a += b;
+        }
+    }
+}
+
+impl std::ops::SubAssign for Splits {
+    fn sub_assign(&mut self, rhs: Splits) {
+        for (a, b) in self.0.iter_mut().zip(rhs.0) {
+            *a -= b;
+        }
+    }
+}
+
+impl std::iter::Sum for Splits {
+    fn sum<I: Iterator<Item = Splits>>(iter: I) -> Splits {
+        iter.fold(Splits::default(), |a, b| a + b)
+    }
+}
+
+pub fn table(splits: Splits) -> Table<7> {
+    let mut table = Table::new(
+        [
+            ("Lineup Slot", "Slot"),
+            ("Batters Faced", "BF"),
+            ("Strikeouts", "SO"),
+            ("Bases on Balls (Walks)", "BB"),
+            ("Strikeout Percentage", "SO%"),
+            ("Walk Percentage", "BB%"),
+            ("Strikeout-to-Walk Ratio", "SO/BB"),
+        ],
+        "text-right",
+        "number",
+    );
+    for (i, split) in splits.0.iter().enumerate() {
+        if split.batters_faced == 0 {
+            continue;
+        }
+        table.push(row![
+            if i == SLOTS - 1 {
+                format!("{}+", i + 1)
+            } else {
+                (i + 1).to_string()
+            },
+            split.batters_faced,
+            split.struck_outs,
+            split.walks_issued,
+            split.struck_out_percentage(),
+            split.walk_percentage(),
+            split.struck_outs_walks_ratio(),
+        ]);
+    }
+    table
+}
+
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+#[derive(Debug)]
+pub struct DaySplit {
+    pub season: Season,
+    /// Zero-indexed regular season days, inclusive
+    pub days: RangeInclusive<u16>,
+    pub stats: Stats,
+}
+
+impl DaySplit {
+    pub fn days_label(&self) -> String {
+        format!(
+            "Days {}\u{2013}{}",
+            self.days.start() + 1,
+            self.days.end() + 1
+        )
+    }
+}
+
+/// Divides `days` days into `segments` contiguous ranges of (nearly) equal length.
+pub fn day_ranges(days: u16, segments: u16) -> Vec<RangeInclusive<u16>> {
+    let days = u32::from(days);
+    let segments = u32::from(segments).clamp(1, days.max(1));
+    (0..segments)
+        .map(|i| {
+            let start = days * i / segments;
+            let end = (days * (i + 1) / segments).saturating_sub(1);
+            (start as u16)..=(end as u16)
+        })
+        .collect()
+}
+
+/// Splits a player's regular season stats into `segments` day ranges per season, using the box
+/// scores of each game played by their teams. Seasons are divided by the number of days their
+/// teams actually played, so eras with shorter seasons get proportionally shorter ranges.
+pub fn day_splits(player_id: Uuid, segments: u16) -> Result<Vec<DaySplit>> {
+    let mut seasons: BTreeMap<Season, Vec<Uuid>> = BTreeMap::new();
+    for row in summary::player_summary(player_id)? {
+        if !row.is_postseason && !row.is_special {
+            seasons.entry(row.season).or_default().push(row.team_id);
+        }
+    }
+
+    let mut splits = Vec::new();
+    for (season, teams) in seasons {
+        let mut seen = HashSet::new();
+        let mut games = Vec::new();
+        for team in teams {
+            for (_, entry) in schedule::schedule(team, &season)? {
+                if entry.kind == Kind::Regular && seen.insert(entry.id) {
+                    games.push((entry.id, entry.day));
+                }
+            }
+        }
+
+        let days = games
+            .iter()
+            .map(|(_, day)| day + 1)
+            .max()
+            .unwrap_or_default();
+        let mut season_splits = day_ranges(days, segments)
+            .into_iter()
+            .map(|days| DaySplit {
+                season: season.clone(),
+                days,
+                stats: Stats::default(),
+            })
+            .collect::<Vec<_>>();
+        for (id, day) in games {
+            let game = match game::load(id)? {
+                Some(game) => game,
+                None => continue,
+            };
+            let stats = match game.teams().find_map(|team| team.stats.get(&player_id)) {
+                Some(stats) => *stats,
+                None => continue,
+            };
+            if let Some(split) = season_splits
+                .iter_mut()
+                .find(|split| split.days.contains(&day))
+            {
+                split.stats += stats;
+            }
+        }
+        splits.extend(season_splits);
+    }
+    Ok(splits)
+}
+
+#[cfg(test)]
+#[test]
+fn test_day_ranges() {
+    assert_eq!(day_ranges(99, 3), [0..=32, 33..=65, 66..=98]);
+    assert_eq!(day_ranges(90, 4), [0..=21, 22..=44, 45..=66, 67..=89]);
+    assert_eq!(day_ranges(2, 3), [0..=0, 1..=1]);
+    assert_eq!(day_ranges(0, 3), [0..=0]);
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    let mut a = Splits::default();
+    a.slot_mut(0).batters_faced += 1;
+    a.slot_mut(12).struck_outs += 1;
+    assert_eq!(a.0[SLOTS - 1].struck_outs, 1);
+
+    let mut b = a + a;
+    assert_eq!(b.0[0].batters_faced, 2);
+    b -= a;
+    assert_eq!(b, a);
+    assert_eq!([a, a].into_iter().sum::<Splits>(), a + a);
+}
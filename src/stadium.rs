@@ -0,0 +1,15 @@
+use crate::chronicler;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+pub async fn load(id: Uuid, at: DateTime<Utc>) -> Result<Option<Stadium>> {
+    chronicler::load("stadium", id, at).await
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stadium {
+    pub name: String,
+}
@@ -0,0 +1,175 @@
+//! Player star ratings as of the end of each season they played in. Ratings are computed from the
+//! player's attributes in Chronicler, loaded as of their last game that season, using the same
+//! formulas as the star ratings on player cards.
+
+use crate::percentage::Pct;
+use crate::seasons::Season;
+use crate::summary::{self, Daily};
+use crate::{chronicler, fraction::Fraction, schedule, DB};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::mem::size_of_val;
+use uuid::Uuid;
+
+pub const TREE: &str = "player_stars_v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Stars {
+    pub batting: f64,
+    pub pitching: f64,
+    pub baserunning: f64,
+    pub defense: f64,
+}
+
+impl Stars {
+    fn new(player: &Player) -> Stars {
+        let p = player;
+        Stars {
+            batting: stars(
+                (1.0 - p.tragicness).powf(0.01)
+                    * (1.0 - p.patheticism).powf(0.05)
+                    * (p.thwackability * p.divinity).powf(0.35)
+                    * (p.moxie * p.musclitude).powf(0.075)
+                    * p.martyrdom.powf(0.02),
+            ),
+            pitching: stars(
+                p.unthwackability.powf(0.5)
+                    * p.ruthlessness.powf(0.4)
+                    * p.overpowerment.powf(0.15)
+                    * p.shakespearianism.powf(0.1)
+                    * p.coldness.powf(0.025),
+            ),
+            baserunning: stars(
+                p.laserlikeness.powf(0.5)
+                    * (p.base_thirst * p.continuation * p.ground_friction * p.indulgence).powf(0.1),
+            ),
+            defense: stars(
+                (p.omniscience * p.tenaciousness).powf(0.2)
+                    * (p.watchfulness * p.anticapitalism * p.chasiness).powf(0.1),
+            ),
+        }
+    }
+}
+
+/// Ratings are shown in half stars, rounded down.
+fn stars(rating: f64) -> f64 {
+    (rating * 10.0).floor() / 2.0
+}
+
+pub fn to_pct(stars: f64) -> Pct<1> {
+    Pct(Fraction::new((stars * 10.0).round() as i64, 10))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Player {
+    tragicness: f64,
+    patheticism: f64,
+    thwackability: f64,
+    divinity: f64,
+    moxie: f64,
+    musclitude: f64,
+    martyrdom: f64,
+    unthwackability: f64,
+    ruthlessness: f64,
+    overpowerment: f64,
+    shakespearianism: f64,
+    coldness: f64,
+    laserlikeness: f64,
+    base_thirst: f64,
+    continuation: f64,
+    ground_friction: f64,
+    indulgence: f64,
+    omniscience: f64,
+    tenaciousness: f64,
+    watchfulness: f64,
+    anticapitalism: f64,
+    chasiness: f64,
+}
+
+/// Returns the player's ratings at the end of each season they played in, in season order.
+/// Seasons are loaded from Chronicler the first time they're asked for, then cached (including
+/// when Chronicler has no data for the player). A failed load is logged and skipped.
+pub async fn player_stars(player_id: Uuid) -> Result<Vec<(Season, Stars)>> {
+    let tree = DB.open_tree(TREE)?;
+    let daily = summary::player_daily(player_id)?;
+    let mut v = Vec::new();
+    for (i, last) in daily.iter().enumerate() {
+        // Only the player's last row of each season matters.
+        if daily
+            .get(i + 1)
+            .map_or(false, |next| next.season == last.season)
+        {
+            continue;
+        }
+        let key = build_key(player_id, &last.season);
+        let stars = match tree.get(&key)? {
+            Some(value) => serde_json::from_slice::<Option<Stars>>(&value)?,
+            None => {
+                let at = match season_end(last)? {
+                    Some(at) => at,
+                    None => continue,
+                };
+                match chronicler::load::<Player>("player", player_id, at).await {
+                    Ok(player) => {
+                        let stars = player.as_ref().map(Stars::new);
+                        tree.insert(key, serde_json::to_vec(&stars)?)?;
+                        stars
+                    }
+                    Err(err) => {
+                        log::warn!(
+                            "failed to load player {} as of {}: {:#}",
+                            player_id,
+                            at,
+                            err
+                        );
+                        continue;
+                    }
+                }
+            }
+        };
+        if let Some(stars) = stars {
+            v.push((last.season.clone(), stars));
+        }
+    }
+    Ok(v)
+}
+
+/// When the player's last game of the season ended.
+fn season_end(last: &Daily) -> Result<Option<DateTime<Utc>>> {
+    Ok(schedule::schedule(last.team_id, &last.season)?
+        .into_iter()
+        .find(|(_, entry)| entry.id == last.game_id)
+        .and_then(|(_, entry)| entry.end))
+}
+
+fn build_key(player_id: Uuid, season: &Season) -> Vec<u8> {
+    let mut key = Vec::with_capacity(
+        size_of_val(&player_id) + size_of_val(&season.season) + season.sim.len(),
+    );
+    key.extend_from_slice(player_id.as_bytes());
+    key.extend_from_slice(&season.season.to_ne_bytes());
+    key.extend_from_slice(season.sim.as_bytes());
+    key
+}
+
+#[cfg(test)]
+#[test]
+fn test_stars() {
+    let player = Player {
+        tragicness: 0.1,
+        patheticism: 0.5,
+        thwackability: 0.5,
+        divinity: 0.5,
+        moxie: 0.5,
+        musclitude: 0.5,
+        martyrdom: 0.5,
+        ..Player::default()
+    };
+    let stars = Stars::new(&player);
+    // 0.9^0.01 * 0.5^0.05 * 0.25^0.35 * 0.25^0.075 * 0.5^0.02 = 0.528
+    assert_eq!(stars.batting, 2.5);
+    assert_eq!(stars.pitching, 0.0);
+    assert_eq!(to_pct(stars.batting).to_string(), "2.5");
+}
@@ -1,14 +1,76 @@
-use crate::feed::{ExtraData, GameEvent};
+use crate::event_type::EventType;
+use crate::feed::GameEvent;
+use crate::fielding::Credit;
 use crate::game::{Game, Kind, Stats, Team};
-use crate::{seasons::Season, team};
+use crate::highlights::{self, Highlight};
+use crate::matchup::{Matchup, MatchupStats};
+use crate::milestones;
+use crate::run_expectancy::{self, RunExpectancy};
+use crate::seasons::Season;
+use crate::splits::Split;
+use crate::win_probability::{self, Situation, WinProbability};
 use anyhow::{anyhow, bail, ensure, Context, Result};
-use chrono::Duration;
 use itertools::Itertools;
+use rocket::futures::future::BoxFuture;
 use serde::Serialize;
 use std::cmp::Ordering;
 use std::collections::HashSet;
 use uuid::Uuid;
 
+macro_rules! checkdesc {
+    ($desc:expr, $expr:expr) => {
+        anyhow::ensure!($expr, "unexpected event description: {:?}", $desc)
+    };
+}
+
+mod baserunning;
+mod game_flow;
+mod hits;
+mod modifications;
+mod outs;
+mod pitches;
+mod roster;
+
+/// How an event type is processed. Each event family module claims the types it handles.
+#[derive(Clone, Copy)]
+enum Handler {
+    /// Known, but nothing to record
+    Ignore,
+    Sync(fn(&mut State, &GameEvent) -> Result<()>),
+    Async(for<'a> fn(&'a mut State, &'a GameEvent) -> BoxFuture<'a, Result<()>>),
+}
+
+const FAMILIES: &[fn(EventType) -> Option<Handler>] = &[
+    game_flow::handler,
+    pitches::handler,
+    hits::handler,
+    outs::handler,
+    baserunning::handler,
+    roster::handler,
+    modifications::handler,
+];
+
+fn handler(ty: EventType) -> Option<Handler> {
+    FAMILIES.iter().find_map(|family| family(ty))
+}
+
+#[cfg(test)]
+#[test]
+fn test_handler_families_disjoint() {
+    for ty in 0..=u16::MAX {
+        let claimed = FAMILIES
+            .iter()
+            .filter(|family| family(EventType::from_raw(ty)).is_some())
+            .count();
+        assert!(
+            claimed <= 1,
+            "event type {} claimed by {} families",
+            ty,
+            claimed
+        );
+    }
+}
+
 type PitcherData = (u128, [(u128, &'static str); 2]);
 
 const HARDCODED_PITCHERS: &[PitcherData] = &[
@@ -51,21 +113,41 @@ pub struct State {
     #[serde(skip)]
     last_runs_cmp: Ordering,
     half_inning_outs: u16,
+    /// outs the defense would have recorded this half-inning if not for errors
+    error_outs: u16,
     at_bat: Option<Uuid>,
     last_fielded_out: Option<Uuid>,
     rbi_credit: Option<Uuid>,
     save_situation: [Option<SaveSituation>; 2],
     on_base: Vec<Runner>,
+    /// batter, base-out state, and runs scored before each plate appearance this half-inning
+    half_inning_plate_appearances: Vec<(Uuid, usize, u16)>,
+    /// fielders named in event descriptions, which are credited by name once the game is over
+    /// (the defense's names aren't known until they've batted)
+    fielded: Vec<Fielded>,
+    /// lineup players who left their team's active roster during the game; they keep their lineup
+    /// slots in the box score, but no longer bat (see `roster_move`)
+    departed: HashSet<Uuid>,
     #[serde(skip)]
     on_base_start_of_play: Vec<Runner>,
     #[serde(skip)]
     expected: (u16, u16),
     #[serde(skip)]
     mods: HashSet<(Uuid, &'static str)>,
+    /// The season's run expectancy matrix, for win probability
+    #[serde(skip)]
+    run_expectancy: [f64; run_expectancy::STATES],
 }
 
 impl State {
     pub fn new(season: Season, id: Uuid) -> State {
+        let league = match crate::summary::league_totals(&season) {
+            Ok(league) => league.run_expectancy,
+            Err(err) => {
+                log::warn!("no league totals for {}: {:#}", id, err);
+                RunExpectancy::default()
+            }
+        };
         let mut game = Game {
             season,
             ..Default::default()
@@ -97,20 +179,26 @@ impl State {
             top_of_inning: true,
             last_runs_cmp: Ordering::Equal,
             half_inning_outs: 0,
+            error_outs: 0,
             at_bat: None,
             last_fielded_out: None,
             rbi_credit: None,
             save_situation: [None; 2],
             on_base: Vec::new(),
+            half_inning_plate_appearances: Vec::new(),
+            fielded: Vec::new(),
+            departed: HashSet::new(),
             on_base_start_of_play: Vec::new(),
             expected: (0, 0),
             mods: HashSet::new(),
+            run_expectancy: win_probability::matrix(&league),
         }
     }
 
-    pub fn finish(self) -> Result<Game> {
+    pub fn finish(mut self) -> Result<Game> {
         ensure!(self.game_finished, "game incomplete");
         self.ensure_pitchers_known()?;
+        self.credit_fielders();
         let mut game = self.game;
         ensure!(game.away.won ^ game.home.won, "winner mismatch");
 
@@ -208,6 +296,21 @@ impl State {
                 !team.player_names.contains_key(&Uuid::default()),
                 "placeholder pitcher ID present in player names"
             );
+            let starters = team
+                .lineup
+                .iter()
+                .filter_map(|position| position.first())
+                .copied()
+                .collect::<HashSet<_>>();
+            for player in team
+                .lineup
+                .iter()
+                .flat_map(|position| position.iter().skip(1))
+            {
+                if !starters.contains(player) {
+                    team.stats.entry(*player).or_default().games_as_substitute = 1;
+                }
+            }
             for stats in team.stats.values_mut() {
                 if stats.is_batting() {
                     stats.games_batted += 1;
@@ -218,9 +321,42 @@ impl State {
             }
         }
 
+        game.milestones = milestones::detect(&game);
+        highlights::finish(&mut game);
         Ok(game)
     }
 
+    pub fn is_finished(&self) -> bool {
+        self.game_finished
+    }
+
+    /// Returns the game as processed so far, without any of the checks or end-of-game
+    /// bookkeeping in `finish`. Used for games in progress.
+    pub fn into_partial(mut self) -> Game {
+        self.credit_fielders();
+        self.game
+    }
+
+    fn credit_fielders(&mut self) {
+        for fielded in self.fielded.drain(..) {
+            let team = if fielded.home_defense {
+                &mut self.game.home
+            } else {
+                &mut self.game.away
+            };
+            let fielder = team
+                .player_names
+                .iter()
+                .find(|(_, name)| **name == fielded.fielder)
+                .map(|(id, _)| *id);
+            if let Some(fielder) = fielder {
+                fielded
+                    .credit
+                    .apply(&mut team.stats.entry(fielder).or_default().fielding);
+            }
+        }
+    }
+
     fn ensure_pitchers_known(&self) -> Result<()> {
         ensure!(
             self.game
@@ -243,9 +379,116 @@ impl State {
     }
 
     pub async fn push(&mut self, event: &GameEvent) -> Result<()> {
+        let plate_appearance = self.plate_appearance_start();
         self.push_inner(event)
             .await
-            .with_context(|| format!("while processing event {}, type {}", event.id, event.ty))
+            .with_context(|| format!("while processing event {}, type {}", event.id, event.ty))?;
+        if let Some(start) = plate_appearance {
+            self.record_run_expectancy(start);
+        }
+        self.record_win_probability();
+        self.record_highlight(event);
+        Ok(())
+    }
+
+    /// Occupied bases, as a bitmask with bit 0 for first base. Fourth base and beyond are ignored.
+    fn bases(&self) -> u8 {
+        self.on_base
+            .iter()
+            .filter(|runner| runner.base < 3)
+            .fold(0, |bases, runner| bases | 1 << runner.base)
+    }
+
+    fn plate_appearance_start(&self) -> Option<PlateAppearanceStart> {
+        let batter = self.at_bat?;
+        Some(PlateAppearanceStart {
+            batter,
+            pitcher: self.pitcher(),
+            top_of_inning: self.top_of_inning,
+            state: run_expectancy::state(self.half_inning_outs, self.bases())?,
+            runs: self.offense().runs(),
+            plate_appearances: self.offense().stats.get(&batter)?.plate_appearances,
+        })
+    }
+
+    /// If the event ended the plate appearance that `start` describes, credits the batter and
+    /// pitcher with its change in base-out state (see `run_expectancy`). Once the half-inning is
+    /// over, the batters are also credited with the runs scored after each plate appearance.
+    fn record_run_expectancy(&mut self, start: PlateAppearanceStart) {
+        if self.top_of_inning != start.top_of_inning
+            || self
+                .offense()
+                .stats
+                .get(&start.batter)
+                .map_or(0, |s| s.plate_appearances)
+                <= start.plate_appearances
+        {
+            return;
+        }
+
+        let end = run_expectancy::state(self.half_inning_outs, self.bases());
+        let runs = i32::from(self.offense().runs()) - i32::from(start.runs);
+        let batting = &mut self.offense_stats(start.batter).run_expectancy;
+        batting.batting[start.state] -= 1;
+        if let Some(end) = end {
+            batting.batting[end] += 1;
+        }
+        batting.batting_runs += runs;
+        let pitching = &mut self.defense_stats(start.pitcher).run_expectancy;
+        pitching.pitching[start.state] -= 1;
+        if let Some(end) = end {
+            pitching.pitching[end] += 1;
+        }
+        pitching.pitching_runs += runs;
+
+        self.half_inning_plate_appearances
+            .push((start.batter, start.state, start.runs));
+        if end.is_none() {
+            let total = self.offense().runs();
+            for (batter, state, runs) in std::mem::take(&mut self.half_inning_plate_appearances) {
+                let value = &mut self.offense_stats(batter).run_expectancy;
+                value.occurrences[state] += 1;
+                value.runs_after[state] += i32::from(total) - i32::from(runs);
+            }
+        }
+    }
+
+    fn record_win_probability(&mut self) {
+        let point = if self.game_finished {
+            WinProbability {
+                inning: self.inning,
+                top_of_inning: self.top_of_inning,
+                home: if self.game.home.won { 1000 } else { 0 },
+            }
+        } else {
+            Situation {
+                inning: self.inning,
+                top_of_inning: self.top_of_inning,
+                outs: self.half_inning_outs,
+                bases: self.bases(),
+                away_runs: self.game.away.score(),
+                home_runs: self.game.home.score(),
+                run_expectancy: &self.run_expectancy,
+            }
+            .point()
+        };
+        let series = &mut self.game.win_probability;
+        if series.last().map(|last| last.home) != Some(point.home) {
+            series.push(point);
+        }
+    }
+
+    fn record_highlight(&mut self, event: &GameEvent) {
+        if let Some(kind) = highlights::Kind::from_event(event.ty) {
+            self.game.highlights.push(Highlight {
+                inning: self.inning,
+                top_of_inning: self.top_of_inning,
+                kind,
+                description: event.description.lines().collect::<Vec<_>>().join(" "),
+                away_runs: self.game.away.runs(),
+                home_runs: self.game.home.runs(),
+            });
+        }
     }
 
     async fn push_inner(&mut self, event: &GameEvent) -> Result<()> {
@@ -259,10 +502,15 @@ impl State {
         self.expected = event.expect(self.expected)?;
 
         if self.is_game_over() {
-            if event.ty == 107 && event.metadata.r#mod.as_deref() == Some("INHABITING") {
+            if event.ty == EventType::RemovedMod
+                && event.metadata.r#mod.as_deref() == Some("INHABITING")
+            {
                 // sometimes this happens!
             } else {
-                ensure!(self.game_finished || event.ty == 11, "game over mismatch");
+                ensure!(
+                    self.game_finished || event.ty == EventType::GameEnd,
+                    "game over mismatch"
+                );
             }
         }
 
@@ -294,397 +542,15 @@ impl State {
             }
         }
 
-        if event.is_title_match == Some(true) && self.game.season.sim == "gamma10" {
+        if event.is_title_match == Some(true) {
             self.game.kind = Kind::Special;
         }
 
-        let desc = &event.description;
-        macro_rules! checkdesc {
-            ($expr:expr) => {
-                ensure!($expr, "unexpected event description: {:?}", desc)
-            };
-        }
-
-        match event.ty {
-            0 => self.start_event(event).await?,
-            1 => {} // Play ball!
-            2 => self.next_half_inning()?,
-            3 => {
-                // Pitcher change
-                if let Some((name, _)) = desc.rsplit_once(" is now pitching for the ") {
-                    ensure!(event.player_tags.len() == 1, "invalid player tag count");
-
-                    if let Err(err) = self.ensure_pitchers_known() {
-                        // Starting in gamma9, the sim does not correctly set the pitcher before
-                        // the start of a tournament's first game, leading to a pitcher change
-                        // event at the start of each half of the first inning because the sim is
-                        // changing the pitcher from "null" to whoever the starting pitcher is. If
-                        // it's the first inning and the starting pitcher is a placeholder who has
-                        // not yet thrown the ball, we should ignore this error.
-                        ensure!(
-                            self.inning == 1
-                                && self.defense().pitchers.len() == 1
-                                && self.defense_stats(self.pitcher())
-                                    == &Stats {
-                                        games_started: 1,
-                                        ..Default::default()
-                                    },
-                            err
-                        );
-
-                        // If we still have placeholder pitcher data for the defense, fill in the
-                        // data we now have so that the pitcher change branch below doesn't run.
-                        self.defense_mut()
-                            .replace_placeholder_pitcher(event.player_tags[0], name);
-                    }
-
-                    if self.pitcher() != event.player_tags[0] {
-                        // starting pitchers must pitch 5 innings to be credited for the win. clear
-                        // the pitcher of record if they are not eligible to record the win; if the
-                        // losing team's pitcher is still cleared by the end of the game, fill it
-                        // back in with the starting pitcher for the losing team.
-                        let old_pitcher = self.pitcher();
-                        if self.defense().pitchers.len() == 1
-                            && self.defense_stats(old_pitcher).outs_recorded < 15
-                        {
-                            self.defense_mut().pitcher_of_record = Uuid::default();
-                        }
-
-                        self.defense_mut().pitchers.push(event.player_tags[0]);
-                        self.defense_mut()
-                            .player_names
-                            .insert(event.player_tags[0], name.into());
-
-                        let offense_runs = self.offense().runs();
-                        let defense_runs = self.defense().runs();
-                        let save = &mut self.save_situation[if self.top_of_inning { 1 } else { 0 }];
-                        *save = if defense_runs > offense_runs {
-                            if offense_runs + 1 >= defense_runs
-                                || offense_runs + u16::try_from(self.on_base.len())? >= defense_runs
-                            {
-                                // potential tying run on base or at bat
-                                // NOTE: on deck handled via `check_save_situation`
-                                Some(SaveSituation::TyingRun)
-                            } else if defense_runs - offense_runs <= 3 {
-                                Some(SaveSituation::LeadThreeOrLess)
-                            } else {
-                                None
-                            }
-                        } else {
-                            None
-                        };
-                    }
-                } else {
-                    checkdesc!(false);
-                }
-            }
-            4 => {
-                // Stolen base
-                if let Some((name, _)) = desc.rsplit_once(" gets caught stealing ") {
-                    checkdesc!(desc.ends_with(" base."));
-                    let runner = self.name_lookup(name, event.player_tags.get(0).copied())?;
-                    self.record_runner_event(runner, |s| &mut s.caught_stealing)?;
-                    self.half_inning_outs += 1;
-                    self.record_pitcher_event(|s| &mut s.outs_recorded)?;
-                    self.remove_runner(runner)?
-                        .context("runner caught stealing wasn't on base?")?;
-                } else if let Some((name, _)) = desc.rsplit_once(" steals ") {
-                    checkdesc!(desc.ends_with(" base!"));
-                    let runner = self.name_lookup(name, event.player_tags.get(0).copied())?;
-                    self.record_runner_event(runner, |s| &mut s.stolen_bases)?;
-                    if desc.ends_with("steals fourth base!") {
-                        self.credit_run(runner)?;
-                    }
-                } else {
-                    checkdesc!(false);
-                }
-            }
-            5 => checkdesc!(self.walk(event)?),
-            6 => {
-                // Strikeout
-                checkdesc!(desc.contains("strikes out"));
-                self.record_batter_event(|s| &mut s.strike_outs)?;
-                self.record_pitcher_event(|s| &mut s.struck_outs)?;
-                self.batter_out()?;
-            }
-            7 | 8 => {
-                // Flyout or ground out
-                if desc.ends_with("reaches on fielder's choice.") {
-                    // nothing, we already handled this in the "out at" branch
-                } else {
-                    checkdesc!(
-                        desc.contains("hit a flyout to")
-                            || desc.contains("hit a ground out to")
-                            || desc.contains("out at")
-                            || desc.ends_with("hit into a double play!")
-                    );
-                    self.fielded_out(event)?;
-                }
-            }
-            9 => checkdesc!(self.home_run(event)?),
-            10 => {
-                if desc.ends_with("advances on the sacrifice.")
-                    || desc.ends_with("tags up and scores!")
-                {
-                    self.sac(event)?;
-                } else {
-                    checkdesc!(self.hit(event)?);
-                }
-            }
-            11 => {
-                self.game_finished = true;
-                let winner = event.metadata.winner.context("missing winner data")?;
-                for team in self.game.teams_mut() {
-                    if team.id == winner {
-                        team.won = true;
-                    }
-                }
-                for team in self.game.teams_mut() {
-                    let stats = team
-                        .stats
-                        .entry(*team.pitchers.last().unwrap())
-                        .or_default();
-                    stats.games_finished = 1;
-                    if stats.games_started > 0 {
-                        stats.complete_games = 1;
-                        if stats.earned_runs == 0 {
-                            stats.shutouts = 1;
-                            if stats.hits_allowed == 0 {
-                                stats.no_hitters = 1;
-                                if stats.walks_issued == 0 {
-                                    stats.perfect_games = 1;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            12 => {
-                // Start of plate appearance
-                if event.player_tags.len() == 2
-                    && desc.contains("is Inhabiting")
-                    && !desc.contains("batting for the")
-                {
-                    let position = self
-                        .offense_mut()
-                        .positions_mut()
-                        .find(|position| position.last() == Some(&event.player_tags[1]))
-                        .context("unable to find position for inhabited player")?;
-                    position.push(event.player_tags[0]);
-                    position.push(event.player_tags[1]);
-                } else {
-                    ensure!(
-                        event.player_tags.len() == 1 || event.player_tags.len() == 2,
-                        "invalid player tag count"
-                    );
-                    if let Some((name, _)) = desc.rsplit_once(" batting for the ") {
-                        self.offense_mut()
-                            .player_names
-                            .insert(event.player_tags[0], name.into());
-                        self.at_bat = Some(event.player_tags[0]);
-                    } else {
-                        checkdesc!(false);
-                    }
-                }
-            }
-            13 => {
-                // Strike
-                checkdesc!(
-                    desc.starts_with("Strike, looking.")
-                        || desc.starts_with("Strike, swinging.")
-                        || desc.starts_with("Strike, flinching.")
-                        || desc.starts_with("Strikes, looking.")
-                        || desc.starts_with("Strikes, swinging.")
-                );
-                self.record_pitcher_event(|s| &mut s.strikes_pitched)?;
-            }
-            14 => {
-                // Ball
-                checkdesc!(desc.starts_with("Ball."));
-                self.record_pitcher_event(|s| &mut s.balls_pitched)?;
-            }
-            15 => {
-                // Foul Ball
-                checkdesc!(desc.starts_with("Foul Ball.") || desc.starts_with("Foul Balls."));
-                self.record_pitcher_event(|s| &mut s.strikes_pitched)?;
-            }
-            20 => {} // Shame!
-            23 => {} // player skipped (Elsewhere or Shelled)
-            24 => {} // partying
-            28 => {} // end of inning
-            41 => {
-                if desc.ends_with("switch teams in the feedback!") {
-                    ensure!(event.player_tags.len() == 2, "invalid team tag count");
-                    self.player_trade(event.player_tags[0], event.player_tags[1])?;
-                }
-            }
-            46 => {} // yummy peanut reaction
-            47 => {} // allergic peanut reaction
-            54 => {} // incineration
-            62 => {
-                // Flooding: baserunners swept
-                checkdesc!(desc == "A surge of Immateria rushes up from Under!\nBaserunners are swept from play!");
-                self.on_base.clear();
-            }
-            73 => {} // peanut flavor text
-            84 => {} // player returned from Elsewhere
-            106 | 107 | 146 | 147 => {
-                // modification added or removed
-                match event.metadata.r#mod.as_deref() {
-                    Some("FROZEN") => {
-                        if let Some(name) = desc.strip_suffix(" was Frozen!") {
-                            // we only care about FROZEN for calculating CRiSP, which requires
-                            // that they're on base. if we can't look up their name, they can't
-                            // be on base.
-                            if let Ok(player) =
-                                self.name_lookup(name, event.player_tags.get(0).copied())
-                            {
-                                if event.ty & 1 == 0 {
-                                    self.mods.insert((player, "FROZEN"));
-                                } else {
-                                    self.mods.remove(&(player, "FROZEN"));
-                                }
-                            }
-                        }
-                    }
-                    Some(_) => {}
-                    None => bail!("missing modification data"),
-                }
-            }
-            113 => {
-                // Trade (e.g. Feedback swap)
-                // for feedback, handled in 41
-                checkdesc!(desc.ends_with("were swapped in Feedback."));
-            }
-            114 => {
-                // Swap within team
-                checkdesc!(
-                    desc.ends_with("swapped two players on their roster.")
-                        || desc.ends_with("had several players shuffled in the Reverb!")
-                );
-                let swap = match &event.metadata.extra {
-                    Some(ExtraData::Swap(swap)) => swap,
-                    _ => bail!("missing player swap data"),
-                };
-                self.player_trade(swap.a_player_id, swap.b_player_id)?;
-                // yolo
-                for (player, player_name) in [
-                    (swap.a_player_id, &swap.a_player_name),
-                    (swap.b_player_id, &swap.b_player_name),
-                ] {
-                    for team in self.game.teams_mut() {
-                        team.player_names.insert(player, player_name.clone());
-                    }
-                }
-            }
-            116 => {
-                // Incineration
-                if desc.contains("replaced the incinerated") {
-                    self.ensure_pitchers_known()?;
-                    let replacement = match &event.metadata.extra {
-                        Some(ExtraData::Incineration(replacement)) => replacement,
-                        _ => bail!("missing incineration replacement data"),
-                    };
-                    for team in self.game.teams_mut() {
-                        if team.id == replacement.team_id {
-                            team.player_names.insert(
-                                replacement.in_player_id,
-                                replacement.in_player_name.clone(),
-                            );
-                            for position in team.positions_mut() {
-                                if position.last() == Some(&replacement.out_player_id) {
-                                    position.push(replacement.in_player_id);
-                                }
-                            }
-                        }
-                    }
-                } else if desc.starts_with("They're replaced by") {
-                    // nothing, redundant event
-                } else {
-                    checkdesc!(false);
-                }
-            }
-            117 => {} // player stat increase
-            118 => {} // player stat decrease
-            119 => {} // player stat reroll
-            125 => {} // player entered Hall of Flame
-            130 | 131 => {
-                // Reverb. 130 is a full-team shuffle, and 131 is a lineup shuffle. Players are not
-                // swapped, but instead the order is shuffled, so there's no information in the
-                // feed about the new lineup order.
-                //
-                // To get the new roster order, we ask Chronicler for the team information one
-                // minute after this feed event, since it fetches team data once per minute during
-                // games.
-                //
-                // This only sets the new lineup order. Pitching changes (if any) are handled via
-                // type 3.
-
-                // The feed doesn't specify which team got Reverbed, so we need to scan the event
-                // description for the team's nickname.
-                let team = self
-                    .game
-                    .teams_mut()
-                    .find(|team| desc.contains(&team.name.nickname))
-                    .context("could not identify reverbed team")?;
-                let data = team::load(team.id, event.created + Duration::minutes(1))
-                    .await?
-                    .context("no data for team")?;
-                ensure!(
-                    team.lineup.len() == data.lineup.len(),
-                    "lineup size mismatch"
-                );
-                for (position, player) in team.lineup.iter_mut().zip(data.lineup) {
-                    if position.last() != Some(&player) {
-                        position.push(player);
-                    }
-                }
-            }
-            132 => {
-                checkdesc!(desc.ends_with("had their rotation shuffled in the Reverb!"));
-                // do nothing, because type 3 will follow
-            }
-            137 => {} // player hatched
-            193 => {
-                // prize match
-                if self.game.season.sim == "gamma10" {
-                    self.game.kind = Kind::Special;
-                }
-            }
-            209 => {
-                // score update
-                let score = match &event.metadata.extra {
-                    Some(ExtraData::Score(data)) => data,
-                    _ => bail!("missing score data"),
-                };
-                for (team, score) in self
-                    .game
-                    .teams()
-                    .zip([&score.away_score, &score.home_score])
-                {
-                    ensure!(
-                        u64::from(team.runs())
-                            == score.as_u64().context("score is not unsigned integer")?,
-                        "score mismatch"
-                    );
-                }
-            }
-            214 => {} // collected a Win
-            215 => {} // collected a Win (postseason, sometimes)
-            216 => {} // game over
-            223 => {} // weather is happening
-            252 => {} // Night Shift (handled in type 114)
-            261 => {
-                // Double strike
-                checkdesc!(desc.ends_with("fires a Double Strike!"));
-                // only record one extra strike; the next event catches the other
-                self.record_pitcher_event(|s| &mut s.strikes_pitched)?;
-            }
-            262 => {} // electricity zaps a strike away
-            263 => {} // WINTER STORM WARNING
-            264 => {} // snowflakes modify the field
-            265 => {} // player is Unfreezable
-            _ => bail!("unexpected event type {}", event.ty),
+        match handler(event.ty) {
+            Some(Handler::Ignore) => {}
+            Some(Handler::Sync(handle)) => handle(self, event)?,
+            Some(Handler::Async(handle)) => handle(self, event).await?,
+            None => bail!("unexpected event type {}", event.ty),
         }
 
         if usize::from(event.metadata.sub_play) == event.metadata.sibling_ids.len() - 1 {
@@ -735,6 +601,11 @@ impl State {
                         runner.base = base;
                     }
                     ensure!(known.is_empty(), "baserunners {:?} not known to us", known);
+
+                    let shared = shared_bases(bases_occupied);
+                    for runner in &mut self.on_base {
+                        runner.shared = shared.contains(&runner.base);
+                    }
                 }
             }
 
@@ -747,6 +618,7 @@ impl State {
                 })
                 .collect::<Vec<_>>();
             self.offense_mut().crisp.extend(crisp);
+            self.strand_runners();
 
             self.last_runs_cmp = self.runs_cmp();
             self.on_base_start_of_play = self.on_base.clone();
@@ -759,13 +631,13 @@ impl State {
         self.inning >= 9
             && !self.top_of_inning
             && self.half_inning_outs == 3
-            && self.game.away.runs() != self.game.home.runs()
+            && self.game.away.score() != self.game.home.score()
     }
 
     fn check_save_situation(&mut self) {
         if self.defense_stats(self.pitcher()).batters_faced == 1
-            && self.defense().runs() > self.offense().runs()
-            && self.offense().runs() + 1 >= self.defense().runs()
+            && self.defense().score() > self.offense().score()
+            && self.offense().score() + 1 >= self.defense().score()
             && !self.is_game_over()
         {
             // potential tying run on deck as of pitcher's start
@@ -774,56 +646,6 @@ impl State {
         }
     }
 
-    async fn start_event(&mut self, event: &GameEvent) -> Result<()> {
-        self.game.day = event.day;
-        self.game.weather = event.metadata.weather.context("missing weather")?;
-
-        self.game.kind = if self.game.season.sim == "gamma8" {
-            if self.game.day >= 99 {
-                Kind::Postseason
-            } else {
-                Kind::Regular
-            }
-        } else if self.game.season.sim == "gamma9" {
-            if self.game.day >= 166 {
-                Kind::Postseason
-            } else {
-                Kind::Regular
-            }
-        } else if self.game.season.sim == "gamma10" {
-            if self.game.day >= 112 && self.game.day <= 115 {
-                Kind::Special
-            } else if self.game.day >= 219 {
-                // probably gonna have to fix this
-                Kind::Postseason
-            } else {
-                Kind::Regular
-            }
-        } else {
-            Kind::Regular
-        };
-
-        ensure!(event.team_tags.len() == 2, "invalid team tag count");
-        for (team, id) in self.game.teams_mut().zip(event.team_tags.iter()) {
-            team.id = *id;
-        }
-
-        for team in self.game.teams_mut() {
-            let data = team::load(team.id, event.created)
-                .await?
-                .context("no data for team")?;
-            team.name.name = data.full_name;
-            team.name.nickname = data.nickname;
-            team.name.shorthand = data.shorthand;
-            team.name.emoji = data.emoji;
-            for player in data.lineup {
-                team.lineup.push(vec![player]);
-            }
-        }
-
-        Ok(())
-    }
-
     fn risp(&self) -> bool {
         self.on_base_start_of_play
             .iter()
@@ -866,136 +688,28 @@ impl State {
         }
     }
 
-    fn next_half_inning(&mut self) -> Result<()> {
-        self.offense_mut().left_on_base += self.on_base.len();
-
-        if self.game_started {
-            self.top_of_inning = !self.top_of_inning;
-            if self.top_of_inning {
-                self.inning += 1;
-            }
-        } else {
-            self.game_started = true;
-        }
-
-        let inning = self.inning;
-        self.offense_mut().inning_runs.insert(inning, 0);
-        self.half_inning_outs = 0;
-        self.on_base.clear();
-
-        Ok(())
-    }
-
-    fn sac(&mut self, event: &GameEvent) -> Result<()> {
-        ensure!(event.player_tags.len() == 1, "invalid player tag count");
-        self.credit_run(event.player_tags[0])?;
-        let batter = self
-            .last_fielded_out
-            .as_ref()
-            .copied()
-            .context("sac advance without a prior fielded out")?;
-        let risp = self.risp();
-        let stats = self.offense_stats(batter);
-        stats.sacrifices += 1;
-        stats.runs_batted_in += 1;
-        stats.at_bats -= 1;
-        if risp {
-            stats.at_bats_with_risp -= 1;
+    /// Once a half-inning's third out is made, counts the runners still on base as left there and
+    /// clears the bases. Counting at the end of the play, rather than at the start of the next
+    /// half-inning, also counts the last half-inning of the game.
+    fn strand_runners(&mut self) {
+        if self.half_inning_outs >= 3 {
+            let left_on_base = self.on_base.len();
+            self.offense_mut().left_on_base += left_on_base;
+            self.on_base.clear();
         }
-
-        Ok(())
-    }
-
-    fn fielded_out(&mut self, event: &GameEvent) -> Result<()> {
-        if let Some((out, base)) = event.description.rsplit_once(" out at ") {
-            // fielder's choice
-            self.record_pitcher_event(|s| &mut s.groundouts_pitched)?;
-            let out = *self
-                .offense()
-                .player_names
-                .iter()
-                .find(|(_, name)| name == &out)
-                .with_context(|| format!("could not determine id for baserunner {}", out))?
-                .0;
-            let base = match base {
-                "second base." => 0,
-                "third base." => 1,
-                "fourth base." => 2,
-                _ => bail!("unexpected base for fielder's choice"),
-            };
-            let pitcher = self
-                .remove_runner_base(out, base)?
-                .context("baserunner out in fielder's choice not on base")?
-                .pitcher;
-            self.on_base.push(Runner {
-                id: self.batter()?,
-                pitcher,
-                base: 0,
-            });
-            self.fix_minimum_base();
-        } else if event.description.ends_with("hit into a double play!") {
-            // double play
-            self.half_inning_outs += 1;
-            self.rbi_credit = None;
-            self.record_batter_event(|s| &mut s.double_plays_grounded_into)?;
-            self.record_pitcher_event(|s| &mut s.groundouts_pitched)?;
-            self.record_pitcher_event(|s| &mut s.outs_recorded)?;
-            if event.id.as_u128() == 0x3fdb026f97a3401385ee44f935c26f01 {
-                // missing data in Chronicler at the start of 5ffbde13-1807-4694-9d13-861c6302b384.
-                // the runner put out was Craig Faucet.
-                self.remove_runner(Uuid::from_u128(0xe34b37e1b47448ed8a657e182733996c))?;
-                self.offense_stats(self.batter()?).left_on_base += 1;
-            } else if self.on_base.len() == 1 {
-                self.on_base.clear();
-                self.offense_stats(self.batter()?).left_on_base += 1;
-            } else if self.half_inning_outs == 2 {
-                // this double play was made on one out, so it's the last play of the half-inning.
-                // at this point it doesn't matter, so just add to player / team LOB correctly and
-                // clear the baserunner list
-                self.offense_stats(self.batter()?).left_on_base += self.on_base.len();
-                self.offense_mut().left_on_base += self.on_base.len();
-                self.on_base.clear();
-            } else {
-                // uh-oh. we have multiple runners on, but the Feed doesn't tell us which one is
-                // out. we'll need to rely on the baseRunners object merged in from sachet.
-                let base_runners = event
-                    .base_runners
-                    .as_ref()
-                    .context("unable to determine runner out in double play")?;
-                let out = self
-                    .on_base
-                    .iter()
-                    // if more than one batter is removed, it's a scoring play; the other half of
-                    // the double play will be on an earlier base and thus will have been added to
-                    // this map _later_. reverse the iterator to find the latest one.
-                    .rev()
-                    .find(|runner| !base_runners.contains(&runner.id))
-                    .map(|runner| runner.id)
-                    .context("unable to determine runner out in double play")?;
-                self.remove_runner(out)?;
-                self.offense_stats(self.batter()?).left_on_base += 1;
-            }
-        } else if event.description.contains("hit a flyout to") {
-            self.record_pitcher_event(|s| &mut s.flyouts_pitched)?;
-            self.last_fielded_out = self.at_bat;
-        } else if event.description.contains("hit a ground out to") {
-            self.record_pitcher_event(|s| &mut s.groundouts_pitched)?;
-            self.last_fielded_out = self.at_bat;
-        } else {
-            unreachable!();
-        }
-
-        self.batter_out()
     }
 
     fn batter_out(&mut self) -> Result<()> {
         self.half_inning_outs += 1;
         self.offense_stats(self.batter()?).left_on_base += self.on_base.len();
         self.record_batter_event(|s| &mut s.plate_appearances)?;
+        self.record_batter_event(|s| &mut s.pitches_seen)?;
         self.record_batter_event(|s| &mut s.at_bats)?;
         if self.risp() {
             self.record_batter_event(|s| &mut s.at_bats_with_risp)?;
         }
+        self.record_pitcher_split(|s| &mut s.batters_faced)?;
+        self.record_matchup(|m| &mut m.plate_appearances)?;
         self.at_bat = None;
         self.record_pitcher_event(|s| &mut s.batters_faced)?;
         self.check_save_situation();
@@ -1011,7 +725,12 @@ impl State {
             .iter()
             .position(|r| r.id == runner)
             .context("cannot determine pitcher to charge with earned run")?;
-        let pitcher = self.on_base.remove(index).pitcher;
+        let Runner {
+            pitcher,
+            earned,
+            inherited_by,
+            ..
+        } = self.on_base.remove(index);
 
         let inning = self.inning;
         *self.offense_mut().inning_runs.entry(inning).or_default() += 1;
@@ -1019,11 +738,32 @@ impl State {
         if let Some(rbi_credit) = self.rbi_credit {
             self.record_runner_event(rbi_credit, |s| &mut s.runs_batted_in)?;
         }
-        self.defense_mut()
-            .stats
-            .entry(pitcher)
-            .or_default()
-            .earned_runs += 1;
+        // a run is unearned if the runner reached on an error, or if the inning should have
+        // already ended without the defense's errors
+        let earned = earned && self.half_inning_outs + self.error_outs < 3;
+        let stats = self.defense_mut().stats.entry(pitcher).or_default();
+        if earned {
+            stats.earned_runs += 1;
+        } else {
+            stats.unearned_runs += 1;
+        }
+        if let Some(reliever) = inherited_by {
+            self.defense_mut()
+                .stats
+                .entry(reliever)
+                .or_default()
+                .inherited_runners_scored += 1;
+        }
+
+        // a relief pitcher who entered in a save situation and allows the tying run has blown the
+        // save, and can no longer earn a save or hold.
+        let save = if self.top_of_inning { 1 } else { 0 };
+        if self.save_situation[save].is_some() && self.offense().score() == self.defense().score() {
+            self.save_situation[save] = None;
+            if self.defense().pitchers.len() > 1 {
+                self.record_pitcher_event(|s| &mut s.blown_saves)?;
+            }
+        }
 
         let runs_cmp = self.runs_cmp();
         if runs_cmp != self.last_runs_cmp && runs_cmp != Ordering::Equal {
@@ -1036,152 +776,11 @@ impl State {
     }
 
     fn runs_cmp(&self) -> Ordering {
-        self.game.away.runs().cmp(&self.game.home.runs())
-    }
-
-    fn walk(&mut self, event: &GameEvent) -> Result<bool> {
-        if event.description.ends_with("draws a walk.") {
-            self.on_base.push(Runner {
-                id: self.batter()?,
-                pitcher: self.pitcher(),
-                base: 0,
-            });
-            self.fix_minimum_base();
-            self.record_batter_event(|s| &mut s.plate_appearances)?;
-            self.record_batter_event(|s| &mut s.walks)?;
-            self.rbi_credit = self.at_bat;
-            self.at_bat = None;
-            self.record_pitcher_event(|s| &mut s.batters_faced)?;
-            self.check_save_situation();
-            self.record_pitcher_event(|s| &mut s.walks_issued)?;
-            Ok(true)
-        } else if let Some(name) = event.description.strip_suffix(" scores!") {
-            let runner = self.name_lookup(name, event.player_tags.get(1).copied())?;
-            self.credit_run(runner)?;
-            Ok(true)
-        } else {
-            Ok(false)
-        }
-    }
-
-    fn home_run(&mut self, event: &GameEvent) -> Result<bool> {
-        if !(self.hit(event)?) {
-            return Ok(false);
-        }
-
-        let was_on_base = self.on_base.clone();
-        for runner in was_on_base {
-            self.credit_run(runner.id)?;
-        }
-        self.on_base.clear();
-
-        Ok(true)
+        self.game.away.score().cmp(&self.game.home.score())
     }
 
     fn fix_minimum_base(&mut self) {
-        let mut iter = self.on_base.iter_mut().rev();
-        let mut last = match iter.next() {
-            Some(runner) => runner.base,
-            None => return,
-        };
-        for runner in iter {
-            if runner.base <= last {
-                // due to the fact that the minimum base is only used to determine RISP, and
-                // because of the 🤝 glitch, we treat only first base as exclusive
-                if last == 0 {
-                    runner.base = 1;
-                } else {
-                    runner.base = last;
-                }
-            }
-            last = runner.base;
-        }
-    }
-
-    fn hit(&mut self, event: &GameEvent) -> Result<bool> {
-        macro_rules! common {
-            ($base:expr) => {{
-                self.on_base.push(Runner {
-                    id: self.batter()?,
-                    pitcher: self.pitcher(),
-                    base: $base,
-                });
-                self.fix_minimum_base();
-                self.record_batter_event(|s| &mut s.plate_appearances)?;
-                self.record_batter_event(|s| &mut s.at_bats)?;
-                if self.risp() {
-                    self.record_batter_event(|s| &mut s.at_bats_with_risp)?;
-                    self.record_batter_event(|s| &mut s.hits_with_risp)?;
-                }
-                self.rbi_credit = self.at_bat;
-                self.at_bat = None;
-                self.record_pitcher_event(|s| &mut s.batters_faced)?;
-                self.check_save_situation();
-                self.record_pitcher_event(|s| &mut s.strikes_pitched)?;
-                self.record_pitcher_event(|s| &mut s.hits_allowed)?;
-                Ok(true)
-            }};
-        }
-
-        let desc = &event.description;
-
-        if event.ty == 9 && (desc.ends_with("home run!") || desc.ends_with("hits a grand slam!")) {
-            self.record_batter_event(|s| &mut s.home_runs)?;
-            self.record_pitcher_event(|s| &mut s.home_runs_allowed)?;
-            common!(3)
-        } else if event.ty == 10 && desc.ends_with("hits a Single!") {
-            self.record_batter_event(|s| &mut s.singles)?;
-            common!(0)
-        } else if event.ty == 10 && desc.ends_with("hits a Double!") {
-            self.record_batter_event(|s| &mut s.doubles)?;
-            common!(1)
-        } else if event.ty == 10 && desc.ends_with("hits a Triple!") {
-            self.record_batter_event(|s| &mut s.triples)?;
-            common!(2)
-        } else if let Some(name) = desc.strip_suffix(" scores!") {
-            let runner = self.name_lookup(name, event.player_tags.get(0).copied())?;
-            self.credit_run(runner)?;
-            Ok(true)
-        } else {
-            Ok(false)
-        }
-    }
-
-    fn player_trade(&mut self, a: Uuid, b: Uuid) -> Result<()> {
-        self.ensure_pitchers_known()?;
-        let mut a_name = self
-            .game
-            .teams()
-            .find_map(|team| team.player_names.get(&a))
-            .cloned();
-        let mut b_name = self
-            .game
-            .teams()
-            .find_map(|team| team.player_names.get(&b))
-            .cloned();
-        for team in self.game.teams_mut() {
-            let mut insert_names = Vec::new();
-            for position in team.positions_mut() {
-                if position.last() == Some(&a) {
-                    position.push(b);
-                    if let Some(name) = b_name.take() {
-                        insert_names.push((b, name));
-                    }
-                } else if position.last() == Some(&b) {
-                    position.push(a);
-                    if let Some(name) = a_name.take() {
-                        insert_names.push((a, name));
-                    }
-                }
-            }
-            team.player_names.extend(insert_names);
-        }
-        if self.at_bat == Some(a) {
-            self.at_bat = Some(b)
-        } else if self.at_bat == Some(b) {
-            self.at_bat = Some(a)
-        }
-        Ok(())
+        fix_minimum_base(&mut self.on_base);
     }
 
     fn offense(&self) -> &Team {
@@ -1241,6 +840,76 @@ impl State {
         Ok(())
     }
 
+    /// Records a pitching event in the pitcher's splits for the current batter's lineup slot.
+    fn record_pitcher_split<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Split) -> &mut u32,
+    {
+        let batter = self.batter()?;
+        if let Some(slot) = self
+            .offense()
+            .lineup
+            .iter()
+            .position(|position| position.contains(&batter))
+        {
+            let pitcher = self.pitcher();
+            *f(self
+                .defense_stats(pitcher)
+                .lineup_slot_splits
+                .slot_mut(slot)) += 1;
+        }
+        Ok(())
+    }
+
+    /// Records a pitch for the pitcher, and as a pitch seen for the batter if one is at bat.
+    fn record_pitch<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Stats) -> &mut u32,
+    {
+        if self.at_bat.is_some() {
+            self.record_batter_event(|s| &mut s.pitches_seen)?;
+        }
+        self.record_pitcher_event(f)
+    }
+
+    /// Records what the current batter did with a pitch. Pitches thrown before anyone is at bat
+    /// (which can happen after some mid-plate-appearance events) only count for the pitcher.
+    fn record_pitch_seen<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Stats) -> &mut u32,
+    {
+        if self.at_bat.is_some() {
+            self.record_batter_event(f)?;
+        }
+        Ok(())
+    }
+
+    /// Records a plate appearance outcome for the current batter against the current pitcher.
+    fn record_matchup<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut MatchupStats) -> &mut u32,
+    {
+        let batter = self.batter()?;
+        let pitcher = self.pitcher();
+        let matchups = &mut self.game.matchups;
+        let index = match matchups
+            .iter()
+            .position(|m| m.batter == batter && m.pitcher == pitcher)
+        {
+            Some(index) => index,
+            None => {
+                matchups.push(Matchup {
+                    batter,
+                    pitcher,
+                    stats: MatchupStats::default(),
+                });
+                matchups.len() - 1
+            }
+        };
+        *f(&mut matchups[index].stats) += 1;
+        Ok(())
+    }
+
     fn record_runner_event<F>(&mut self, runner: Uuid, f: F) -> Result<()>
     where
         F: FnOnce(&mut Stats) -> &mut u32,
@@ -1259,6 +928,22 @@ impl State {
     }
 }
 
+struct PlateAppearanceStart {
+    batter: Uuid,
+    pitcher: Uuid,
+    top_of_inning: bool,
+    state: usize,
+    runs: u16,
+    plate_appearances: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct Fielded {
+    home_defense: bool,
+    fielder: String,
+    credit: Credit,
+}
+
 // Reasons why a finishing pitcher _might_ be in a save situation.
 #[derive(Debug, Clone, Copy, Serialize)]
 enum SaveSituation {
@@ -1266,6 +951,86 @@ enum SaveSituation {
     LeadThreeOrLess,
 }
 
+/// Raises each runner's minimum base so they are no further back than the runners behind them.
+///
+/// Due to the fact that the minimum base is only used to determine RISP, and because of the 🤝
+/// glitch, we treat only first base as exclusive, and not even first base if the last event showed
+/// both runners sharing it.
+fn fix_minimum_base(on_base: &mut [Runner]) {
+    let mut iter = on_base.iter_mut().rev();
+    let mut last = match iter.next() {
+        Some(runner) => (runner.base, runner.shared),
+        None => return,
+    };
+    for runner in iter {
+        let sharing = runner.shared && last.1 && runner.base == last.0;
+        if runner.base <= last.0 && !sharing {
+            if last.0 == 0 {
+                runner.base = 1;
+            } else {
+                runner.base = last.0;
+            }
+        }
+        last = (runner.base, runner.shared);
+    }
+}
+
+/// Returns the bases occupied by more than one runner, from an event's `basesOccupied`.
+fn shared_bases(bases_occupied: &[u16]) -> Vec<u16> {
+    let mut bases = bases_occupied.to_vec();
+    bases.sort_unstable();
+    let mut shared = bases
+        .windows(2)
+        .filter(|w| w[0] == w[1])
+        .map(|w| w[0])
+        .collect::<Vec<_>>();
+    shared.dedup();
+    shared
+}
+
+#[cfg(test)]
+#[test]
+fn test_shared_bases() {
+    fn runner(base: u16, shared: bool) -> Runner {
+        Runner {
+            id: Uuid::default(),
+            pitcher: Uuid::default(),
+            base,
+            earned: true,
+            shared,
+            inherited_by: None,
+        }
+    }
+    fn bases(on_base: &[Runner]) -> Vec<u16> {
+        on_base.iter().map(|runner| runner.base).collect()
+    }
+
+    assert!(shared_bases(&[]).is_empty());
+    assert!(shared_bases(&[2, 1, 0]).is_empty());
+    assert_eq!(shared_bases(&[0, 0]), [0]);
+    assert_eq!(shared_bases(&[2, 2, 2, 0, 0]), [0, 2]);
+
+    // batter reaches with a runner on first: first base is exclusive
+    let mut on_base = vec![runner(0, false), runner(0, false)];
+    fix_minimum_base(&mut on_base);
+    assert_eq!(bases(&on_base), [1, 0]);
+
+    // ...unless both runners were already shown sharing it
+    let mut on_base = vec![runner(0, true), runner(0, true)];
+    fix_minimum_base(&mut on_base);
+    assert_eq!(bases(&on_base), [0, 0]);
+
+    // a runner that wasn't sharing the base still has to move up
+    let mut on_base = vec![runner(0, true), runner(0, true), runner(0, false)];
+    fix_minimum_base(&mut on_base);
+    assert_eq!(bases(&on_base), [1, 1, 0]);
+
+    // bases past first aren't exclusive
+    let mut on_base = vec![runner(1, false), runner(1, false), runner(0, false)];
+    fix_minimum_base(&mut on_base);
+    assert_eq!(bases(&on_base), [1, 1, 0]);
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct Runner {
     id: Uuid,
@@ -1273,4 +1038,81 @@ struct Runner {
     pitcher: Uuid,
     /// minimum base this runner is on
     base: u16,
+    /// false if this runner reached base on an error, so their run is unearned
+    earned: bool,
+    /// true if another runner was on the same base as of the last event, which the 🤝 glitch
+    /// allows
+    shared: bool,
+    /// relief pitcher who inherited this runner, if one has entered since the runner reached base
+    inherited_by: Option<Uuid>,
+}
+
+/// A game in the top of the first inning, with each team's pitcher (away 3, home 6) and two
+/// batters (away 1 and 2, home 4 and 5) known and the away leadoff batter at bat.
+#[cfg(test)]
+fn test_state() -> State {
+    let season = Season {
+        sim: "thisidisstaticyo".into(),
+        season: 0,
+    };
+    let mut state = State::new(season, Uuid::nil());
+    for (team, first) in state.game.teams_mut().zip([1, 4]) {
+        team.id = Uuid::from_u128(first + 100);
+        team.player_names.clear();
+        for id in first..first + 3 {
+            let id = Uuid::from_u128(id);
+            team.player_names
+                .insert(id, format!("Player {}", id.as_u128()));
+        }
+        team.lineup = vec![
+            vec![Uuid::from_u128(first)],
+            vec![Uuid::from_u128(first + 1)],
+        ];
+        team.pitchers = vec![Uuid::from_u128(first + 2)];
+        team.stats.clear();
+    }
+    state.game_started = true;
+    state.at_bat = Some(Uuid::from_u128(1));
+    state
+}
+
+#[cfg(test)]
+fn test_event(ty: u16, description: &str, player_tags: &[u128]) -> GameEvent {
+    GameEvent {
+        metadata: crate::feed::GameEventMetadata {
+            play: 0,
+            sub_play: 0,
+            sibling_ids: vec![Uuid::nil()],
+            r#mod: None,
+            weather: None,
+            winner: None,
+            extra: None,
+        },
+        id: Uuid::nil(),
+        player_tags: player_tags.iter().copied().map(Uuid::from_u128).collect(),
+        team_tags: Vec::new(),
+        created: chrono::Utc::now(),
+        day: 0,
+        season: 0,
+        ty: EventType::from_raw(ty),
+        description: description.into(),
+        base_runners: None,
+        bases_occupied: None,
+        away_pitcher: None,
+        away_pitcher_name: None,
+        home_pitcher: None,
+        home_pitcher_name: None,
+        is_title_match: None,
+    }
+}
+
+/// Runs a synchronous or ignored event through its registered handler.
+#[cfg(test)]
+fn test_handle(state: &mut State, ty: u16, description: &str, player_tags: &[u128]) -> Result<()> {
+    match handler(EventType::from_raw(ty)) {
+        Some(Handler::Ignore) => Ok(()),
+        Some(Handler::Sync(handle)) => handle(state, &test_event(ty, description, player_tags)),
+        Some(Handler::Async(_)) => bail!("event type {} is handled asynchronously", ty),
+        None => bail!("unexpected event type {}", ty),
+    }
 }
@@ -0,0 +1,106 @@
+//! Runners advancing or removed from the bases between plate appearances.
+
+use super::{Handler, State};
+use crate::event_type::EventType;
+use crate::feed::GameEvent;
+use anyhow::{Context, Result};
+
+pub(super) fn handler(ty: EventType) -> Option<Handler> {
+    Some(match ty {
+        EventType::StolenBase => Handler::Sync(State::stolen_base),
+        EventType::Flooding => Handler::Sync(State::flooding),
+        _ => return None,
+    })
+}
+
+impl State {
+    /// Stolen bases and caught stealing.
+    fn stolen_base(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        if let Some((name, base)) = desc.rsplit_once(" gets caught stealing ") {
+            checkdesc!(desc, desc.ends_with(" base."));
+            let runner = self.name_lookup(name, event.player_tags.get(0).copied())?;
+            self.record_runner_event(runner, |s| &mut s.caught_stealing)?;
+            match base {
+                "second base." => {
+                    self.record_runner_event(runner, |s| &mut s.caught_stealing_second)?
+                }
+                "third base." => {
+                    self.record_runner_event(runner, |s| &mut s.caught_stealing_third)?
+                }
+                "fourth base." => {
+                    self.record_runner_event(runner, |s| &mut s.caught_stealing_home)?
+                }
+                _ => {}
+            }
+            self.half_inning_outs += 1;
+            self.record_pitcher_event(|s| &mut s.outs_recorded)?;
+            self.remove_runner(runner)?
+                .context("runner caught stealing wasn't on base?")?;
+        } else if let Some((name, base)) = desc.rsplit_once(" steals ") {
+            checkdesc!(desc, desc.ends_with(" base!"));
+            let runner = self.name_lookup(name, event.player_tags.get(0).copied())?;
+            self.record_runner_event(runner, |s| &mut s.stolen_bases)?;
+            match base {
+                "second base!" => {
+                    self.record_runner_event(runner, |s| &mut s.stolen_bases_second)?
+                }
+                "third base!" => self.record_runner_event(runner, |s| &mut s.stolen_bases_third)?,
+                "fourth base!" => self.record_runner_event(runner, |s| &mut s.stolen_bases_home)?,
+                _ => {}
+            }
+            if desc.ends_with("steals fourth base!") {
+                self.credit_run(runner)?;
+            }
+        } else {
+            checkdesc!(desc, false);
+        }
+        Ok(())
+    }
+
+    /// Flooding: baserunners swept
+    fn flooding(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        checkdesc!(
+            desc,
+            desc == "A surge of Immateria rushes up from Under!\nBaserunners are swept from play!"
+        );
+        self.on_base.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    use super::{test_handle, Runner};
+    use uuid::Uuid;
+
+    let mut state = super::test_state();
+    state.on_base.push(Runner {
+        id: Uuid::from_u128(2),
+        pitcher: Uuid::from_u128(6),
+        base: 0,
+        earned: true,
+        shared: false,
+        inherited_by: None,
+    });
+    test_handle(&mut state, 4, "Player 2 steals second base!", &[2]).unwrap();
+    let runner = state.game.away.stats[&Uuid::from_u128(2)];
+    assert_eq!(runner.stolen_bases, 1);
+    assert_eq!(runner.stolen_bases_second, 1);
+
+    test_handle(
+        &mut state,
+        4,
+        "Player 2 gets caught stealing third base.",
+        &[2],
+    )
+    .unwrap();
+    assert_eq!(
+        state.game.away.stats[&Uuid::from_u128(2)].caught_stealing_third,
+        1
+    );
+    assert_eq!(state.half_inning_outs, 1);
+    assert!(state.on_base.is_empty());
+}
@@ -0,0 +1,209 @@
+//! Game flow: the start and end of the game and of each half-inning, and score checks.
+
+use super::{Handler, State};
+use crate::event_type::EventType;
+use crate::feed::{ExtraData, GameEvent};
+use crate::game::Kind;
+use crate::{schedule, stadium, team};
+use anyhow::{bail, ensure, Context, Result};
+use rocket::futures::future::FutureExt;
+
+/// Runs on the scoreboard at which Sun 2 sets a Win, or a Black Hole swallows one.
+const WIN_THRESHOLD: u16 = 10;
+
+pub(super) fn handler(ty: EventType) -> Option<Handler> {
+    Some(match ty {
+        EventType::LetsGo => Handler::Async(|state, event| state.start_event(event).boxed()),
+        EventType::PlayBall => Handler::Ignore,
+        EventType::HalfInning => Handler::Sync(State::next_half_inning),
+        EventType::GameEnd => Handler::Sync(State::game_over),
+        EventType::InningEnd => Handler::Ignore,
+        EventType::SunTwo => Handler::Sync(|state, event| state.weather_win(event, "Sun 2", 1)),
+        EventType::BlackHole => {
+            Handler::Sync(|state, event| state.weather_win(event, "Black Hole", -1))
+        }
+        EventType::PrizeMatch => Handler::Sync(State::prize_match),
+        EventType::ScoreUpdate => Handler::Sync(State::score_update),
+        EventType::WinCollectedRegular => Handler::Ignore,
+        EventType::WinCollectedPostseason => Handler::Ignore, // in some postseasons
+        EventType::GameOver => Handler::Ignore,
+        _ => return None,
+    })
+}
+
+impl State {
+    async fn start_event(&mut self, event: &GameEvent) -> Result<()> {
+        self.game.day = event.day;
+        self.game.weather = event.metadata.weather.context("missing weather")?;
+
+        let season = &self.game.season;
+        let is_postseason = match schedule::is_postseason(season, self.game.day, self.id)? {
+            Some(is_postseason) => is_postseason,
+            // the game's day wasn't fetched; anything after the postseason started is postseason
+            None => schedule::postseason_start(season)?.map_or(false, |day| self.game.day >= day),
+        };
+        let is_special = schedule::is_special_day(season, self.game.day)?.unwrap_or(false);
+        self.game.kind = if is_special {
+            Kind::Special
+        } else if is_postseason {
+            Kind::Postseason
+        } else {
+            Kind::Regular
+        };
+
+        ensure!(event.team_tags.len() == 2, "invalid team tag count");
+        for (team, id) in self.game.teams_mut().zip(event.team_tags.iter()) {
+            team.id = *id;
+        }
+
+        let mut stadium = None;
+        for team in self.game.teams_mut() {
+            let data = team::load(team.id, event.created)
+                .await?
+                .context("no data for team")?;
+            team.name.name = data.full_name;
+            team.name.nickname = data.nickname;
+            team.name.shorthand = data.shorthand;
+            team.name.emoji = data.emoji;
+            for player in data.lineup {
+                team.lineup.push(vec![player]);
+            }
+            // the home team is last, and the game is played in their stadium
+            stadium = data.stadium;
+        }
+        if let Some(stadium) = stadium {
+            self.game.stadium = stadium::load(stadium, event.created)
+                .await?
+                .map(|stadium| stadium.name);
+        }
+
+        Ok(())
+    }
+
+    fn next_half_inning(&mut self, _event: &GameEvent) -> Result<()> {
+        if self.game_started {
+            self.top_of_inning = !self.top_of_inning;
+            if self.top_of_inning {
+                self.inning += 1;
+            }
+        } else {
+            self.game_started = true;
+        }
+
+        let inning = self.inning;
+        self.offense_mut().inning_runs.insert(inning, 0);
+        self.half_inning_outs = 0;
+        self.error_outs = 0;
+        self.on_base.clear();
+        self.half_inning_plate_appearances.clear();
+
+        Ok(())
+    }
+
+    /// The game is over; credit the finishing pitchers.
+    fn game_over(&mut self, event: &GameEvent) -> Result<()> {
+        self.game_finished = true;
+        let winner = event.metadata.winner.context("missing winner data")?;
+        for team in self.game.teams_mut() {
+            if team.id == winner {
+                team.won = true;
+            }
+        }
+        for team in self.game.teams_mut() {
+            let stats = team
+                .stats
+                .entry(*team.pitchers.last().unwrap())
+                .or_default();
+            stats.games_finished = 1;
+            if stats.games_started > 0 {
+                stats.complete_games = 1;
+                if stats.runs_allowed() == 0 {
+                    stats.shutouts = 1;
+                    if stats.hits_allowed == 0 {
+                        stats.no_hitters = 1;
+                        if stats.walks_issued == 0 {
+                            stats.perfect_games = 1;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sun 2 sets a Win upon a team, or a Black Hole swallows one, clearing their runs from the
+    /// scoreboard.
+    fn weather_win(&mut self, event: &GameEvent, weather: &str, wins: i16) -> Result<()> {
+        let desc = &event.description;
+        ensure!(desc.contains(weather), "description mismatch");
+        // the feed doesn't tag the team, so look for its nickname
+        let team = self
+            .game
+            .teams_mut()
+            .find(|team| desc.contains(&team.name.nickname))
+            .context("could not identify team")?;
+        ensure!(team.score() >= WIN_THRESHOLD, "not enough runs to clear");
+        team.weather_wins += wins;
+        team.runs_cleared += WIN_THRESHOLD;
+        Ok(())
+    }
+
+    /// Prize match.
+    fn prize_match(&mut self, _event: &GameEvent) -> Result<()> {
+        if self.game.season.sim == "gamma10" {
+            self.game.kind = Kind::Special;
+        }
+        Ok(())
+    }
+
+    /// Score update, checked against our runs.
+    fn score_update(&mut self, event: &GameEvent) -> Result<()> {
+        let score = match &event.metadata.extra {
+            Some(ExtraData::Score(data)) => data,
+            _ => bail!("missing score data"),
+        };
+        for (team, score) in self
+            .game
+            .teams()
+            .zip([&score.away_score, &score.home_score])
+        {
+            ensure!(
+                u64::from(team.score())
+                    == score.as_u64().context("score is not unsigned integer")?,
+                "score mismatch"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    use super::test_handle;
+
+    let mut state = super::test_state();
+    test_handle(&mut state, 2, "Top of 1, Away Team batting.", &[]).unwrap();
+    assert!(!state.top_of_inning);
+    assert_eq!(state.inning, 1);
+    test_handle(&mut state, 2, "Top of 2, Away Team batting.", &[]).unwrap();
+    assert!(state.top_of_inning);
+    assert_eq!(state.inning, 2);
+
+    test_handle(&mut state, 1, "Play ball!", &[]).unwrap();
+    assert!(test_handle(&mut state, 209, "", &[]).is_err());
+
+    state.game.away.name.nickname = "Tigers".into();
+    state.game.home.name.nickname = "Crabs".into();
+    let desc = "The Black Hole swallowed the Runs and a Win from the Tigers!";
+    assert!(test_handle(&mut state, 31, desc, &[]).is_err());
+    state.game.away.inning_runs.insert(1, 11);
+    test_handle(&mut state, 31, desc, &[]).unwrap();
+    assert_eq!(state.game.away.weather_wins, -1);
+    assert_eq!(state.game.away.runs(), 11);
+    assert_eq!(state.game.away.score(), 1);
+    assert_eq!(
+        state.game.weather_lines(),
+        ["The Black Hole swallowed a Win from the Tigers"]
+    );
+}
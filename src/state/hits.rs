@@ -0,0 +1,179 @@
+//! Batters reaching base: walks, hits, and home runs, along with the runners they drive in.
+
+use super::{Handler, Runner, State};
+use crate::event_type::EventType;
+use crate::feed::GameEvent;
+use anyhow::{ensure, Context, Result};
+
+pub(super) fn handler(ty: EventType) -> Option<Handler> {
+    Some(match ty {
+        EventType::Walk => Handler::Sync(|state, event| {
+            checkdesc!(event.description, state.walk(event)?);
+            Ok(())
+        }),
+        EventType::HomeRun => Handler::Sync(|state, event| {
+            checkdesc!(event.description, state.home_run(event)?);
+            Ok(())
+        }),
+        EventType::Hit => Handler::Sync(State::hit_or_sacrifice),
+        _ => return None,
+    })
+}
+
+impl State {
+    fn walk(&mut self, event: &GameEvent) -> Result<bool> {
+        if event.description.ends_with("draws a walk.") {
+            self.on_base.push(Runner {
+                id: self.batter()?,
+                pitcher: self.pitcher(),
+                base: 0,
+                earned: true,
+                shared: false,
+                inherited_by: None,
+            });
+            self.fix_minimum_base();
+            self.record_batter_event(|s| &mut s.plate_appearances)?;
+            self.record_batter_event(|s| &mut s.pitches_seen)?;
+            self.record_batter_event(|s| &mut s.walks)?;
+            self.rbi_credit = self.at_bat;
+            self.record_pitcher_split(|s| &mut s.batters_faced)?;
+            self.record_matchup(|m| &mut m.plate_appearances)?;
+            self.record_pitcher_split(|s| &mut s.walks_issued)?;
+            self.record_matchup(|m| &mut m.walks)?;
+            self.at_bat = None;
+            self.record_pitcher_event(|s| &mut s.batters_faced)?;
+            self.check_save_situation();
+            self.record_pitcher_event(|s| &mut s.walks_issued)?;
+            Ok(true)
+        } else if let Some(name) = event.description.strip_suffix(" scores!") {
+            let runner = self.name_lookup(name, event.player_tags.get(1).copied())?;
+            self.credit_run(runner)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn home_run(&mut self, event: &GameEvent) -> Result<bool> {
+        if !(self.hit(event)?) {
+            return Ok(false);
+        }
+
+        let was_on_base = self.on_base.clone();
+        for runner in was_on_base {
+            self.credit_run(runner.id)?;
+        }
+        self.on_base.clear();
+
+        Ok(true)
+    }
+
+    /// Hits and sacrifice advances.
+    fn hit_or_sacrifice(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        if desc.ends_with("advances on the sacrifice.") || desc.ends_with("tags up and scores!") {
+            self.sac(event)?;
+        } else {
+            checkdesc!(desc, self.hit(event)?);
+        }
+        Ok(())
+    }
+
+    fn hit(&mut self, event: &GameEvent) -> Result<bool> {
+        macro_rules! common {
+            ($base:expr) => {{
+                self.on_base.push(Runner {
+                    id: self.batter()?,
+                    pitcher: self.pitcher(),
+                    base: $base,
+                    earned: true,
+                    shared: false,
+                    inherited_by: None,
+                });
+                self.fix_minimum_base();
+                self.record_batter_event(|s| &mut s.plate_appearances)?;
+                self.record_batter_event(|s| &mut s.pitches_seen)?;
+                self.record_batter_event(|s| &mut s.at_bats)?;
+                if self.risp() {
+                    self.record_batter_event(|s| &mut s.at_bats_with_risp)?;
+                    self.record_batter_event(|s| &mut s.hits_with_risp)?;
+                }
+                self.rbi_credit = self.at_bat;
+                self.record_pitcher_split(|s| &mut s.batters_faced)?;
+                self.record_matchup(|m| &mut m.plate_appearances)?;
+                self.record_matchup(|m| &mut m.hits)?;
+                self.at_bat = None;
+                self.record_pitcher_event(|s| &mut s.batters_faced)?;
+                self.check_save_situation();
+                self.record_pitcher_event(|s| &mut s.strikes_pitched)?;
+                self.record_pitcher_event(|s| &mut s.hits_allowed)?;
+                let inning = self.inning;
+                *self.offense_mut().inning_hits.entry(inning).or_default() += 1;
+                Ok(true)
+            }};
+        }
+
+        let desc = &event.description;
+
+        if event.ty == EventType::HomeRun
+            && (desc.ends_with("home run!") || desc.ends_with("hits a grand slam!"))
+        {
+            self.record_batter_event(|s| &mut s.home_runs)?;
+            self.record_pitcher_event(|s| &mut s.home_runs_allowed)?;
+            self.record_matchup(|m| &mut m.home_runs)?;
+            common!(3)
+        } else if event.ty == EventType::Hit && desc.ends_with("hits a Single!") {
+            self.record_batter_event(|s| &mut s.singles)?;
+            common!(0)
+        } else if event.ty == EventType::Hit && desc.ends_with("hits a Double!") {
+            self.record_batter_event(|s| &mut s.doubles)?;
+            common!(1)
+        } else if event.ty == EventType::Hit && desc.ends_with("hits a Triple!") {
+            self.record_batter_event(|s| &mut s.triples)?;
+            common!(2)
+        } else if let Some(name) = desc.strip_suffix(" scores!") {
+            let runner = self.name_lookup(name, event.player_tags.get(0).copied())?;
+            self.credit_run(runner)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn sac(&mut self, event: &GameEvent) -> Result<()> {
+        ensure!(event.player_tags.len() == 1, "invalid player tag count");
+        self.credit_run(event.player_tags[0])?;
+        let batter = self
+            .last_fielded_out
+            .as_ref()
+            .copied()
+            .context("sac advance without a prior fielded out")?;
+        let risp = self.risp();
+        let stats = self.offense_stats(batter);
+        stats.sacrifices += 1;
+        stats.runs_batted_in += 1;
+        stats.at_bats -= 1;
+        if risp {
+            stats.at_bats_with_risp -= 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    use super::test_handle;
+    use uuid::Uuid;
+
+    let mut state = super::test_state();
+    test_handle(&mut state, 5, "Player 1 draws a walk.", &[]).unwrap();
+    assert_eq!(state.at_bat, None);
+    assert_eq!(state.bases(), 0b1);
+    assert_eq!(state.game.away.stats[&Uuid::from_u128(1)].walks, 1);
+    assert_eq!(state.game.away.stats[&Uuid::from_u128(1)].pitches_seen, 1);
+    assert_eq!(state.game.home.stats[&Uuid::from_u128(6)].walks_issued, 1);
+
+    assert!(test_handle(&mut state, 5, "Player 1 hits a Single!", &[]).is_err());
+}
@@ -0,0 +1,85 @@
+//! Modifications added to or removed from players, and flavor events that don't affect the game.
+
+use super::{Handler, State};
+use crate::event_type::EventType;
+use crate::feed::GameEvent;
+use anyhow::{bail, Result};
+
+pub(super) fn handler(ty: EventType) -> Option<Handler> {
+    Some(match ty {
+        EventType::AddedMod
+        | EventType::RemovedMod
+        | EventType::AddedModFromOtherMod
+        | EventType::RemovedModFromOtherMod => Handler::Sync(State::modification),
+        EventType::Shame => Handler::Ignore,
+        EventType::PlayerSkipped => Handler::Ignore, // Elsewhere or Shelled
+        EventType::Partying => Handler::Ignore,
+        EventType::PeanutYummy => Handler::Ignore,
+        EventType::PeanutAllergic => Handler::Ignore,
+        EventType::Incineration => Handler::Ignore,
+        EventType::PeanutFlavorText => Handler::Ignore,
+        EventType::ReturnFromElsewhere => Handler::Ignore,
+        EventType::StatIncrease => Handler::Ignore,
+        EventType::StatDecrease => Handler::Ignore,
+        EventType::StatReroll => Handler::Ignore,
+        EventType::EnterHallOfFlame => Handler::Ignore,
+        EventType::PlayerHatched => Handler::Ignore,
+        EventType::WeatherEvent => Handler::Ignore,
+        EventType::WinterStormWarning => Handler::Ignore,
+        EventType::Snowflakes => Handler::Ignore,
+        EventType::Unfreezable => Handler::Ignore,
+        _ => return None,
+    })
+}
+
+impl State {
+    /// A modification was added or removed.
+    fn modification(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        match event.metadata.r#mod.as_deref() {
+            Some("FROZEN") => {
+                if let Some(name) = desc.strip_suffix(" was Frozen!") {
+                    // we only care about FROZEN for calculating CRiSP, which requires
+                    // that they're on base. if we can't look up their name, they can't
+                    // be on base.
+                    if let Ok(player) = self.name_lookup(name, event.player_tags.get(0).copied()) {
+                        if matches!(
+                            event.ty,
+                            EventType::AddedMod | EventType::AddedModFromOtherMod
+                        ) {
+                            self.mods.insert((player, "FROZEN"));
+                        } else {
+                            self.mods.remove(&(player, "FROZEN"));
+                        }
+                    }
+                }
+            }
+            Some(_) => {}
+            None => bail!("missing modification data"),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    use super::{test_event, Handler};
+    use uuid::Uuid;
+
+    let mut state = super::test_state();
+    let mut event = test_event(106, "Player 2 was Frozen!", &[2]);
+    event.metadata.r#mod = Some("FROZEN".into());
+    state.modification(&event).unwrap();
+    assert!(state.mods.contains(&(Uuid::from_u128(2), "FROZEN")));
+    event.ty = EventType::RemovedMod;
+    state.modification(&event).unwrap();
+    assert!(state.mods.is_empty());
+
+    event.metadata.r#mod = None;
+    assert!(state.modification(&event).is_err());
+    assert!(matches!(
+        handler(EventType::Incineration),
+        Some(Handler::Ignore)
+    ));
+}
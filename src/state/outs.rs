@@ -0,0 +1,221 @@
+//! Batters put out, and batters reaching on errors or fielder's choices.
+
+use super::{Fielded, Handler, Runner, State};
+use crate::event_type::EventType;
+use crate::feed::GameEvent;
+use crate::fielding::Credit;
+use anyhow::{bail, Context, Result};
+use uuid::Uuid;
+
+pub(super) fn handler(ty: EventType) -> Option<Handler> {
+    Some(match ty {
+        EventType::Strikeout => Handler::Sync(State::strikeout),
+        EventType::FlyOut | EventType::GroundOut => Handler::Sync(State::flyout_or_ground_out),
+        _ => return None,
+    })
+}
+
+impl State {
+    /// Strikeout
+    fn strikeout(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        checkdesc!(desc, desc.contains("strikes out"));
+        self.record_batter_event(|s| &mut s.strike_outs)?;
+        self.record_pitcher_event(|s| &mut s.struck_outs)?;
+        if desc.contains("strikes out looking") {
+            self.record_batter_event(|s| &mut s.strike_outs_looking)?;
+            self.record_batter_event(|s| &mut s.strikes_taken)?;
+            self.record_pitcher_event(|s| &mut s.struck_outs_looking)?;
+            self.record_pitcher_event(|s| &mut s.called_strikes_pitched)?;
+        } else if desc.contains("strikes out swinging") {
+            self.record_batter_event(|s| &mut s.strike_outs_swinging)?;
+            self.record_batter_event(|s| &mut s.swinging_strikes)?;
+            self.record_pitcher_event(|s| &mut s.struck_outs_swinging)?;
+        }
+        self.record_pitcher_split(|s| &mut s.struck_outs)?;
+        self.record_matchup(|m| &mut m.strike_outs)?;
+        self.batter_out()?;
+        Ok(())
+    }
+
+    /// Flyout or ground out
+    fn flyout_or_ground_out(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        if desc.ends_with("reaches on fielder's choice.") {
+            // nothing, we already handled this in the "out at" branch
+        } else if desc.contains("reaches on an error") {
+            self.reached_on_error()?;
+        } else {
+            checkdesc!(
+                desc,
+                desc.contains("hit a flyout to")
+                    || desc.contains("hit a ground out to")
+                    || desc.contains("out at")
+                    || desc.ends_with("hit into a double play!")
+            );
+            self.fielded_out(event)?;
+        }
+        Ok(())
+    }
+
+    fn fielded_out(&mut self, event: &GameEvent) -> Result<()> {
+        if let Some((out, base)) = event.description.rsplit_once(" out at ") {
+            // fielder's choice
+            self.record_pitcher_event(|s| &mut s.groundouts_pitched)?;
+            let out = *self
+                .offense()
+                .player_names
+                .iter()
+                .find(|(_, name)| name == &out)
+                .with_context(|| format!("could not determine id for baserunner {}", out))?
+                .0;
+            let base = match base {
+                "second base." => 0,
+                "third base." => 1,
+                "fourth base." => 2,
+                _ => bail!("unexpected base for fielder's choice"),
+            };
+            // the batter takes the place of the runner who was put out, including which pitchers
+            // they're charged to
+            let runner = self
+                .remove_runner_base(out, base)?
+                .context("baserunner out in fielder's choice not on base")?;
+            self.on_base.push(Runner {
+                id: self.batter()?,
+                pitcher: runner.pitcher,
+                base: 0,
+                earned: true,
+                shared: false,
+                inherited_by: runner.inherited_by,
+            });
+            self.fix_minimum_base();
+        } else if event.description.ends_with("hit into a double play!") {
+            // double play
+            self.half_inning_outs += 1;
+            self.rbi_credit = None;
+            self.record_batter_event(|s| &mut s.double_plays_grounded_into)?;
+            self.record_pitcher_event(|s| &mut s.groundouts_pitched)?;
+            self.record_pitcher_event(|s| &mut s.outs_recorded)?;
+            let pitcher = self.pitcher();
+            self.defense_stats(pitcher).fielding.double_plays += 1;
+            if event.id.as_u128() == 0x3fdb026f97a3401385ee44f935c26f01 {
+                // missing data in Chronicler at the start of 5ffbde13-1807-4694-9d13-861c6302b384.
+                // the runner put out was Craig Faucet.
+                self.remove_runner(Uuid::from_u128(0xe34b37e1b47448ed8a657e182733996c))?;
+                self.offense_stats(self.batter()?).left_on_base += 1;
+            } else if self.on_base.len() == 1 {
+                self.on_base.clear();
+                self.offense_stats(self.batter()?).left_on_base += 1;
+            } else if self.half_inning_outs == 2 {
+                // this double play was made on one out, so it's the last play of the half-inning.
+                // which runner was put out doesn't matter anymore, only how many are left on base
+                // when the half-inning ends.
+                self.on_base.pop();
+                self.offense_stats(self.batter()?).left_on_base += 1;
+            } else {
+                // uh-oh. we have multiple runners on, but the Feed doesn't tell us which one is
+                // out. we'll need to rely on the baseRunners object merged in from sachet.
+                let base_runners = event
+                    .base_runners
+                    .as_ref()
+                    .context("unable to determine runner out in double play")?;
+                let out = self
+                    .on_base
+                    .iter()
+                    // if more than one batter is removed, it's a scoring play; the other half of
+                    // the double play will be on an earlier base and thus will have been added to
+                    // this map _later_. reverse the iterator to find the latest one.
+                    .rev()
+                    .find(|runner| !base_runners.contains(&runner.id))
+                    .map(|runner| runner.id)
+                    .context("unable to determine runner out in double play")?;
+                self.remove_runner(out)?;
+                self.offense_stats(self.batter()?).left_on_base += 1;
+            }
+        } else if event.description.contains("hit a flyout to") {
+            self.record_pitcher_event(|s| &mut s.flyouts_pitched)?;
+            self.last_fielded_out = self.at_bat;
+            self.record_fielder(event);
+        } else if event.description.contains("hit a ground out to") {
+            self.record_pitcher_event(|s| &mut s.groundouts_pitched)?;
+            self.last_fielded_out = self.at_bat;
+            self.record_fielder(event);
+        } else {
+            unreachable!();
+        }
+
+        self.batter_out()
+    }
+
+    fn record_fielder(&mut self, event: &GameEvent) {
+        if let Some((credit, fielder)) = Credit::parse(&event.description) {
+            self.fielded.push(Fielded {
+                home_defense: self.top_of_inning,
+                fielder: fielder.to_owned(),
+                credit,
+            });
+        }
+    }
+
+    fn reached_on_error(&mut self) -> Result<()> {
+        self.on_base.push(Runner {
+            id: self.batter()?,
+            pitcher: self.pitcher(),
+            base: 0,
+            earned: false,
+            shared: false,
+            inherited_by: None,
+        });
+        self.fix_minimum_base();
+        self.error_outs += 1;
+        self.defense_mut().errors += 1;
+        self.record_batter_event(|s| &mut s.plate_appearances)?;
+        self.record_batter_event(|s| &mut s.pitches_seen)?;
+        self.record_batter_event(|s| &mut s.at_bats)?;
+        self.rbi_credit = None;
+        self.record_pitcher_split(|s| &mut s.batters_faced)?;
+        self.record_matchup(|m| &mut m.plate_appearances)?;
+        self.at_bat = None;
+        self.record_pitcher_event(|s| &mut s.batters_faced)?;
+        self.check_save_situation();
+        self.record_pitcher_event(|s| &mut s.strikes_pitched)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    use super::test_handle;
+
+    let mut state = super::test_state();
+    test_handle(&mut state, 6, "Player 1 strikes out looking.", &[]).unwrap();
+    assert_eq!(state.half_inning_outs, 1);
+    assert_eq!(state.at_bat, None);
+
+    let batter = state.game.away.stats[&Uuid::from_u128(1)];
+    assert_eq!(batter.strike_outs, 1);
+    assert_eq!(batter.strike_outs_looking, 1);
+    assert_eq!(batter.at_bats, 1);
+    let pitcher = state.game.home.stats[&Uuid::from_u128(6)];
+    assert_eq!(pitcher.struck_outs_looking, 1);
+    assert_eq!(pitcher.outs_recorded, 1);
+
+    // an inning-ending double play with two runners on leaves one of them on base
+    for (id, base) in [(4, 1), (5, 0)] {
+        state.on_base.push(super::Runner {
+            id: Uuid::from_u128(id),
+            pitcher: Uuid::from_u128(6),
+            base,
+            earned: true,
+            shared: false,
+            inherited_by: None,
+        });
+    }
+    state.at_bat = Some(Uuid::from_u128(2));
+    test_handle(&mut state, 8, "Player 2 hit into a double play!", &[]).unwrap();
+    assert_eq!(state.half_inning_outs, 3);
+    state.strand_runners();
+    assert!(state.on_base.is_empty());
+    assert_eq!(state.game.away.left_on_base, 1);
+    assert_eq!(state.game.away.stats[&Uuid::from_u128(2)].left_on_base, 2);
+}
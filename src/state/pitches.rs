@@ -0,0 +1,130 @@
+//! Pitches, and the batter stepping up to start each plate appearance.
+
+use super::{Handler, State};
+use crate::event_type::EventType;
+use crate::feed::GameEvent;
+use crate::inhabiting::Inhabiting;
+use anyhow::{ensure, Context, Result};
+
+pub(super) fn handler(ty: EventType) -> Option<Handler> {
+    Some(match ty {
+        EventType::BatterUp => Handler::Sync(State::batter_up),
+        EventType::Strike => Handler::Sync(State::strike),
+        EventType::Ball => Handler::Sync(State::ball),
+        EventType::FoulBall => Handler::Sync(State::foul_ball),
+        EventType::DoubleStrike => Handler::Sync(State::double_strike),
+        EventType::StrikeZapped => Handler::Ignore,
+        _ => return None,
+    })
+}
+
+impl State {
+    /// Start of plate appearance
+    fn batter_up(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        if event.player_tags.len() == 2
+            && desc.contains("is Inhabiting")
+            && !desc.contains("batting for the")
+        {
+            let position = self
+                .offense_mut()
+                .positions_mut()
+                .find(|position| position.last() == Some(&event.player_tags[1]))
+                .context("unable to find position for inhabited player")?;
+            position.push(event.player_tags[0]);
+            position.push(event.player_tags[1]);
+            let inhabiting = Inhabiting {
+                ghost: event.player_tags[0],
+                host: event.player_tags[1],
+            };
+            if !self.game.inhabiting.contains(&inhabiting) {
+                self.game.inhabiting.push(inhabiting);
+            }
+        } else {
+            ensure!(
+                event.player_tags.len() == 1 || event.player_tags.len() == 2,
+                "invalid player tag count"
+            );
+            if let Some((name, _)) = desc.rsplit_once(" batting for the ") {
+                self.offense_mut()
+                    .player_names
+                    .insert(event.player_tags[0], name.into());
+                self.at_bat = Some(event.player_tags[0]);
+            } else {
+                checkdesc!(desc, false);
+            }
+        }
+        Ok(())
+    }
+
+    /// Strike
+    fn strike(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        checkdesc!(
+            desc,
+            desc.starts_with("Strike, looking.")
+                || desc.starts_with("Strike, swinging.")
+                || desc.starts_with("Strike, flinching.")
+                || desc.starts_with("Strikes, looking.")
+                || desc.starts_with("Strikes, swinging.")
+        );
+        self.record_pitch(|s| &mut s.strikes_pitched)?;
+        if desc.contains("swinging.") {
+            self.record_pitch_seen(|s| &mut s.swinging_strikes)?;
+        } else {
+            self.record_pitch_seen(|s| &mut s.strikes_taken)?;
+            self.record_pitcher_event(|s| &mut s.called_strikes_pitched)?;
+        }
+        Ok(())
+    }
+
+    /// Ball
+    fn ball(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        checkdesc!(desc, desc.starts_with("Ball."));
+        self.record_pitch(|s| &mut s.balls_pitched)?;
+        Ok(())
+    }
+
+    /// Foul Ball
+    fn foul_ball(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        checkdesc!(
+            desc,
+            desc.starts_with("Foul Ball.") || desc.starts_with("Foul Balls.")
+        );
+        self.record_pitch(|s| &mut s.strikes_pitched)?;
+        self.record_pitch_seen(|s| &mut s.foul_balls)?;
+        Ok(())
+    }
+
+    /// Double strike
+    fn double_strike(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        checkdesc!(desc, desc.ends_with("fires a Double Strike!"));
+        // only record one extra strike; the next event catches the other
+        self.record_pitch(|s| &mut s.strikes_pitched)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    use super::test_handle;
+    use uuid::Uuid;
+
+    let mut state = super::test_state();
+    test_handle(&mut state, 14, "Ball. 1-0", &[]).unwrap();
+    test_handle(&mut state, 13, "Strike, swinging. 1-1", &[]).unwrap();
+    test_handle(&mut state, 15, "Foul Ball. 1-2", &[]).unwrap();
+    assert!(test_handle(&mut state, 14, "Strike, looking. 1-3", &[]).is_err());
+
+    let pitcher = state.game.home.stats[&Uuid::from_u128(6)];
+    assert_eq!(pitcher.balls_pitched, 1);
+    assert_eq!(pitcher.strikes_pitched, 2);
+    let batter = state.game.away.stats[&Uuid::from_u128(1)];
+    assert_eq!(batter.pitches_seen, 3);
+    assert_eq!(batter.swinging_strikes, 1);
+    assert_eq!(batter.foul_balls, 1);
+}
@@ -0,0 +1,446 @@
+//! Roster changes: pitching changes, trades and swaps, incinerations, shuffled lineups, and players
+//! moving to and from the Shadows.
+
+use super::{Handler, SaveSituation, State};
+use crate::event_type::EventType;
+use crate::feed::{ExtraData, GameEvent};
+use crate::game::Stats;
+use crate::team;
+use anyhow::{bail, ensure, Context, Result};
+use chrono::Duration;
+use rocket::futures::future::FutureExt;
+use uuid::Uuid;
+
+/// `PlayerMoveData::location` for the lineup.
+const LINEUP: u8 = 0;
+
+pub(super) fn handler(ty: EventType) -> Option<Handler> {
+    Some(match ty {
+        EventType::PitcherChange => Handler::Sync(State::pitcher_change),
+        EventType::Feedback => Handler::Sync(State::feedback),
+        EventType::Trade => Handler::Sync(State::trade),
+        EventType::Swap => Handler::Sync(State::swap),
+        EventType::IncinerationReplacement => Handler::Sync(State::incineration),
+        EventType::PlayerAddedToTeam
+        | EventType::PlayerRemovedFromTeam
+        | EventType::PlayerMoved => Handler::Sync(State::roster_move),
+        EventType::ReverbShuffle | EventType::ReverbLineupShuffle => {
+            Handler::Async(|state, event| state.reverb(event).boxed())
+        }
+        EventType::ReverbRotationShuffle => Handler::Sync(State::rotation_shuffle),
+        EventType::NightShift => Handler::Ignore, // handled with Swap
+        _ => return None,
+    })
+}
+
+impl State {
+    /// Pitcher change
+    fn pitcher_change(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        if let Some((name, _)) = desc.rsplit_once(" is now pitching for the ") {
+            ensure!(event.player_tags.len() == 1, "invalid player tag count");
+
+            if let Err(err) = self.ensure_pitchers_known() {
+                // Starting in gamma9, the sim does not correctly set the pitcher before
+                // the start of a tournament's first game, leading to a pitcher change
+                // event at the start of each half of the first inning because the sim is
+                // changing the pitcher from "null" to whoever the starting pitcher is. If
+                // it's the first inning and the starting pitcher is a placeholder who has
+                // not yet thrown the ball, we should ignore this error.
+                ensure!(
+                    self.inning == 1
+                        && self.defense().pitchers.len() == 1
+                        && self.defense_stats(self.pitcher())
+                            == &Stats {
+                                games_started: 1,
+                                ..Default::default()
+                            },
+                    err
+                );
+
+                // If we still have placeholder pitcher data for the defense, fill in the
+                // data we now have so that the pitcher change branch below doesn't run.
+                self.defense_mut()
+                    .replace_placeholder_pitcher(event.player_tags[0], name);
+            }
+
+            if self.pitcher() != event.player_tags[0] {
+                // starting pitchers must pitch 5 innings to be credited for the win. clear
+                // the pitcher of record if they are not eligible to record the win; if the
+                // losing team's pitcher is still cleared by the end of the game, fill it
+                // back in with the starting pitcher for the losing team.
+                let old_pitcher = self.pitcher();
+                if self.defense().pitchers.len() == 1
+                    && self.defense_stats(old_pitcher).outs_recorded < 15
+                {
+                    self.defense_mut().pitcher_of_record = Uuid::default();
+                }
+
+                // a relief pitcher who entered in a save situation, recorded an out, and
+                // leaves with the lead intact is credited with a hold.
+                if self.defense().pitchers.len() > 1
+                    && self.save_situation[if self.top_of_inning { 1 } else { 0 }].is_some()
+                    && self.defense_stats(old_pitcher).outs_recorded >= 1
+                    && self.defense().score() > self.offense().score()
+                {
+                    self.record_pitcher_event(|s| &mut s.holds)?;
+                }
+
+                let new_pitcher = event.player_tags[0];
+                self.defense_mut().pitchers.push(new_pitcher);
+                self.defense_mut()
+                    .player_names
+                    .insert(new_pitcher, name.into());
+
+                // runners on base stay charged to the pitchers who allowed them, but are
+                // inherited by the new pitcher
+                for runner in &mut self.on_base {
+                    runner.inherited_by = Some(new_pitcher);
+                }
+                let inherited = u32::try_from(self.on_base.len())?;
+                if inherited > 0 {
+                    self.defense_mut()
+                        .stats
+                        .entry(new_pitcher)
+                        .or_default()
+                        .inherited_runners += inherited;
+                }
+
+                let offense_runs = self.offense().score();
+                let defense_runs = self.defense().score();
+                let save = &mut self.save_situation[if self.top_of_inning { 1 } else { 0 }];
+                *save = if defense_runs > offense_runs {
+                    if offense_runs + 1 >= defense_runs
+                        || offense_runs + u16::try_from(self.on_base.len())? >= defense_runs
+                    {
+                        // potential tying run on base or at bat
+                        // NOTE: on deck handled via `check_save_situation`
+                        Some(SaveSituation::TyingRun)
+                    } else if defense_runs - offense_runs <= 3 {
+                        Some(SaveSituation::LeadThreeOrLess)
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                };
+            }
+        } else {
+            checkdesc!(desc, false);
+        }
+        Ok(())
+    }
+
+    fn feedback(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        if desc.ends_with("switch teams in the feedback!") {
+            ensure!(event.player_tags.len() == 2, "invalid team tag count");
+            self.player_trade(event.player_tags[0], event.player_tags[1])?;
+        }
+        Ok(())
+    }
+
+    /// Trade (e.g. Feedback swap)
+    fn trade(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        // for feedback, handled in 41
+        checkdesc!(desc, desc.ends_with("were swapped in Feedback."));
+        Ok(())
+    }
+
+    /// Swap within team
+    fn swap(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        checkdesc!(
+            desc,
+            desc.ends_with("swapped two players on their roster.")
+                || desc.ends_with("had several players shuffled in the Reverb!")
+        );
+        let swap = match &event.metadata.extra {
+            Some(ExtraData::Swap(swap)) => swap,
+            _ => bail!("missing player swap data"),
+        };
+        self.player_trade(swap.a_player_id, swap.b_player_id)?;
+        // yolo
+        for (player, player_name) in [
+            (swap.a_player_id, &swap.a_player_name),
+            (swap.b_player_id, &swap.b_player_name),
+        ] {
+            for team in self.game.teams_mut() {
+                team.player_names.insert(player, player_name.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Incineration
+    fn incineration(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        if desc.contains("replaced the incinerated") {
+            self.ensure_pitchers_known()?;
+            let replacement = match &event.metadata.extra {
+                Some(ExtraData::Incineration(replacement)) => replacement,
+                _ => bail!("missing incineration replacement data"),
+            };
+            for team in self.game.teams_mut() {
+                if team.id == replacement.team_id {
+                    team.player_names
+                        .insert(replacement.in_player_id, replacement.in_player_name.clone());
+                    for position in team.positions_mut() {
+                        if position.last() == Some(&replacement.out_player_id) {
+                            position.push(replacement.in_player_id);
+                        }
+                    }
+                }
+            }
+        } else if desc.starts_with("They're replaced by") {
+            // nothing, redundant event
+        } else {
+            checkdesc!(desc, false);
+        }
+        Ok(())
+    }
+
+    /// A player joined or left a team, or moved between the active roster and the Shadows (such as
+    /// when Voicemail sends a player to the Shadows, or a Shadows player is called up). A player
+    /// who joins the lineup mid-game bats at the end of it. A lineup player who leaves keeps their
+    /// slot in the box score, but is marked as departed so that their slot is skipped when the
+    /// lineup is next shuffled. The player's name is recorded in case they appear later.
+    fn roster_move(&mut self, event: &GameEvent) -> Result<()> {
+        let data = match &event.metadata.extra {
+            Some(ExtraData::Move(data)) => data,
+            _ => bail!("missing roster move data"),
+        };
+        let joined_lineup =
+            event.ty != EventType::PlayerRemovedFromTeam && data.location == Some(LINEUP);
+        // a moved player's location is where they went
+        let left_lineup = event.ty == EventType::PlayerRemovedFromTeam
+            || (event.ty == EventType::PlayerMoved && data.location != Some(LINEUP));
+        // moves on teams that aren't playing in this game don't matter
+        if let Some(team) = self.game.teams_mut().find(|team| team.id == data.team_id) {
+            if let Some(name) = &data.player_name {
+                team.player_names.insert(data.player_id, name.clone());
+            }
+            if joined_lineup {
+                self.departed.remove(&data.player_id);
+                if !team
+                    .lineup
+                    .iter()
+                    .any(|position| position.contains(&data.player_id))
+                {
+                    team.lineup.push(vec![data.player_id]);
+                }
+            } else if left_lineup
+                && team
+                    .lineup
+                    .iter()
+                    .any(|position| position.last() == Some(&data.player_id))
+            {
+                self.departed.insert(data.player_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reverb. 130 is a full-team shuffle, and 131 is a lineup shuffle. Players are not
+    async fn reverb(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        // swapped, but instead the order is shuffled, so there's no information in the
+        // feed about the new lineup order.
+        //
+        // To get the new roster order, we ask Chronicler for the team information one
+        // minute after this feed event, since it fetches team data once per minute during
+        // games.
+        //
+        // This only sets the new lineup order. Pitching changes (if any) are handled via
+        // type 3.
+
+        // The feed doesn't specify which team got Reverbed, so we need to scan the event
+        // description for the team's nickname.
+        let team = self
+            .game
+            .teams_mut()
+            .find(|team| desc.contains(&team.name.nickname))
+            .context("could not identify reverbed team")?;
+        let data = team::load(team.id, event.created + Duration::minutes(1))
+            .await?
+            .context("no data for team")?;
+        // players who left the team or went to the Shadows aren't in the new lineup
+        let departed = &self.departed;
+        let positions = team
+            .lineup
+            .iter_mut()
+            .filter(|position| {
+                position
+                    .last()
+                    .map_or(true, |last| !departed.contains(last))
+            })
+            .collect::<Vec<_>>();
+        ensure!(positions.len() == data.lineup.len(), "lineup size mismatch");
+        for (position, player) in positions.into_iter().zip(data.lineup) {
+            if position.last() != Some(&player) {
+                position.push(player);
+            }
+        }
+        Ok(())
+    }
+
+    fn rotation_shuffle(&mut self, event: &GameEvent) -> Result<()> {
+        let desc = &event.description;
+        checkdesc!(
+            desc,
+            desc.ends_with("had their rotation shuffled in the Reverb!")
+        );
+        // do nothing, because type 3 will follow
+        Ok(())
+    }
+
+    fn player_trade(&mut self, a: Uuid, b: Uuid) -> Result<()> {
+        self.ensure_pitchers_known()?;
+        let mut a_name = self
+            .game
+            .teams()
+            .find_map(|team| team.player_names.get(&a))
+            .cloned();
+        let mut b_name = self
+            .game
+            .teams()
+            .find_map(|team| team.player_names.get(&b))
+            .cloned();
+        for team in self.game.teams_mut() {
+            let mut insert_names = Vec::new();
+            for position in team.positions_mut() {
+                if position.last() == Some(&a) {
+                    position.push(b);
+                    if let Some(name) = b_name.take() {
+                        insert_names.push((b, name));
+                    }
+                } else if position.last() == Some(&b) {
+                    position.push(a);
+                    if let Some(name) = a_name.take() {
+                        insert_names.push((a, name));
+                    }
+                }
+            }
+            team.player_names.extend(insert_names);
+        }
+        if self.at_bat == Some(a) {
+            self.at_bat = Some(b)
+        } else if self.at_bat == Some(b) {
+            self.at_bat = Some(a)
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    use super::test_handle;
+
+    let mut state = super::test_state();
+    test_handle(
+        &mut state,
+        41,
+        "Player 1 and Player 4 switch teams in the feedback!",
+        &[1, 4],
+    )
+    .unwrap();
+    assert_eq!(state.game.away.lineup[0], [1, 4].map(Uuid::from_u128));
+    assert_eq!(state.game.home.lineup[0], [4, 1].map(Uuid::from_u128));
+    assert_eq!(state.at_bat, Some(Uuid::from_u128(4)));
+
+    let mut event = super::test_event(115, "Player 7 was moved to the Lineup.", &[7]);
+    assert!(state.roster_move(&event).is_err());
+    event.metadata.extra = serde_json::from_str(
+        r#"{"playerId":"00000000-0000-0000-0000-000000000007","playerName":"Player 7",
+            "teamId":"00000000-0000-0000-0000-000000000069","location":0}"#,
+    )
+    .unwrap();
+    state.roster_move(&event).unwrap();
+    assert_eq!(state.game.away.lineup.len(), 2);
+    state.game.home.id = Uuid::from_u128(0x69);
+    state.roster_move(&event).unwrap();
+    state.roster_move(&event).unwrap();
+    assert_eq!(state.game.home.lineup.len(), 3);
+    assert_eq!(state.game.home.lineup[2], [Uuid::from_u128(7)]);
+    assert_eq!(
+        state.game.home.player_names[&Uuid::from_u128(7)],
+        "Player 7"
+    );
+
+    let mut event = super::test_event(115, "Player 8 was sent to the Shadows.", &[8]);
+    event.metadata.extra = serde_json::from_str(
+        r#"{"playerId":"00000000-0000-0000-0000-000000000008","playerName":"Player 8",
+            "teamId":"00000000-0000-0000-0000-000000000069","location":2}"#,
+    )
+    .unwrap();
+    state.roster_move(&event).unwrap();
+    assert_eq!(state.game.home.lineup.len(), 3);
+    assert!(state.departed.is_empty());
+
+    // a lineup player sent to the Shadows keeps their slot, but is no longer in the batting order
+    let mut event = super::test_event(115, "Player 7 was sent to the Shadows.", &[7]);
+    event.metadata.extra = serde_json::from_str(
+        r#"{"playerId":"00000000-0000-0000-0000-000000000007","playerName":"Player 7",
+            "teamId":"00000000-0000-0000-0000-000000000069","location":2}"#,
+    )
+    .unwrap();
+    state.roster_move(&event).unwrap();
+    assert_eq!(state.game.home.lineup.len(), 3);
+    assert!(state.departed.contains(&Uuid::from_u128(7)));
+    event.metadata.extra = serde_json::from_str(
+        r#"{"playerId":"00000000-0000-0000-0000-000000000007","playerName":"Player 7",
+            "teamId":"00000000-0000-0000-0000-000000000069","location":0}"#,
+    )
+    .unwrap();
+    state.roster_move(&event).unwrap();
+    assert_eq!(state.game.home.lineup.len(), 3);
+    assert!(state.departed.is_empty());
+
+    let mut event = super::test_event(112, "Player 1 was removed from the Home Team.", &[1]);
+    event.metadata.extra = serde_json::from_str(
+        r#"{"playerId":"00000000-0000-0000-0000-000000000001","playerName":"Player 1",
+            "teamId":"00000000-0000-0000-0000-000000000069","location":0}"#,
+    )
+    .unwrap();
+    state.roster_move(&event).unwrap();
+    assert!(state.departed.contains(&Uuid::from_u128(1)));
+
+    assert!(test_handle(&mut state, 132, "Reverb!", &[]).is_err());
+    assert!(test_handle(&mut state, 130, "", &[]).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_inherited_runners() {
+    use super::{test_handle, Runner};
+
+    let mut state = super::test_state();
+    state.on_base.push(Runner {
+        id: Uuid::from_u128(1),
+        pitcher: Uuid::from_u128(6),
+        base: 0,
+        earned: true,
+        shared: false,
+        inherited_by: None,
+    });
+    test_handle(
+        &mut state,
+        3,
+        "Player 9 is now pitching for the Home Team.",
+        &[9],
+    )
+    .unwrap();
+    assert_eq!(
+        state.game.home.stats[&Uuid::from_u128(9)].inherited_runners,
+        1
+    );
+
+    state.credit_run(Uuid::from_u128(1)).unwrap();
+    let starter = state.game.home.stats[&Uuid::from_u128(6)];
+    let reliever = state.game.home.stats[&Uuid::from_u128(9)];
+    assert_eq!(starter.earned_runs, 1);
+    assert_eq!(reliever.earned_runs, 0);
+    assert_eq!(reliever.inherited_runners_scored, 1);
+}
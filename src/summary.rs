@@ -1,26 +1,232 @@
-use crate::game::{Game, Kind, Stats};
-use crate::{seasons::Season, DB};
+use crate::game::{Game, Kind, Stats, Team};
+use crate::seasons::{Baseline, Season};
+use crate::{metrics, DB};
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use rocket::tokio::sync::Notify;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sled::transaction::{
-    ConflictableTransactionError, ConflictableTransactionResult, TransactionalTree,
+    ConflictableTransactionError, ConflictableTransactionResult, Transactional, TransactionalTree,
 };
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::Hash;
 use std::mem::{size_of, size_of_val};
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 use zerocopy::{AsBytes, FromBytes, LayoutVerified};
 
 pub const TREE: &str = "summary_v1";
 pub const SEASON_TREE: &str = "season_summary_v1";
+pub const DELTA_TREE: &str = "summary_deltas_v1";
+pub const DAILY_TREE: &str = "daily_summary_v1";
 
+/// Number of `league_totals` results kept in memory. Player pages look up the totals for every
+/// season row, so this covers the seasons of several recently viewed pages.
+const LEAGUE_TOTALS_CACHE_SIZE: usize = 64;
+
+/// Number of deltas applied per transaction by `fold`. During a rebuild, the folder task also
+/// waits for this many deltas to be queued before folding (see `should_fold`).
+pub const FOLD_BATCH: usize = 256;
+
+lazy_static::lazy_static! {
+    /// Notified whenever deltas are queued, to wake the folder task.
+    pub static ref FOLD: Notify = Notify::new();
+    static ref FOLD_LOCK: Mutex<()> = Mutex::new(());
+    static ref LEAGUE_TOTALS: Mutex<Lru<Season, Stats>> =
+        Mutex::new(Lru::new(LEAGUE_TOTALS_CACHE_SIZE));
+    static ref TEAM_BATTING_TOTALS: Mutex<Lru<Season, Arc<HashMap<Uuid, Stats>>>> =
+        Mutex::new(Lru::new(LEAGUE_TOTALS_CACHE_SIZE));
+}
+
+/// A game's stats waiting to be added to (or subtracted from) the summary trees.
+#[derive(Serialize, Deserialize)]
+struct Delta<G> {
+    remove: bool,
+    game: G,
+}
+
+/// Queues this game's stats to be added to the summary trees by `fold`. Every delta gets its own
+/// key, so processing games concurrently never conflicts on the summary trees.
 pub fn write_summary(
-    tree: &TransactionalTree,
-    season_tree: &TransactionalTree,
+    delta_tree: &TransactionalTree,
     game: &Game,
 ) -> ConflictableTransactionResult<(), serde_json::Error> {
-    if game.kind == Kind::Special {
-        return Ok(());
+    push_delta(delta_tree, game, false)
+}
+
+/// Reverses a prior `write_summary` call for this game.
+pub fn remove_summary(
+    delta_tree: &TransactionalTree,
+    game: &Game,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    push_delta(delta_tree, game, true)
+}
+
+fn push_delta(
+    delta_tree: &TransactionalTree,
+    game: &Game,
+    remove: bool,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    // big-endian so that deltas are folded in the order they were queued
+    let key = delta_tree.generate_id()?.to_be_bytes();
+    delta_tree.insert(
+        &key,
+        serde_json::to_vec(&Delta { remove, game }).map_err(ConflictableTransactionError::Abort)?,
+    )?;
+    Ok(())
+}
+
+/// Whether the folder task should fold now. While rebuilding, folding a handful of deltas at a
+/// time repeats the same reads and writes for every game, so deltas are left to accumulate into
+/// full batches; the start task folds whatever remains once every game is processed.
+pub fn should_fold(rebuilding: bool) -> Result<bool> {
+    Ok(!rebuilding || DB.open_tree(DELTA_TREE)?.iter().take(FOLD_BATCH).count() >= FOLD_BATCH)
+}
+
+/// Applies queued deltas to the summary trees, oldest first, `FOLD_BATCH` at a time. Only one
+/// fold runs at a time, so folding never contends with itself. Returns the number of deltas
+/// applied.
+pub fn fold() -> Result<usize> {
+    let _guard = FOLD_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let tree = DB.open_tree(TREE)?;
+    let season_tree = DB.open_tree(SEASON_TREE)?;
+    let delta_tree = DB.open_tree(DELTA_TREE)?;
+
+    let mut folded = 0;
+    loop {
+        let deltas = delta_tree
+            .iter()
+            .take(FOLD_BATCH)
+            .map(|row| {
+                let (key, value) = row?;
+                Ok((key, serde_json::from_slice::<Delta<Game>>(&value)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if deltas.is_empty() {
+            return Ok(folded);
+        }
+
+        // a delta is only removed in the same transaction that applies it, so deltas left over
+        // after a crash are replayed exactly once by the next fold
+        (&tree, &season_tree, &delta_tree).transaction(|(tree, season_tree, delta_tree)| {
+            let mut pending = Pending::default();
+            for (key, delta) in &deltas {
+                if delta.remove {
+                    update_summary(
+                        &mut pending,
+                        tree,
+                        season_tree,
+                        &delta.game,
+                        |value, stats| *value -= stats,
+                    )?;
+                } else {
+                    update_summary(
+                        &mut pending,
+                        tree,
+                        season_tree,
+                        &delta.game,
+                        |value, stats| *value += stats,
+                    )?;
+                }
+                delta_tree.remove(key)?;
+            }
+            pending.flush(tree, season_tree)
+        })?;
+        folded += deltas.len();
+        metrics::SUMMARY_DELTAS_FOLDED.add(deltas.len());
+        invalidate_league_totals();
+        crate::names::invalidate();
+        crate::resolve::invalidate();
+        crate::etag::invalidate();
     }
+}
+
+/// Summary rows touched by one batch of deltas. Each row is read from its tree at most once and
+/// written back once, in `flush`, rather than once per game.
+#[derive(Default)]
+struct Pending {
+    summary: BTreeMap<Vec<u8>, Value>,
+    season: BTreeMap<Vec<u8>, SeasonValue>,
+    weather: BTreeMap<Vec<u8>, WeatherValue>,
+    lineup: BTreeMap<Vec<u8>, LineupValue>,
+}
 
+impl Pending {
+    fn flush(
+        self,
+        tree: &TransactionalTree,
+        season_tree: &TransactionalTree,
+    ) -> ConflictableTransactionResult<(), serde_json::Error> {
+        for (key, value) in self.summary {
+            write_row(tree, key, &value, value.stats == Stats::default())?;
+        }
+        for (key, value) in self.season {
+            write_row(season_tree, key, &value, value.stats == Stats::default())?;
+        }
+        for (key, mut value) in self.weather.into_iter().chain(self.lineup) {
+            value.retain(|_, stats| *stats != Stats::default());
+            write_row(season_tree, key, &value, value.is_empty())?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a row back, or removes it if every stat in it has been subtracted back out to zero (as
+/// when the only game it counted is removed), so that removed games don't leave empty rows behind.
+fn write_row<V: Serialize>(
+    tree: &TransactionalTree,
+    key: Vec<u8>,
+    value: &V,
+    is_empty: bool,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    if is_empty {
+        tree.remove(key)?;
+    } else {
+        tree.insert(
+            key,
+            serde_json::to_vec(value).map_err(ConflictableTransactionError::Abort)?,
+        )?;
+    }
+    Ok(())
+}
+
+fn pending_entry<'a, V: DeserializeOwned>(
+    pending: &'a mut BTreeMap<Vec<u8>, V>,
+    tree: &TransactionalTree,
+    key: Vec<u8>,
+    default: impl FnOnce() -> V,
+) -> ConflictableTransactionResult<&'a mut V, serde_json::Error> {
+    Ok(match pending.entry(key) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let value = match tree.get(entry.key())? {
+                None => default(),
+                Some(value) => {
+                    serde_json::from_slice(&value).map_err(ConflictableTransactionError::Abort)?
+                }
+            };
+            entry.insert(value)
+        }
+    })
+}
+
+fn update_summary(
+    pending: &mut Pending,
+    tree: &TransactionalTree,
+    season_tree: &TransactionalTree,
+    game: &Game,
+    apply: impl Fn(&mut Stats, Stats),
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    // special games are kept in their own player and team rows of the season summary tree, so that
+    // they never count toward regular season leaderboards or league baselines
+    let is_regular = game.kind == Kind::Regular;
+    let player_kind = match game.kind {
+        Kind::Regular => Some(b'p'),
+        Kind::Special => Some(b's'),
+        Kind::Postseason => None,
+    };
     let mut totals = Stats::default();
 
     for team in game.teams() {
@@ -28,42 +234,24 @@ pub fn write_summary(
 
         for (id, stats) in team.stats.iter().map(|v| (*v.0, *v.1)) {
             for key in [
-                build_key(team.id, id, &game.season, game.is_postseason()),
-                build_key(id, team.id, &game.season, game.is_postseason()),
+                build_key(team.id, id, &game.season, game.kind),
+                build_key(id, team.id, &game.season, game.kind),
             ] {
-                let mut value = match tree.get(&key)? {
-                    None => Value::new(game.day),
-                    Some(value) => serde_json::from_slice(&value)
-                        .map_err(ConflictableTransactionError::Abort)?,
-                };
-                value.stats += stats;
-                tree.insert(
-                    key.as_slice(),
-                    serde_json::to_vec(&value)
-                        .map_err(ConflictableTransactionError::Abort)?
-                        .as_slice(),
-                )?;
+                let value =
+                    pending_entry(&mut pending.summary, tree, key, || Value::new(game.day))?;
+                apply(&mut value.stats, stats);
             }
 
-            if !game.is_postseason() {
-                let key = build_season_key(&game.season, b'p', id);
-                let mut value = match season_tree.get(&key)? {
-                    None => SeasonValue::default(),
-                    Some(value) => serde_json::from_slice(&value)
-                        .map_err(ConflictableTransactionError::Abort)?,
-                };
-                value.stats += stats;
+            if let Some(player_kind) = player_kind {
+                let key = build_season_key(&game.season, player_kind, id);
+                let value =
+                    pending_entry(&mut pending.season, season_tree, key, SeasonValue::default)?;
+                apply(&mut value.stats, stats);
                 value.team_id = team.id;
                 value.team_abbr = team.name.shorthand.clone();
                 if let Some(name) = team.player_names.get(&id) {
                     value.name = name.into();
                 }
-                season_tree.insert(
-                    key.as_slice(),
-                    serde_json::to_vec(&value)
-                        .map_err(ConflictableTransactionError::Abort)?
-                        .as_slice(),
-                )?;
             }
 
             team_totals += stats;
@@ -71,63 +259,73 @@ pub fn write_summary(
 
         team_totals.games_batted = 1;
         team_totals.games_pitched = 1;
-        let key = build_season_key(
-            &game.season,
-            if game.is_postseason() { b'u' } else { b't' },
-            team.id,
-        );
-        let mut value = match season_tree.get(&key)? {
-            None => SeasonValue::default(),
-            Some(value) => {
-                serde_json::from_slice(&value).map_err(ConflictableTransactionError::Abort)?
-            }
-        };
-        value.stats += team_totals;
+        let key = build_season_key(&game.season, team_totals_kind(game.kind), team.id);
+        let value = pending_entry(&mut pending.season, season_tree, key, SeasonValue::default)?;
+        apply(&mut value.stats, team_totals);
         value.team_id = team.id;
         value.team_abbr = team.name.shorthand.clone();
         value.name = team.name.nickname.clone();
-        season_tree.insert(
-            key.as_slice(),
-            serde_json::to_vec(&value)
-                .map_err(ConflictableTransactionError::Abort)?
-                .as_slice(),
-        )?;
+
+        if is_regular {
+            let key = build_season_key(&game.season, b'w', team.id);
+            let value = pending_entry(
+                &mut pending.weather,
+                season_tree,
+                key,
+                WeatherValue::default,
+            )?;
+            apply(value.entry(game.weather).or_default(), team_totals);
+
+            let key = build_season_key(&game.season, b'o', team.id);
+            let value = pending_entry(&mut pending.lineup, season_tree, key, LineupValue::default)?;
+            for (slot, stats) in lineup_slots(team) {
+                apply(value.entry(slot).or_default(), stats);
+            }
+        }
 
         totals += team_totals;
     }
 
+    if !is_regular {
+        return Ok(());
+    }
     totals.games_batted = 1;
     totals.games_pitched = 1;
     let key = build_season_key(&game.season, b'l', Uuid::default());
-    let mut value = match season_tree.get(&key)? {
-        None => SeasonValue::default(),
-        Some(value) => {
-            serde_json::from_slice(&value).map_err(ConflictableTransactionError::Abort)?
-        }
-    };
-    value.stats += totals;
-    season_tree.insert(
-        key.as_slice(),
-        serde_json::to_vec(&value)
-            .map_err(ConflictableTransactionError::Abort)?
-            .as_slice(),
-    )?;
+    let value = pending_entry(&mut pending.season, season_tree, key, SeasonValue::default)?;
+    apply(&mut value.stats, totals);
 
     Ok(())
 }
 
 // =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Summary {
     pub season: Season,
     pub first_day: u16,
     pub is_postseason: bool,
+    /// Stats from special games (exhibitions, prize matches, tournaments), which are only shown
+    /// on player and team pages when asked for
+    pub is_special: bool,
     pub player_id: Uuid,
     pub team_id: Uuid,
     pub stats: Stats,
 }
 
+impl Summary {
+    fn sort_key(&self) -> (&Season, u16, bool, bool, Uuid, Uuid) {
+        (
+            &self.season,
+            self.first_day,
+            self.is_postseason,
+            self.is_special,
+            self.player_id,
+            self.team_id,
+        )
+    }
+}
+
 pub fn player_summary(player_id: Uuid) -> Result<Vec<Summary>> {
     load_summary(player_id, true, None)
 }
@@ -136,6 +334,25 @@ pub fn team_summary(team_id: Uuid, season: &Season) -> Result<Vec<Summary>> {
     load_summary(team_id, false, Some(season))
 }
 
+/// Returns each player's regular season totals with a franchise, given the franchise's team ID in
+/// each season (see `TeamName::all_seasons`).
+pub fn franchise_player_totals(seasons: &[(Season, Uuid)]) -> Result<BTreeMap<Uuid, Stats>> {
+    let team_ids = seasons.iter().map(|(_, id)| *id).collect::<BTreeSet<_>>();
+    let mut totals = BTreeMap::<Uuid, Stats>::new();
+    for team_id in team_ids {
+        for row in load_summary(team_id, false, None)? {
+            if row.is_postseason
+                || row.is_special
+                || !seasons.contains(&(row.season.clone(), team_id))
+            {
+                continue;
+            }
+            *totals.entry(row.player_id).or_default() += row.stats;
+        }
+    }
+    Ok(totals)
+}
+
 fn load_summary(
     scan_id: Uuid,
     scan_id_is_player: bool,
@@ -167,12 +384,13 @@ fn load_summary(
             player_id: Uuid::from_bytes(player_id),
             team_id: Uuid::from_bytes(team_id),
             season,
-            is_postseason: prefix.is_postseason > 0,
+            is_postseason: prefix.kind == KIND_POSTSEASON,
+            is_special: prefix.kind == KIND_SPECIAL,
             stats: value.stats,
             first_day: value.first_day,
         });
     }
-    v.sort_unstable();
+    v.sort_unstable_by(|a, b| a.sort_key().cmp(&b.sort_key()));
     Ok(v)
 }
 
@@ -182,17 +400,25 @@ struct KeyPrefix {
     scan_id: [u8; 16],
     other_id: [u8; 16],
     season: u16,
-    is_postseason: u16,
+    kind: u16,
 }
 
-fn build_key(scan_id: Uuid, other_id: Uuid, season: &Season, is_postseason: bool) -> Vec<u8> {
+const KIND_REGULAR: u16 = 0;
+const KIND_POSTSEASON: u16 = 1;
+const KIND_SPECIAL: u16 = 2;
+
+fn build_key(scan_id: Uuid, other_id: Uuid, season: &Season, kind: Kind) -> Vec<u8> {
     let mut key = Vec::with_capacity(size_of::<KeyPrefix>() + season.sim.len());
     key.extend_from_slice(
         KeyPrefix {
             scan_id: *scan_id.as_bytes(),
             other_id: *other_id.as_bytes(),
             season: season.season,
-            is_postseason: if is_postseason { 1 } else { 0 },
+            kind: match kind {
+                Kind::Regular => KIND_REGULAR,
+                Kind::Postseason => KIND_POSTSEASON,
+                Kind::Special => KIND_SPECIAL,
+            },
         }
         .as_bytes(),
     );
@@ -217,7 +443,7 @@ impl Value {
 
 // =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct SeasonSummary {
     pub name: String,
     pub id: Uuid,
@@ -226,6 +452,19 @@ pub struct SeasonSummary {
     pub stats: Stats,
 }
 
+impl SeasonSummary {
+    /// Rows are ordered by name, then ID, so that rows with the same name are always in the same
+    /// order across rebuilds (and in exports). Sorting a season table by a stat keeps this order
+    /// among rows with equal stats (see `Table::sort_by`), giving stat, then name, then ID.
+    fn sort_key(&self) -> (&str, Uuid) {
+        (&self.name, self.id)
+    }
+}
+
+fn sort_season_summary(v: &mut [SeasonSummary]) {
+    v.sort_unstable_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+}
+
 pub fn season_player_summary(season: &Season) -> Result<Vec<SeasonSummary>> {
     season_summary(season, b'p')
 }
@@ -234,6 +473,109 @@ pub fn season_team_summary(season: &Season) -> Result<Vec<SeasonSummary>> {
     season_summary(season, b't')
 }
 
+pub fn season_postseason_team_summary(season: &Season) -> Result<Vec<SeasonSummary>> {
+    season_summary(season, b'u')
+}
+
+/// Player rows from special games (exhibitions, prize matches, tournaments).
+pub fn season_exhibition_player_summary(season: &Season) -> Result<Vec<SeasonSummary>> {
+    season_summary(season, b's')
+}
+
+/// Team rows from special games (exhibitions, prize matches, tournaments).
+pub fn season_exhibition_team_summary(season: &Season) -> Result<Vec<SeasonSummary>> {
+    season_summary(season, team_totals_kind(Kind::Special))
+}
+
+/// Whether any regular season games were recorded for a season. Seasons without any are
+/// tournaments, made up entirely of special games.
+pub fn has_regular_season(season: &Season) -> Result<bool> {
+    Ok(season_league_totals(season)?.games_batted > 0)
+}
+
+/// Returns the regular season rows for every player on the team's roster, in the same form as
+/// `season_player_summary`. Unlike that summary, a player who was traded away still has a row for
+/// the games they played for this team.
+pub fn team_player_summary(team_id: Uuid, season: &Season) -> Result<Vec<SeasonSummary>> {
+    let team_abbr = crate::names::team_name(team_id)?
+        .map(|name| name.shorthand)
+        .unwrap_or_default();
+    let mut stats: BTreeMap<Uuid, Stats> = BTreeMap::new();
+    for row in team_summary(team_id, season)? {
+        if !row.is_postseason && !row.is_special {
+            *stats.entry(row.player_id).or_default() += row.stats;
+        }
+    }
+    let mut v = Vec::with_capacity(stats.len());
+    for (id, stats) in stats {
+        v.push(SeasonSummary {
+            name: crate::names::player_name(id)?.unwrap_or_default(),
+            id,
+            team_id,
+            team_abbr: team_abbr.clone(),
+            stats,
+        });
+    }
+    sort_season_summary(&mut v);
+    Ok(v)
+}
+
+/// A player's regular season totals across several seasons.
+#[derive(Debug)]
+pub struct CareerSummary {
+    pub name: String,
+    pub id: Uuid,
+    /// Number of seasons the player recorded stats in
+    pub seasons: u16,
+    pub stats: Stats,
+    /// The league totals for each of the player's season rows, summed the same way as on player
+    /// pages
+    pub league: Stats,
+}
+
+/// Returns every player's regular season totals across `seasons`, ordered by name, then ID. Each
+/// player is listed under the name from the last of these seasons they played in.
+///
+/// Only the player list is read up front; each player's totals are read as the iterator reaches
+/// them, so the whole export is never held in memory.
+pub fn career_player_summary(
+    seasons: Vec<Season>,
+) -> Result<impl Iterator<Item = Result<CareerSummary>>> {
+    let mut names = HashMap::new();
+    let mut leagues = Vec::with_capacity(seasons.len());
+    for season in &seasons {
+        for row in season_player_summary(season)? {
+            names.insert(row.id, row.name);
+        }
+        leagues.push(league_totals(season)?);
+    }
+    let mut players = names
+        .into_iter()
+        .map(|(id, name)| (name, id))
+        .collect::<Vec<_>>();
+    players.sort_unstable();
+
+    let tree = DB.open_tree(SEASON_TREE)?;
+    Ok(players.into_iter().map(move |(name, id)| {
+        let mut career = CareerSummary {
+            name,
+            id,
+            seasons: 0,
+            stats: Stats::default(),
+            league: Stats::default(),
+        };
+        for (season, league) in seasons.iter().zip(&leagues) {
+            if let Some(value) = tree.get(build_season_key(season, b'p', id))? {
+                let value: SeasonValue = serde_json::from_slice(&value)?;
+                career.seasons += 1;
+                career.stats += value.stats;
+                career.league += *league;
+            }
+        }
+        Ok(career)
+    }))
+}
+
 fn season_summary(season: &Season, kind: u8) -> Result<Vec<SeasonSummary>> {
     let mut v = Vec::new();
     let tree = DB.open_tree(SEASON_TREE)?;
@@ -254,13 +596,109 @@ fn season_summary(season: &Season, kind: u8) -> Result<Vec<SeasonSummary>> {
             stats: value.stats,
         });
     }
-    v.sort_unstable();
+    sort_season_summary(&mut v);
     Ok(v)
 }
 
-pub fn team_totals(season: &Season, team_id: Uuid, is_postseason: bool) -> Result<Stats> {
+/// Minimum plate appearances per team game for a batter to qualify, in tenths (3.1 per game).
+const QUALIFYING_PA_TENTHS_PER_GAME: u32 = 31;
+/// Minimum outs recorded per team game for a pitcher to qualify (one inning pitched per game).
+const QUALIFYING_OUTS_PER_GAME: u32 = 3;
+
+/// Decides whether a player has played enough for their rate stats to be meaningful, relative to
+/// the number of regular season games their current team has played.
+pub struct Qualifier(Option<HashMap<Uuid, u32>>);
+
+impl Qualifier {
+    /// If `enabled` is false, every player qualifies.
+    pub fn new(season: &Season, enabled: bool) -> Result<Qualifier> {
+        Ok(Qualifier(if enabled {
+            Some(
+                season_team_summary(season)?
+                    .into_iter()
+                    .map(|team| (team.id, team.stats.games_batted))
+                    .collect(),
+            )
+        } else {
+            None
+        }))
+    }
+
+    fn team_games(&self, row: &SeasonSummary) -> Option<u32> {
+        self.0
+            .as_ref()
+            .map(|games| games.get(&row.team_id).copied().unwrap_or_default())
+    }
+
+    pub fn batting(&self, row: &SeasonSummary) -> bool {
+        self.team_games(row).map_or(true, |games| {
+            row.stats.plate_appearances * 10 >= games * QUALIFYING_PA_TENTHS_PER_GAME
+        })
+    }
+
+    pub fn pitching(&self, row: &SeasonSummary) -> bool {
+        self.team_games(row).map_or(true, |games| {
+            row.stats.outs_recorded >= games * QUALIFYING_OUTS_PER_GAME
+        })
+    }
+}
+
+/// Returns the IDs of every player and every team with a season summary.
+pub fn recorded_ids() -> Result<(BTreeSet<Uuid>, BTreeSet<Uuid>)> {
+    let mut players = BTreeSet::new();
+    let mut teams = BTreeSet::new();
+    for row in DB.open_tree(SEASON_TREE)?.iter() {
+        let (key, value) = row?;
+        let (kind, id) = key.split_at(key.len() - size_of::<Uuid>());
+        match kind.last() {
+            Some(b'p') => {
+                players.insert(Uuid::from_slice(id)?);
+                teams.insert(serde_json::from_slice::<SeasonValue>(&value)?.team_id);
+            }
+            Some(b't') | Some(b'u') | Some(b'x') => {
+                teams.insert(Uuid::from_slice(id)?);
+            }
+            _ => {}
+        }
+    }
+    Ok((players, teams))
+}
+
+/// Fills in season summary rows recorded without a name (a player missing from every box score's
+/// `player_names`), looking up a player's name or a team's nickname with `name`, which is passed
+/// the ID and whether it's a team. Returns the number of rows filled in.
+pub fn repair_names(mut name: impl FnMut(Uuid, bool) -> Result<Option<String>>) -> Result<usize> {
+    let tree = DB.open_tree(SEASON_TREE)?;
+    let mut repaired = 0;
+    for row in tree.iter() {
+        let (key, old) = row?;
+        let (kind, id) = key.split_at(key.len() - size_of::<Uuid>());
+        let is_team = match kind.last() {
+            Some(b'p') | Some(b's') => false,
+            Some(b't') | Some(b'u') | Some(b'x') => true,
+            _ => continue,
+        };
+        let mut value: SeasonValue = serde_json::from_slice(&old)?;
+        if !value.name.is_empty() {
+            continue;
+        }
+        if let Some(found) = name(Uuid::from_slice(id)?, is_team)? {
+            value.name = found;
+            // a fold that changed the row since it was read has the final say
+            if tree
+                .compare_and_swap(&key, Some(old), Some(serde_json::to_vec(&value)?))?
+                .is_ok()
+            {
+                repaired += 1;
+            }
+        }
+    }
+    Ok(repaired)
+}
+
+pub fn team_totals(season: &Season, team_id: Uuid, kind: Kind) -> Result<Stats> {
     let tree = DB.open_tree(SEASON_TREE)?;
-    let key = build_season_key(season, if is_postseason { b'u' } else { b't' }, team_id);
+    let key = build_season_key(season, team_totals_kind(kind), team_id);
     Ok(match tree.get(&key)? {
         None => SeasonValue::default(),
         Some(value) => serde_json::from_slice(&value)?,
@@ -268,7 +706,132 @@ pub fn team_totals(season: &Season, team_id: Uuid, is_postseason: bool) -> Resul
     .stats)
 }
 
+fn team_totals_kind(kind: Kind) -> u8 {
+    match kind {
+        Kind::Regular => b't',
+        Kind::Postseason => b'u',
+        Kind::Special => b'x',
+    }
+}
+
+/// Returns a team's regular season totals in each weather it has played in.
+pub fn team_weather_totals(season: &Season, team_id: Uuid) -> Result<BTreeMap<u16, Stats>> {
+    let tree = DB.open_tree(SEASON_TREE)?;
+    let key = build_season_key(season, b'w', team_id);
+    let mut totals: WeatherValue = match tree.get(&key)? {
+        None => WeatherValue::default(),
+        Some(value) => serde_json::from_slice(&value)?,
+    };
+    // removed games leave behind empty entries
+    totals.retain(|_, stats| stats.games_batted > 0);
+    Ok(totals)
+}
+
+/// Returns a team's regular season batting totals for each lineup slot (0 for the leadoff
+/// spot), counting everyone who batted in that slot.
+pub fn team_lineup_totals(season: &Season, team_id: Uuid) -> Result<BTreeMap<u16, Stats>> {
+    let tree = DB.open_tree(SEASON_TREE)?;
+    let key = build_season_key(season, b'o', team_id);
+    let mut totals: LineupValue = match tree.get(&key)? {
+        None => LineupValue::default(),
+        Some(value) => serde_json::from_slice(&value)?,
+    };
+    // removed games leave behind empty entries
+    totals.retain(|_, stats| stats.games_batted > 0);
+    Ok(totals)
+}
+
+/// Sums the stats of the players in each of a team's lineup slots in one game. A player who
+/// moved between slots during the game is counted in the first slot they appeared in.
+fn lineup_slots(team: &Team) -> Vec<(u16, Stats)> {
+    let mut seen = BTreeSet::new();
+    let mut slots = Vec::new();
+    for (slot, position) in team.lineup.iter().enumerate() {
+        let mut stats = Stats::default();
+        for player in position {
+            if seen.insert(player) {
+                stats += team.stats.get(player).copied().unwrap_or_default();
+            }
+        }
+        if stats.is_batting() {
+            stats.games_batted = 1;
+            slots.push((slot as u16, stats));
+        }
+    }
+    slots
+}
+
+/// Returns the league baseline for adjusted stats (OPS+, ERA+, FIP), according to the season's
+/// configured `Baseline`. Only regular season games are counted.
+///
+/// Results are cached in memory until the summary trees next change.
 pub fn league_totals(season: &Season) -> Result<Stats> {
+    let generation = {
+        let mut cache = LEAGUE_TOTALS.lock().unwrap();
+        if let Some(totals) = cache.get(season) {
+            metrics::LEAGUE_TOTALS_CACHE_HITS.inc();
+            return Ok(totals);
+        }
+        cache.generation
+    };
+    metrics::LEAGUE_TOTALS_CACHE_MISSES.inc();
+    let totals = load_league_totals(season)?;
+    LEAGUE_TOTALS
+        .lock()
+        .unwrap()
+        .insert(generation, season.clone(), totals);
+    Ok(totals)
+}
+
+/// Returns each team's regular season totals, by team ID, for opponent quality adjustments (see
+/// `opponents`).
+///
+/// Results are cached in memory until the summary trees next change.
+pub fn team_batting_totals(season: &Season) -> Result<Arc<HashMap<Uuid, Stats>>> {
+    let generation = {
+        let mut cache = TEAM_BATTING_TOTALS.lock().unwrap();
+        if let Some(totals) = cache.get(season) {
+            return Ok(totals);
+        }
+        cache.generation
+    };
+    let totals = Arc::new(
+        season_team_summary(season)?
+            .into_iter()
+            .map(|row| (row.id, row.stats))
+            .collect::<HashMap<_, _>>(),
+    );
+    TEAM_BATTING_TOTALS
+        .lock()
+        .unwrap()
+        .insert(generation, season.clone(), totals.clone());
+    Ok(totals)
+}
+
+/// Drops all cached `league_totals` and `team_batting_totals` results. Called whenever the summary
+/// trees are written to or cleared.
+pub fn invalidate_league_totals() {
+    LEAGUE_TOTALS.lock().unwrap().clear();
+    TEAM_BATTING_TOTALS.lock().unwrap().clear();
+}
+
+fn load_league_totals(season: &Season) -> Result<Stats> {
+    match season.baseline() {
+        Baseline::Season => season_league_totals(season),
+        Baseline::Era => {
+            let era = season.era_name()?;
+            let mut totals = Stats::default();
+            for other in Season::recorded()? {
+                if other.sim == season.sim && other.era_name()? == era {
+                    totals += season_league_totals(&other)?;
+                }
+            }
+            Ok(totals)
+        }
+    }
+}
+
+fn season_league_totals(season: &Season) -> Result<Stats> {
     let tree = DB.open_tree(SEASON_TREE)?;
     let key = build_season_key(season, b'l', Uuid::default());
     Ok(match tree.get(&key)? {
@@ -289,6 +852,151 @@ fn build_season_key(season: &Season, kind: u8, id: Uuid) -> Vec<u8> {
     key
 }
 
+// =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=   =^..^=
+
+/// A player's line from one game, for the daily performance strips on player pages.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Daily {
+    pub game_id: Uuid,
+    pub team_id: Uuid,
+    #[serde(flatten)]
+    pub season: Season,
+    pub day: u16,
+    pub kind: Kind,
+    pub plate_appearances: u32,
+    pub at_bats: u32,
+    pub hits: u32,
+    pub home_runs: u32,
+    pub is_pitching: bool,
+    pub outs_recorded: u32,
+    pub earned_runs: u32,
+    #[serde(default)]
+    pub substitute: bool,
+}
+
+/// Writes a daily row for each player in this game, keyed by player and game. Unlike the other
+/// summary trees, rows are written directly rather than through deltas, since no two games ever
+/// share a row.
+pub fn write_daily(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    for team in game.teams() {
+        for (player_id, stats) in &team.stats {
+            let daily = Daily {
+                game_id: id,
+                team_id: team.id,
+                season: game.season.clone(),
+                day: game.day,
+                kind: game.kind,
+                plate_appearances: stats.plate_appearances,
+                at_bats: stats.at_bats,
+                hits: stats.hits(),
+                home_runs: stats.home_runs,
+                is_pitching: stats.is_pitching(),
+                outs_recorded: stats.outs_recorded,
+                earned_runs: stats.earned_runs,
+                substitute: stats.games_as_substitute > 0,
+            };
+            tree.insert(
+                build_daily_key(*player_id, id),
+                serde_json::to_vec(&daily).map_err(ConflictableTransactionError::Abort)?,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Reverses a prior `write_daily` call for this game.
+pub fn remove_daily(
+    tree: &TransactionalTree,
+    game: &Game,
+    id: Uuid,
+) -> ConflictableTransactionResult<(), serde_json::Error> {
+    for team in game.teams() {
+        for player_id in team.stats.keys() {
+            tree.remove(build_daily_key(*player_id, id))?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns a player's daily rows, in chronological order.
+pub fn player_daily(player_id: Uuid) -> Result<Vec<Daily>> {
+    let mut rows = DB
+        .open_tree(DAILY_TREE)?
+        .scan_prefix(player_id.as_bytes())
+        .values()
+        .map(|value| Ok(serde_json::from_slice::<Daily>(&value?)?))
+        .collect::<Result<Vec<_>>>()?;
+    rows.sort_by(|a, b| a.season.cmp(&b.season).then(a.day.cmp(&b.day)));
+    Ok(rows)
+}
+
+fn build_daily_key(player_id: Uuid, game_id: Uuid) -> Vec<u8> {
+    let mut key = Vec::with_capacity(size_of_val(&player_id) + size_of_val(&game_id));
+    key.extend_from_slice(player_id.as_bytes());
+    key.extend_from_slice(game_id.as_bytes());
+    key
+}
+
+/// A small least-recently-used cache. Clearing it bumps its generation, so that a value loaded
+/// before the clear isn't inserted afterward.
+struct Lru<K, V> {
+    capacity: usize,
+    generation: u64,
+    tick: u64,
+    entries: HashMap<K, (u64, V)>,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> Lru<K, V> {
+    fn new(capacity: usize) -> Lru<K, V> {
+        Lru {
+            capacity,
+            generation: 0,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<V> {
+        self.tick += 1;
+        let (used, value) = self.entries.get_mut(key)?;
+        *used = self.tick;
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, generation: u64, key: K, value: V) {
+        if generation != self.generation {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (used, _))| *used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(key, (self.tick, value));
+    }
+
+    fn clear(&mut self) {
+        self.generation += 1;
+        self.entries.clear();
+    }
+}
+
+/// Team totals by weather ID, stored under the `w` kind in `SEASON_TREE`.
+type WeatherValue = BTreeMap<u16, Stats>;
+
+/// Team batting totals by lineup slot, stored under the `o` kind in `SEASON_TREE`.
+type LineupValue = BTreeMap<u16, Stats>;
+
 #[derive(Serialize, Deserialize, Default)]
 struct SeasonValue {
     stats: Stats,
@@ -296,3 +1004,189 @@ struct SeasonValue {
     team_id: Uuid,
     team_abbr: String,
 }
+
+#[cfg(test)]
+#[test]
+fn test_season_summary_order() {
+    use crate::export::{Export, WithLeagueStats};
+
+    let row = |name: &str, id: u128, singles: u32| SeasonSummary {
+        name: name.into(),
+        id: Uuid::from_u128(id),
+        team_id: Uuid::default(),
+        team_abbr: String::new(),
+        stats: Stats {
+            plate_appearances: 4,
+            at_bats: 4,
+            singles,
+            ..Default::default()
+        },
+    };
+    let export = |v: &[SeasonSummary]| {
+        serde_json::to_vec(
+            &v.iter()
+                .map(|row| {
+                    (
+                        row.id,
+                        Export(WithLeagueStats {
+                            inner: row.stats,
+                            league: Stats::default(),
+                        }),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )
+        .unwrap()
+    };
+
+    let mut a = vec![row("Bo", 3, 1), row("Al", 2, 2), row("Al", 1, 3)];
+    let mut b = vec![row("Al", 1, 3), row("Bo", 3, 1), row("Al", 2, 2)];
+    sort_season_summary(&mut a);
+    sort_season_summary(&mut b);
+    assert_eq!(
+        a.iter().map(|row| row.id.as_u128()).collect::<Vec<_>>(),
+        [1, 2, 3]
+    );
+    assert_eq!(export(&a), export(&b));
+}
+
+#[cfg(test)]
+#[test]
+fn test_season_table_order() {
+    use crate::table::Table;
+
+    let row = |name: &str, id: u128, home_runs: u32| SeasonSummary {
+        name: name.into(),
+        id: Uuid::from_u128(id),
+        team_id: Uuid::default(),
+        team_abbr: String::new(),
+        stats: Stats {
+            home_runs,
+            ..Default::default()
+        },
+    };
+    let order = |mut v: Vec<SeasonSummary>| {
+        sort_season_summary(&mut v);
+        let mut table = Table::new([("ID", ""), ("Home Runs", "HR")], "", "");
+        for row in &v {
+            table.push([(row.id.as_u128() as u32).into(), row.stats.home_runs.into()]);
+        }
+        table.sort_by(1, true);
+        table
+            .rows
+            .iter()
+            .map(|row| row.data[0].to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let a = order(vec![
+        row("Bo", 4, 2),
+        row("Al", 3, 2),
+        row("Cy", 5, 9),
+        row("Al", 2, 2),
+        row("Al", 1, 0),
+    ]);
+    let b = order(vec![
+        row("Al", 2, 2),
+        row("Al", 1, 0),
+        row("Bo", 4, 2),
+        row("Cy", 5, 9),
+        row("Al", 3, 2),
+    ]);
+    assert_eq!(a, ["5", "2", "3", "4", "1"]);
+    assert_eq!(a, b);
+}
+
+#[cfg(test)]
+#[test]
+fn test_flush_removes_empty_rows() {
+    let db = sled::Config::new().temporary(true).open().unwrap();
+    let tree = db.open_tree("summary").unwrap();
+    let season_tree = db.open_tree("season").unwrap();
+    for key in [b"empty", b"kept!"] {
+        tree.insert(key, serde_json::to_vec(&Value::new(0)).unwrap())
+            .unwrap();
+        season_tree
+            .insert(key, serde_json::to_vec(&SeasonValue::default()).unwrap())
+            .unwrap();
+    }
+
+    let hit = Stats {
+        singles: 1,
+        ..Default::default()
+    };
+    let pending = || {
+        let mut pending = Pending::default();
+        pending.summary.insert(b"empty".to_vec(), Value::new(0));
+        pending.summary.insert(
+            b"kept!".to_vec(),
+            Value {
+                stats: hit,
+                first_day: 0,
+            },
+        );
+        pending
+            .season
+            .insert(b"empty".to_vec(), SeasonValue::default());
+        pending.weather.insert(
+            b"kept!".to_vec(),
+            [(1, Stats::default()), (2, hit)].into_iter().collect(),
+        );
+        pending
+    };
+    (&tree, &season_tree)
+        .transaction(|(tree, season_tree)| pending().flush(tree, season_tree))
+        .unwrap();
+
+    assert!(!tree.contains_key(b"empty").unwrap());
+    assert!(tree.contains_key(b"kept!").unwrap());
+    assert!(!season_tree.contains_key(b"empty").unwrap());
+    let weather: WeatherValue =
+        serde_json::from_slice(&season_tree.get(b"kept!").unwrap().unwrap()).unwrap();
+    assert_eq!(weather.keys().copied().collect::<Vec<_>>(), [2]);
+}
+
+#[cfg(test)]
+#[test]
+fn test_lru() {
+    let mut lru = Lru::new(2);
+    lru.insert(0, 'a', 1);
+    lru.insert(0, 'b', 2);
+    assert_eq!(lru.get(&'a'), Some(1));
+    // 'b' is least recently used
+    lru.insert(0, 'c', 3);
+    assert_eq!(lru.get(&'b'), None);
+    assert_eq!(lru.get(&'a'), Some(1));
+    assert_eq!(lru.get(&'c'), Some(3));
+
+    // values loaded before a clear are dropped
+    lru.clear();
+    lru.insert(0, 'a', 1);
+    assert_eq!(lru.get(&'a'), None);
+    lru.insert(1, 'a', 1);
+    assert_eq!(lru.get(&'a'), Some(1));
+}
+
+#[cfg(test)]
+#[test]
+fn test_lineup_slots() {
+    let batted = |plate_appearances| Stats {
+        games_batted: 1,
+        plate_appearances,
+        at_bats: plate_appearances,
+        ..Default::default()
+    };
+    let (a, b, c) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+    let mut team = Team::default();
+    team.stats.insert(a, batted(4));
+    team.stats.insert(b, batted(3));
+    team.stats.insert(c, batted(1));
+    // `c` pinch hit for `b`, then `a` was shuffled into the second slot
+    team.lineup = vec![vec![a], vec![b, c, a]];
+
+    let slots = lineup_slots(&team);
+    assert_eq!(slots.len(), 2);
+    assert_eq!((slots[0].0, slots[0].1.plate_appearances), (0, 4));
+    assert_eq!((slots[1].0, slots[1].1.plate_appearances), (1, 4));
+    assert_eq!(slots[1].1.games_batted, 1);
+}
@@ -1,6 +1,7 @@
 use crate::percentage::Pct;
 use derive_more::{Display, From};
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::ops::{Deref, DerefMut};
 
 #[macro_export]
@@ -25,6 +26,10 @@ pub struct Table<const N: usize> {
     pub sort_method: [&'static str; N],
     // (cells, first cell class)
     pub rows: Vec<Row<N>>,
+    /// Header rows to display before the row at each index
+    pub groups: Vec<(usize, String)>,
+    /// Links for column headers, for tables sorted server-side
+    pub header_href: Vec<String>,
 }
 
 impl<const N: usize> Table<N>
@@ -50,6 +55,8 @@ where
             col_class: [col_class; N],
             sort_method: [sort_method; N],
             rows: Vec::new(),
+            groups: Vec::new(),
+            header_href: Vec::new(),
         }
     }
 
@@ -60,6 +67,16 @@ where
         });
     }
 
+    /// Adds a row of totals for the group of rows above it.
+    pub fn push_subtotal(&mut self, data: [Value; N]) {
+        self.rows.push(Row {
+            data,
+            subtotal: true,
+            ..Default::default()
+        });
+    }
+
+    /// Inserts columns from `other` at `index`. Group headers are kept from `self`.
     pub fn insert<const M: usize, const Z: usize>(self, index: usize, other: Table<M>) -> Table<Z>
     where
         [Value; Z]: Default,
@@ -78,6 +95,8 @@ where
                 .zip(other.rows)
                 .map(|(a, b)| a.insert(index, b))
                 .collect(),
+            groups: self.groups,
+            header_href: Vec::new(),
         }
     }
 }
@@ -95,28 +114,128 @@ impl<const N: usize> Table<N> {
         }
     }
 
-    pub fn skip(&mut self, column: &str) -> &mut Table<N> {
-        if let Some(index) = self
-            .abbr
+    /// Starts a new group of rows with a header row.
+    pub fn group(&mut self, name: impl ToString) {
+        self.groups.push((self.rows.len(), name.to_string()));
+    }
+
+    pub fn group_header(&self, index: &usize) -> Option<&str> {
+        self.groups
+            .iter()
+            .find(|(i, _)| i == index)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Links to the glossary entry for a column, looked up by its abbreviation.
+    pub fn glossary_href(&self, index: &usize) -> String {
+        crate::glossary::anchor(&self.abbr[*index])
+            .map(|id| format!("/glossary#{}", id))
+            .unwrap_or_default()
+    }
+
+    pub fn header_href(&self, index: &usize) -> &str {
+        self.header_href.get(*index).map_or("", String::as_str)
+    }
+
+    pub fn visible_columns(&self) -> usize {
+        N - self.skip.len()
+    }
+
+    fn column(&self, column: &str) -> Option<usize> {
+        self.abbr
             .iter()
             .position(|x| x == column)
             .or_else(|| self.header.iter().position(|x| x == column))
-        {
+    }
+
+    /// Finds a column by its abbreviation or header, ignoring case, as given in a query string.
+    pub fn column_ignore_case(&self, column: &str) -> Option<usize> {
+        self.abbr
+            .iter()
+            .position(|x| !x.is_empty() && x.eq_ignore_ascii_case(column))
+            .or_else(|| {
+                self.header
+                    .iter()
+                    .position(|x| x.eq_ignore_ascii_case(column))
+            })
+    }
+
+    /// Sorts rows by a column. Numbers sort numerically and ahead of text, undefined rates (such
+    /// as a batting average with no at bats) and empty cells always sort last, and ties keep their
+    /// order. Don't use this on tables with groups.
+    pub fn sort_by(&mut self, index: usize, descending: bool) -> &mut Table<N> {
+        let mut keyed = self
+            .rows
+            .drain(..)
+            .map(|row| (SortKey::new(&row.data[index]), row))
+            .collect::<Vec<_>>();
+        keyed.sort_by(|(a, _), (b, _)| a.cmp(b, descending));
+        self.rows = keyed.into_iter().map(|(_, row)| row).collect();
+        self
+    }
+
+    /// Keeps only the rows on a page (starting from 1) of `per_page` rows. Returns the number of
+    /// pages, which is at least 1.
+    pub fn paginate(&mut self, page: usize, per_page: usize) -> usize {
+        let pages = ((self.rows.len() + per_page - 1) / per_page).max(1);
+        let start = page.saturating_sub(1).saturating_mul(per_page);
+        self.rows = self.rows.drain(..).skip(start).take(per_page).collect();
+        pages
+    }
+
+    pub fn skip(&mut self, column: &str) -> &mut Table<N> {
+        if let Some(index) = self.column(column) {
             self.skip.push(index);
         }
         self
     }
 
+    /// Marks each cell in a rate stat column with a `heat-1` (worst) through `heat-5` (best) class
+    /// by its quintile among the rows of the table, for optional heat map coloring. Only use this
+    /// on tables whose rows make up a league, such as season pages.
+    pub fn heat(&mut self, column: &str, higher_is_better: bool) -> &mut Table<N> {
+        let index = match self.column(column) {
+            Some(index) => index,
+            None => return self,
+        };
+        let mut values = self
+            .rows
+            .iter()
+            .filter_map(|row| row.data[index].to_f64())
+            .collect::<Vec<_>>();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        if values.len() < HEAT.len() {
+            return self;
+        }
+
+        for row in &mut self.rows {
+            if let Some(value) = row.data[index].to_f64() {
+                // tied values share the quintile of the lowest-ranked of them
+                let rank = if higher_is_better {
+                    values.partition_point(|x| *x < value)
+                } else {
+                    values.len() - values.partition_point(|x| *x <= value)
+                };
+                row.heat[index] = HEAT[rank * HEAT.len() / values.len()];
+            }
+        }
+        self
+    }
+
     pub fn not_skip(&self, index: &usize) -> bool {
         !self.skip.contains(index)
     }
 }
 
+const HEAT: [&str; 5] = ["heat-1", "heat-2", "heat-3", "heat-4", "heat-5"];
+
 #[derive(Debug)]
 pub struct Row<const N: usize> {
     pub data: [Value; N],
     pub href: [String; N],
+    pub heat: [&'static str; N],
     pub class: &'static str,
+    pub subtotal: bool,
 }
 
 impl<const N: usize> Row<N>
@@ -128,11 +247,14 @@ where
     where
         [Value; Z]: Default,
         [String; Z]: Default,
+        [&'static str; Z]: Default,
     {
         Row {
             data: array_insert(self.data, other.data, index),
             href: array_insert(self.href, other.href, index),
+            heat: array_insert(self.heat, other.heat, index),
             class: self.class,
+            subtotal: self.subtotal || other.subtotal,
         }
     }
 }
@@ -146,7 +268,9 @@ where
         Row {
             data: Default::default(),
             href: Default::default(),
+            heat: [""; N],
             class: "",
+            subtotal: false,
         }
     }
 }
@@ -174,6 +298,22 @@ impl Value {
             Value::Usize(x) => x.to_string().into(),
         }
     }
+
+    /// Returns the value of a finite rate stat.
+    fn to_f64(&self) -> Option<f64> {
+        let value = match self {
+            Value::Pct0(Pct(frac))
+            | Value::Pct1(Pct(frac))
+            | Value::Pct2(Pct(frac))
+            | Value::Pct3(Pct(frac)) => frac.to_f64(),
+            _ => return None,
+        };
+        if value.is_finite() {
+            Some(value)
+        } else {
+            None
+        }
+    }
 }
 
 impl Default for Value {
@@ -188,6 +328,41 @@ impl From<&str> for Value {
     }
 }
 
+enum SortKey {
+    Number(f64),
+    Text(String),
+    Missing,
+}
+
+impl SortKey {
+    fn new(value: &Value) -> SortKey {
+        let s = value.sort_value();
+        match s.parse::<f64>() {
+            Ok(n) if n.is_nan() => SortKey::Missing,
+            Ok(n) => SortKey::Number(n),
+            Err(_) if s.is_empty() => SortKey::Missing,
+            Err(_) => SortKey::Text(s.to_lowercase()),
+        }
+    }
+
+    fn cmp(&self, other: &SortKey, descending: bool) -> Ordering {
+        let ordering = match (self, other) {
+            (SortKey::Missing, SortKey::Missing) => return Ordering::Equal,
+            (SortKey::Missing, _) => return Ordering::Greater,
+            (_, SortKey::Missing) => return Ordering::Less,
+            (SortKey::Number(a), SortKey::Number(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (SortKey::Number(_), SortKey::Text(_)) => Ordering::Less,
+            (SortKey::Text(_), SortKey::Number(_)) => Ordering::Greater,
+            (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+        };
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TotalsTable<const N: usize, const S: usize> {
     pub table: Table<N>,
@@ -260,3 +435,50 @@ fn test_array_insert() {
 fn test_array_insert_panic() {
     assert_eq!(array_insert([1, 2, 3], [4], 2), [1, 2, 4, 3, 5]);
 }
+
+#[cfg(test)]
+#[test]
+fn test_heat() {
+    let mut table = Table::new([("Earned Run Average", "ERA")], "", "");
+    for n in [5, 1, 3, 3, 2, 4, 0, 6, 7, 8] {
+        table.push([Pct::<2>::new(n, 1u8).into()]);
+    }
+    table.push(["".into()]);
+
+    table.heat("ERA", false);
+    let heat = table.rows.iter().map(|row| row.heat[0]).collect::<Vec<_>>();
+    assert_eq!(
+        heat,
+        [
+            "heat-2", "heat-5", "heat-3", "heat-3", "heat-4", "heat-3", "heat-5", "heat-2",
+            "heat-1", "heat-1", ""
+        ]
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_sort_paginate() {
+    let mut table = Table::new([("Player", ""), ("Batting Average", "BA")], "", "");
+    for (name, hits, at_bats) in [("b", 1, 4), ("a", 0, 0), ("c", 3, 4), ("d", 1, 4)] {
+        table.push([name.into(), Pct::<3>::new(hits, at_bats as u8).into()]);
+    }
+    let names = |table: &Table<2>| {
+        table
+            .rows
+            .iter()
+            .map(|row| row.data[0].to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let ba = table.column_ignore_case("ba").unwrap();
+    table.sort_by(ba, true);
+    assert_eq!(names(&table), ["c", "b", "d", "a"]);
+    table.sort_by(ba, false);
+    assert_eq!(names(&table), ["b", "d", "c", "a"]);
+    table.sort_by(table.column_ignore_case("player").unwrap(), false);
+    assert_eq!(names(&table), ["a", "b", "c", "d"]);
+
+    assert_eq!(table.paginate(2, 3), 2);
+    assert_eq!(names(&table), ["d"]);
+}
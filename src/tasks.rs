@@ -0,0 +1,168 @@
+//! Supervision for the background tasks. Each run of the start and update tasks is recorded in
+//! sled (when it last started and finished, how long it took, and the last error), and admins can
+//! ask for an update or a full rebuild to run right away instead of waiting for the next loop.
+
+use crate::{metrics, DB};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rocket::tokio::sync::Notify;
+use rocket::tokio::time::sleep;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const TREE: &str = "task_status_v1";
+
+/// Set once the task loop is running, so that triggers aren't accepted when tasks are disabled.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref PENDING: Mutex<Option<Trigger>> = Mutex::new(None);
+    static ref WAKE: Notify = Notify::new();
+    static ref RUNNING: Mutex<Option<Task>> = Mutex::new(None);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Task {
+    Start,
+    Update,
+}
+
+impl Task {
+    pub const ALL: [Task; 2] = [Task::Start, Task::Update];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Task::Start => "start",
+            Task::Update => "update",
+        }
+    }
+
+    fn duration_gauge(self) -> &'static metrics::Gauge {
+        match self {
+            Task::Start => &metrics::START_TASK_DURATION,
+            Task::Update => &metrics::UPDATE_TASK_DURATION,
+        }
+    }
+}
+
+/// What an admin asked to run next. A pending rebuild is never downgraded to an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Trigger {
+    Update,
+    Rebuild,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Status {
+    pub last_start: Option<DateTime<Utc>>,
+    pub last_finish: Option<DateTime<Utc>>,
+    pub last_duration_secs: Option<f64>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub runs: u32,
+    pub failures: u32,
+}
+
+pub fn status(task: Task) -> Result<Status> {
+    Ok(match DB.open_tree(TREE)?.get(task.name())? {
+        Some(value) => serde_json::from_slice(&value)?,
+        None => Status::default(),
+    })
+}
+
+fn write_status(task: Task, status: &Status) -> Result<()> {
+    DB.open_tree(TREE)?
+        .insert(task.name(), serde_json::to_vec(status)?)?;
+    Ok(())
+}
+
+pub fn running() -> Option<Task> {
+    *RUNNING.lock().unwrap()
+}
+
+/// Runs a task, recording its start, finish, duration, and any error. Errors are logged rather
+/// than returned, so that the task loop carries on.
+pub async fn supervise(task: Task, future: impl Future<Output = Result<()>>) {
+    let mut status = log_status(task).unwrap_or_default();
+    status.last_start = Some(Utc::now());
+    log_write(task, &status);
+    *RUNNING.lock().unwrap() = Some(task);
+
+    let start = Instant::now();
+    let result = future.await;
+    let duration = Instant::now() - start;
+
+    *RUNNING.lock().unwrap() = None;
+    task.duration_gauge().set(duration);
+    status.last_finish = Some(Utc::now());
+    status.last_duration_secs = Some(duration.as_secs_f64());
+    status.runs += 1;
+    if let Err(err) = result {
+        log::error!("{} task failed: {:#}", task.name(), err);
+        status.last_error = Some(format!("{:#}", err));
+        status.last_error_at = status.last_finish;
+        status.failures += 1;
+    }
+    log_write(task, &status);
+}
+
+fn log_status(task: Task) -> Option<Status> {
+    status(task)
+        .map_err(|err| log::error!("failed to load {} task status: {:#}", task.name(), err))
+        .ok()
+}
+
+fn log_write(task: Task, status: &Status) {
+    if let Err(err) = write_status(task, status) {
+        log::error!("failed to record {} task status: {:#}", task.name(), err);
+    }
+}
+
+/// Marks the task loop as running; until then, `trigger` refuses requests.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Asks the task loop to run an update or rebuild as soon as the current task finishes. Returns
+/// false if background tasks are disabled.
+pub fn trigger(trigger: Trigger) -> bool {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return false;
+    }
+    let mut pending = PENDING.lock().unwrap();
+    *pending = merge(*pending, trigger);
+    WAKE.notify_one();
+    true
+}
+
+fn merge(pending: Option<Trigger>, trigger: Trigger) -> Option<Trigger> {
+    Some(pending.map_or(trigger, |pending| pending.max(trigger)))
+}
+
+/// Waits for `period` or until triggered, whichever comes first, and returns what to run next. A
+/// trigger that arrived while a task was running wakes this immediately.
+pub async fn wait(period: Duration) -> Trigger {
+    rocket::tokio::select! {
+        _ = sleep(period) => {}
+        _ = WAKE.notified() => {}
+    }
+    PENDING.lock().unwrap().take().unwrap_or(Trigger::Update)
+}
+
+#[cfg(test)]
+#[test]
+fn test_merge() {
+    assert_eq!(merge(None, Trigger::Update), Some(Trigger::Update));
+    assert_eq!(
+        merge(Some(Trigger::Rebuild), Trigger::Update),
+        Some(Trigger::Rebuild)
+    );
+    assert_eq!(
+        merge(Some(Trigger::Update), Trigger::Rebuild),
+        Some(Trigger::Rebuild)
+    );
+}
@@ -17,6 +17,9 @@ pub struct Team {
     #[serde(deserialize_with = "deserialize_emoji")]
     pub emoji: String,
     pub lineup: Vec<Uuid>,
+    /// Teams didn't have stadiums until the Expansion Era
+    #[serde(default)]
+    pub stadium: Option<Uuid>,
 }
 
 fn deserialize_emoji<'de, D>(deserializer: D) -> Result<String, D::Error>
@@ -0,0 +1,43 @@
+use rocket::http::CookieJar;
+
+/// Cookie storing the user's color scheme preference, set by the toggle in the page header.
+pub const COOKIE: &str = "color-scheme";
+
+/// Placeholder in the `<html>` class attribute of every page, replaced with the theme's class.
+pub const PLACEHOLDER: &str = "@THEME@";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    /// No preference; the page follows `prefers-color-scheme`
+    System,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub fn from_cookies(cookies: &CookieJar<'_>) -> Theme {
+        match cookies.get(COOKIE).map(|cookie| cookie.value()) {
+            Some("light") => Theme::Light,
+            Some("dark") => Theme::Dark,
+            _ => Theme::System,
+        }
+    }
+
+    /// The classes to emit on the `<html>` element, padded to the length of `PLACEHOLDER` so they
+    /// can be swapped in place.
+    pub fn class(self) -> &'static str {
+        match self {
+            Theme::System => "system ",
+            Theme::Light => "light  ",
+            Theme::Dark => "dark   ",
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    for theme in [Theme::System, Theme::Light, Theme::Dark] {
+        assert_eq!(theme.class().len(), PLACEHOLDER.len());
+    }
+}
@@ -0,0 +1,71 @@
+//! Tombstones for processed games that the schedule stopped listing, which happens when a game is
+//! wiped upstream or re-run under a new ID.
+//!
+//! A buried game's box score is kept (and shown with a banner saying it was removed), but the game
+//! is taken out of everything built from it: its summary deltas are reversed, and its schedule
+//! entries, daily rows, matchups, decisions, inhabiting records, and milestones are removed (see
+//! `game::bury`). If the schedule lists the game again, it's restored and processed as usual.
+
+use crate::seasons::Season;
+use crate::{game, schedule, DB};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+pub const TREE: &str = "tombstones_v1";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Tombstone {
+    #[serde(flatten)]
+    pub season: Season,
+    pub day: u16,
+    /// When the game was first found missing from the schedule
+    pub removed_at: DateTime<Utc>,
+}
+
+pub fn load(id: Uuid) -> Result<Option<Tombstone>> {
+    Ok(match DB.open_tree(TREE)?.get(id.as_bytes())? {
+        Some(value) => Some(serde_json::from_slice(&value)?),
+        None => None,
+    })
+}
+
+/// Restores buried games that the schedule lists again, so that they're processed as usual. Call
+/// before processing the season's games.
+pub fn restore(season: &Season, scheduled: &[Uuid]) -> Result<()> {
+    let tree = DB.open_tree(TREE)?;
+    for id in scheduled {
+        if let Some(value) = tree.get(id.as_bytes())? {
+            let tombstone: Tombstone = serde_json::from_slice(&value)?;
+            if &tombstone.season == season {
+                log::info!("game {} is scheduled again, restoring it", id);
+                // the game's derived data is already gone, so this only drops the box score
+                game::remove(*id)?;
+                tree.remove(id.as_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Buries processed games in a season that the schedule no longer lists. Call after processing the
+/// season's games. An empty schedule is taken to be an upstream problem rather than a wiped season.
+pub fn sweep(season: &Season, scheduled: &[Uuid]) -> Result<()> {
+    if scheduled.is_empty() {
+        return Ok(());
+    }
+    let scheduled = scheduled.iter().collect::<HashSet<_>>();
+    for (_, entry) in schedule::games(season)? {
+        if !scheduled.contains(&entry.id) {
+            log::warn!(
+                "game {} is no longer in the {} schedule, burying it",
+                entry.id,
+                season
+            );
+            game::bury(entry.id)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! All requests to upstream APIs go through `get`, which throttles requests per host with a token
+//! bucket and coalesces concurrent requests for the same URL into one.
+
+use crate::{metrics, CLIENT};
+use anyhow::{anyhow, Context, Result};
+use rocket::futures::future::{BoxFuture, FutureExt, Shared};
+use rocket::tokio::time::sleep;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+lazy_static::lazy_static! {
+    /// Sustained requests per second to any one host; `BRICKS_UPSTREAM_RATE` overrides.
+    static ref RATE: f64 = env_or("BRICKS_UPSTREAM_RATE", 5.0);
+    /// Requests to a host that can be made at once before throttling starts;
+    /// `BRICKS_UPSTREAM_BURST` overrides.
+    static ref BURST: f64 = env_or("BRICKS_UPSTREAM_BURST", 10.0);
+
+    static ref BUCKETS: Mutex<HashMap<String, Bucket>> = Mutex::new(HashMap::new());
+    static ref IN_FLIGHT: Mutex<HashMap<String, Flight>> = Mutex::new(HashMap::new());
+}
+
+type Flight = Shared<BoxFuture<'static, Result<Arc<Response>, Arc<anyhow::Error>>>>;
+
+fn env_or(name: &str, default: f64) -> f64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|v: &f64| *v > 0.0)
+        .unwrap_or(default)
+}
+
+#[derive(Debug)]
+pub struct Response {
+    /// The `Date` header, if the server sent one
+    pub date: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    pub fn text(&self) -> Result<&str> {
+        Ok(std::str::from_utf8(&self.body)?)
+    }
+}
+
+/// Fetches a URL, waiting for the host's rate limit. If a request for the same URL is already in
+/// flight, waits for that response instead of making another request.
+pub async fn get(url: String) -> Result<Arc<Response>> {
+    let flight = {
+        let mut in_flight = IN_FLIGHT.lock().unwrap();
+        match in_flight.get(&url) {
+            Some(flight) => {
+                metrics::UPSTREAM_COALESCED.inc();
+                flight.clone()
+            }
+            None => {
+                let flight = fetch(url.clone())
+                    .map(|result| result.map(Arc::new).map_err(Arc::new))
+                    .boxed()
+                    .shared();
+                in_flight.insert(url.clone(), flight.clone());
+                flight
+            }
+        }
+    };
+
+    let result = flight.await;
+    {
+        let mut in_flight = IN_FLIGHT.lock().unwrap();
+        if in_flight.get(&url).map(|flight| flight.peek().is_some()) == Some(true) {
+            in_flight.remove(&url);
+        }
+    }
+    result.map_err(|err| anyhow!("{:#}", err))
+}
+
+async fn fetch(url: String) -> Result<Response> {
+    let host = reqwest::Url::parse(&url)?
+        .host_str()
+        .context("upstream URL has no host")?
+        .to_owned();
+    throttle(&host).await;
+
+    let response = CLIENT.get(&url).send().await?;
+    let date = response
+        .headers()
+        .get("date")
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    let body = response.bytes().await?.to_vec();
+    Ok(Response { date, body })
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+async fn throttle(host: &str) {
+    loop {
+        let wait = {
+            let mut buckets = BUCKETS.lock().unwrap();
+            let now = Instant::now();
+            let bucket = buckets.entry(host.to_owned()).or_insert(Bucket {
+                tokens: *BURST,
+                updated: now,
+            });
+            bucket.tokens =
+                (bucket.tokens + (now - bucket.updated).as_secs_f64() * *RATE).min(*BURST);
+            bucket.updated = now;
+            if bucket.tokens >= 1.0 {
+                bucket.tokens -= 1.0;
+                return;
+            }
+            Duration::from_secs_f64((1.0 - bucket.tokens) / *RATE)
+        };
+        metrics::UPSTREAM_THROTTLED.inc();
+        sleep(wait).await;
+    }
+}
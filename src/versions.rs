@@ -0,0 +1,86 @@
+//! Version stamp for game processing fixes that don't need a full rebuild.
+//!
+//! Bumping `DB_VERSION` drops every processed tree and reprocesses every game. When a fix only
+//! alters results in some sims, record the affected sims in `CHANGES` instead: at startup, every
+//! game in the affected seasons is removed and processed again, and the rest of the database is
+//! left alone.
+
+use crate::seasons::Season;
+use crate::DB;
+use anyhow::Result;
+use std::collections::BTreeSet;
+
+/// The sims affected by each change, oldest first; an empty list means every sim. The version is
+/// the number of changes.
+const CHANGES: &[&[&str]] = &[];
+
+const KEY: &str = "version:changes";
+
+/// Which seasons need reprocessing.
+#[derive(Debug, Default)]
+pub struct Stale {
+    all: bool,
+    sims: BTreeSet<&'static str>,
+}
+
+impl Stale {
+    /// Collects the sims affected by changes made since the stamped version.
+    fn since(changes: &[&'static [&'static str]], stamped: u32) -> Stale {
+        let mut stale = Stale::default();
+        for sims in changes.iter().skip(stamped as usize) {
+            if sims.is_empty() {
+                stale.all = true;
+            } else {
+                stale.sims.extend(sims.iter().copied());
+            }
+        }
+        stale
+    }
+
+    pub fn affects(&self, season: &Season) -> bool {
+        self.all || self.sims.contains(season.sim.as_str())
+    }
+}
+
+/// Compares the stamp to the current version. A database without a stamp is taken to be up to
+/// date, since it was built before there was one.
+pub fn stale() -> Result<Stale> {
+    let stamped = match DB.get(KEY)? {
+        Some(value) => u32::from_be_bytes(value.as_ref().try_into()?),
+        None => return Ok(Stale::default()),
+    };
+    if stamped != CHANGES.len() as u32 {
+        log::info!(
+            "processing version {} != {}, reprocessing affected seasons",
+            stamped,
+            CHANGES.len()
+        );
+    }
+    Ok(Stale::since(CHANGES, stamped))
+}
+
+/// Records the database as up to date. Call once every game has been processed.
+pub fn stamp() -> Result<()> {
+    DB.insert(KEY, &(CHANGES.len() as u32).to_be_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    let season = |sim: &str| Season {
+        sim: sim.into(),
+        season: 0,
+    };
+    let changes: &[&[&str]] = &[&[], &["gamma8"], &["gamma9", "gamma10"]];
+
+    let stale = Stale::since(changes, 2);
+    assert!(!stale.affects(&season("gamma8")));
+    assert!(stale.affects(&season("gamma10")));
+
+    let stale = Stale::since(changes, 0);
+    assert!(stale.affects(&season("thisidisstaticyo")));
+
+    let stale = Stale::since(changes, 3);
+    assert!(!stale.affects(&season("gamma10")));
+}
@@ -0,0 +1,82 @@
+use crate::game::Stats;
+use crate::table::{row, Table};
+
+/// Weather names, indexed by the weather ID in game data.
+const NAMES: &[&str] = &[
+    "Void",
+    "Sun 2",
+    "Overcast",
+    "Rainy",
+    "Sandstorm",
+    "Snowy",
+    "Acidic",
+    "Solar Eclipse",
+    "Glitter",
+    "Blooddrain",
+    "Peanuts",
+    "Birds",
+    "Feedback",
+    "Reverb",
+    "Black Hole",
+    "Coffee",
+    "Coffee 2",
+    "Coffee 3s",
+    "Flooding",
+    "Salmon",
+    "Polarity +",
+    "Polarity -",
+    "???",
+    "Sun 90",
+    "Sun .1",
+    "Sum Sun",
+    "Supernova Eclipse",
+    "Black Hole (Black Hole)",
+    "Jazz",
+    "Night",
+];
+
+pub fn name(weather: u16) -> String {
+    match NAMES.get(usize::from(weather)) {
+        Some(name) => (*name).into(),
+        None => format!("Weather {}", weather),
+    }
+}
+
+pub const COLS: usize = 9;
+
+/// Team record and performance in each weather, from per-weather team totals.
+pub fn table(iter: impl Iterator<Item = (u16, Stats)>) -> Table<COLS> {
+    let mut table = Table::new(
+        [
+            ("Weather", ""),
+            ("Games Played", "G"),
+            ("Wins", "W"),
+            ("Losses", "L"),
+            ("Win-Loss Percentage", "W-L%"),
+            ("Runs Scored", "R"),
+            ("Runs Allowed", "RA"),
+            ("Batting Average", "BA"),
+            ("Earned Run Average", "ERA"),
+        ],
+        "text-right",
+        "number",
+    );
+    table.col_class[0] = "text-left";
+    table.sort_method[0] = "none";
+
+    for (weather, stats) in iter {
+        table.push(row![
+            name(weather),
+            stats.games_batted,
+            stats.wins,
+            stats.losses,
+            stats.win_loss_percentage(),
+            stats.runs,
+            stats.runs_allowed(),
+            stats.batting_average(),
+            stats.earned_run_average(),
+        ]);
+    }
+
+    table
+}
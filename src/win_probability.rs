@@ -0,0 +1,170 @@
+//! A simple win expectancy model. Each team's remaining runs are treated as Poisson-distributed,
+//! with the mean built from the season's run expectancy matrix (see `run_expectancy`): the
+//! expected runs for the rest of the current half-inning, plus the expected runs of a fresh
+//! half-inning for every half-inning the team has left to bat.
+
+use crate::run_expectancy::{self, RunExpectancy, STATES};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Plate appearances starting with the bases empty and nobody out (roughly, half-innings) a
+/// season needs before its own matrix is used.
+const MIN_OCCURRENCES: i32 = 100;
+
+/// Average runs scored per half-inning, for seasons without enough games for their own matrix.
+/// Blaseball's run environment runs a little hotter than modern MLB's, so the MLB table below is
+/// scaled to match this.
+const RUNS_PER_HALF_INNING: f64 = 0.55;
+
+/// MLB 2010–2015 RE24 values, indexed as in `run_expectancy::state`, where a half-inning starting
+/// from nothing averages 0.481 runs.
+const MLB_RUN_EXPECTANCY: [f64; STATES] = [
+    0.481, 0.859, 1.100, 1.437, 1.350, 1.784, 1.964, 2.292, //
+    0.254, 0.509, 0.664, 0.884, 0.950, 1.130, 1.376, 1.541, //
+    0.098, 0.224, 0.319, 0.429, 0.353, 0.478, 0.580, 0.752,
+];
+
+const REGULATION_INNINGS: u16 = 9;
+const MAX_RUNS: usize = 40;
+
+/// One point in a game's win probability series, recorded whenever the home team's win
+/// probability changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WinProbability {
+    pub inning: u16,
+    pub top_of_inning: bool,
+    /// Home team win probability, in thousandths
+    pub home: u16,
+}
+
+/// The run expectancy matrix to use for a season, given its league totals: the season's own once
+/// it has enough games, or else the MLB table scaled to `RUNS_PER_HALF_INNING`.
+pub fn matrix(league: &RunExpectancy) -> [f64; STATES] {
+    let start = run_expectancy::state(0, 0).unwrap_or_default();
+    if league.occurrences[start] >= MIN_OCCURRENCES {
+        league.matrix()
+    } else {
+        MLB_RUN_EXPECTANCY.map(|re| re * RUNS_PER_HALF_INNING / MLB_RUN_EXPECTANCY[start])
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Situation<'a> {
+    pub inning: u16,
+    pub top_of_inning: bool,
+    pub outs: u16,
+    /// Occupied bases, as in `run_expectancy::state`
+    pub bases: u8,
+    pub away_runs: u16,
+    pub home_runs: u16,
+    /// See `matrix`
+    pub run_expectancy: &'a [f64; STATES],
+}
+
+impl Situation<'_> {
+    pub fn home_win_probability(&self) -> f64 {
+        let runs_per_half_inning =
+            run_expectancy::state(0, 0).map_or(0.0, |state| self.run_expectancy[state]);
+        let current = run_expectancy::state(self.outs, self.bases)
+            .map_or(0.0, |state| self.run_expectancy[state]);
+        let innings_after = f64::from(REGULATION_INNINGS.saturating_sub(self.inning));
+
+        let (away_mean, home_mean) = if self.top_of_inning {
+            (
+                current + innings_after * runs_per_half_inning,
+                (innings_after + 1.0) * runs_per_half_inning,
+            )
+        } else {
+            if self.inning >= REGULATION_INNINGS && self.home_runs > self.away_runs {
+                return 1.0;
+            }
+            (
+                innings_after * runs_per_half_inning,
+                current + innings_after * runs_per_half_inning,
+            )
+        };
+
+        let away = poisson(away_mean);
+        let home = poisson(home_mean);
+        let mut probability = 0.0;
+        for (a, pa) in away.iter().enumerate() {
+            for (h, ph) in home.iter().enumerate() {
+                let away_total = usize::from(self.away_runs) + a;
+                let home_total = usize::from(self.home_runs) + h;
+                probability += pa
+                    * ph
+                    * match home_total.cmp(&away_total) {
+                        Ordering::Greater => 1.0,
+                        // extra innings are close enough to a coin flip
+                        Ordering::Equal => 0.5,
+                        Ordering::Less => 0.0,
+                    };
+            }
+        }
+        probability
+    }
+
+    pub fn point(&self) -> WinProbability {
+        WinProbability {
+            inning: self.inning,
+            top_of_inning: self.top_of_inning,
+            home: (self.home_win_probability() * 1000.0).round() as u16,
+        }
+    }
+}
+
+fn poisson(mean: f64) -> [f64; MAX_RUNS] {
+    let mut pmf = [0.0; MAX_RUNS];
+    let mut p = (-mean).exp();
+    for (k, value) in pmf.iter_mut().enumerate() {
+        *value = p;
+        p *= mean / (k + 1) as f64;
+    }
+    pmf
+}
+
+/// Formats a win probability series as SVG polyline points, for a chart with a view box of
+/// `0 0 {len - 1} 1000` where the top edge is a certain home win.
+pub fn svg_points(series: &[WinProbability]) -> String {
+    series
+        .iter()
+        .enumerate()
+        .map(|(x, point)| format!("{},{}", x, 1000 - point.home.min(1000)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+#[test]
+fn test() {
+    let run_expectancy = matrix(&RunExpectancy::default());
+    assert!((run_expectancy[0] - RUNS_PER_HALF_INNING).abs() < 1e-9);
+    let start = Situation {
+        inning: 1,
+        top_of_inning: true,
+        outs: 0,
+        bases: 0,
+        away_runs: 0,
+        home_runs: 0,
+        run_expectancy: &run_expectancy,
+    };
+    let p = start.home_win_probability();
+    assert!(p > 0.49 && p < 0.53, "{}", p);
+
+    let late = Situation {
+        inning: 9,
+        top_of_inning: false,
+        outs: 2,
+        bases: 0,
+        away_runs: 3,
+        home_runs: 1,
+        run_expectancy: &run_expectancy,
+    };
+    assert!(late.home_win_probability() < 0.05);
+
+    let walkoff = Situation {
+        home_runs: 4,
+        ..late
+    };
+    assert_eq!(walkoff.home_win_probability(), 1.0);
+}